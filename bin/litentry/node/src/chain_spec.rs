@@ -1,12 +1,21 @@
-use sp_core::{Pair, Public, sr25519};
+use sp_core::{Pair, Public, sr25519, hashing::blake2_256};
 use litentry_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, BabeConfig, Balance, BalancesConfig, GenesisConfig, GrandpaConfig, Hash,
+	ImOnlineConfig, LitentryConfig, SessionConfig, StakerStatus, StakingConfig, SystemConfig,
+	WASM_BINARY, Signature, opaque::SessionKeys,
 };
-use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+#[cfg(not(feature = "governance"))]
+use litentry_runtime::SudoConfig;
+#[cfg(feature = "governance")]
+use litentry_runtime::{CouncilMembershipConfig, DemocracyConfig, TreasuryConfig};
+use sp_consensus_babe::AuthorityId as BabeId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
-use sp_runtime::traits::{Verify, IdentifyAccount};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use sp_runtime::{Perbill, traits::{Verify, IdentifyAccount}};
 use sc_service::ChainType;
+use sc_network::config::MultiaddrWithPeerId;
+use serde::{Deserialize, Serialize};
+use std::{fs::File, path::Path};
 
 // Note this is the URL for the telemetry server
 //const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -30,14 +39,21 @@ pub fn get_account_id_from_seed<TPublic: Public>(seed: &str) -> AccountId where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Helper function to generate an authority key for Aura
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
+/// Helper function to generate the stash, controller and session keys of a validator
+pub fn authority_keys_from_seed(s: &str) -> (AccountId, AccountId, GrandpaId, BabeId, ImOnlineId) {
 	(
-		get_from_seed::<AuraId>(s),
+		get_account_id_from_seed::<sr25519::Public>(&format!("{}//stash", s)),
+		get_account_id_from_seed::<sr25519::Public>(s),
 		get_from_seed::<GrandpaId>(s),
+		get_from_seed::<BabeId>(s),
+		get_from_seed::<ImOnlineId>(s),
 	)
 }
 
+fn session_keys(grandpa: GrandpaId, babe: BabeId, im_online: ImOnlineId) -> SessionKeys {
+	SessionKeys { grandpa, babe, im_online }
+}
+
 pub fn development_config() -> ChainSpec {
 	ChainSpec::from_genesis(
 		"Development",
@@ -99,7 +115,115 @@ pub fn local_testnet_config() -> ChainSpec {
 	)
 }
 
-fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
+/// The keys of a validator, as SS58 addresses.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AuthorityKeys {
+	pub stash: AccountId,
+	pub controller: AccountId,
+	pub grandpa: GrandpaId,
+	pub babe: BabeId,
+	pub im_online: ImOnlineId,
+}
+
+/// The configuration file of a staging chain.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StagingConfig {
+	pub name: String,
+	pub id: String,
+	/// The sudo key, or the only initial council member with the `governance` feature.
+	pub root_key: AccountId,
+	/// Accounts endowed at genesis, besides the stash and controller of each authority.
+	#[serde(default)]
+	pub endowed_accounts: Vec<AccountId>,
+	/// The initial validators, as printed by `litentry generate-keys`.
+	pub authorities: Vec<AuthorityKeys>,
+	#[serde(default)]
+	pub boot_nodes: Vec<MultiaddrWithPeerId>,
+}
+
+/// A live chain whose authorities and accounts are read from the staging configuration file at
+/// `path`.
+pub fn staging_config(path: &Path) -> Result<ChainSpec, String> {
+	let file = File::open(path)
+		.map_err(|e| format!("Error opening staging config {}: {}", path.display(), e))?;
+	let config: StagingConfig = serde_json::from_reader(file)
+		.map_err(|e| format!("Error parsing staging config {}: {}", path.display(), e))?;
+	if config.authorities.is_empty() {
+		return Err(format!("Staging config {} has no authorities", path.display()));
+	}
+
+	let boot_nodes = config.boot_nodes.clone();
+	Ok(ChainSpec::from_genesis(
+		&config.name,
+		&config.id,
+		ChainType::Live,
+		move || {
+			let mut endowed_accounts = config.endowed_accounts.clone();
+			for keys in &config.authorities {
+				for account in vec![&keys.stash, &keys.controller] {
+					if !endowed_accounts.contains(account) {
+						endowed_accounts.push(account.clone());
+					}
+				}
+			}
+			testnet_genesis(
+				config.authorities.iter()
+					.map(|keys| (
+						keys.stash.clone(),
+						keys.controller.clone(),
+						keys.grandpa.clone(),
+						keys.babe.clone(),
+						keys.im_online.clone(),
+					))
+					.collect(),
+				config.root_key.clone(),
+				endowed_accounts,
+				false,
+			)
+		},
+		boot_nodes,
+		None,
+		None,
+		None,
+		None,
+	))
+}
+
+/// The id of the identity every endowed account starts with.
+fn genesis_identity_of(account: &AccountId) -> Hash {
+	let mut seed = b"litentry:identity".to_vec();
+	seed.extend_from_slice(account.as_ref());
+	blake2_256(&seed).into()
+}
+
+/// An identity for each endowed account, and a token from the first identity to each other one.
+fn litentry_genesis(endowed_accounts: &[AccountId]) -> LitentryConfig {
+	let identities: Vec<(AccountId, Hash)> = endowed_accounts.iter()
+		.map(|account| (account.clone(), genesis_identity_of(account)))
+		.collect();
+	let tokens = match identities.split_first() {
+		Some(((_, issuer_identity), others)) => others.iter()
+			.map(|(_, owner_identity)| {
+				let mut seed = issuer_identity.as_ref().to_vec();
+				seed.extend_from_slice(owner_identity.as_ref());
+				let token_id: Hash = blake2_256(&seed).into();
+				(token_id, *owner_identity, *issuer_identity, 0, b"genesis".to_vec(), 0, 1_000_000)
+			})
+			.collect(),
+		None => vec![],
+	};
+	let issuers = identities.first().map(|(_, identity)| *identity).into_iter().collect();
+	LitentryConfig { identities, issuers, tokens }
+}
+
+/// The amount each initial validator bonds from its stash.
+const STASH: Balance = 1 << 50;
+
+/// With the `governance` feature, `root_key` is the only initial council member instead of the
+/// sudo key.
+fn testnet_genesis(initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool) -> GenesisConfig {
@@ -111,14 +235,44 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 		balances: Some(BalancesConfig {
 			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
 		}),
-		aura: Some(AuraConfig {
-			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
+		// The session pallet sets the BABE, GRANDPA and ImOnline authorities from the keys below.
+		babe: Some(BabeConfig {
+			authorities: vec![],
 		}),
 		grandpa: Some(GrandpaConfig {
-			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
+			authorities: vec![],
+		}),
+		im_online: Some(ImOnlineConfig {
+			keys: vec![],
+		}),
+		session: Some(SessionConfig {
+			keys: initial_authorities.iter().map(|x| {
+				(x.0.clone(), x.0.clone(), session_keys(x.2.clone(), x.3.clone(), x.4.clone()))
+			}).collect::<Vec<_>>(),
 		}),
+		staking: Some(StakingConfig {
+			validator_count: initial_authorities.len() as u32 * 2,
+			minimum_validator_count: initial_authorities.len() as u32,
+			stakers: initial_authorities.iter().map(|x| {
+				(x.0.clone(), x.1.clone(), STASH, StakerStatus::Validator)
+			}).collect(),
+			invulnerables: initial_authorities.iter().map(|x| x.0.clone()).collect(),
+			slash_reward_fraction: Perbill::from_percent(10),
+			.. Default::default()
+		}),
+		#[cfg(not(feature = "governance"))]
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		#[cfg(feature = "governance")]
+		membership_Instance1: Some(CouncilMembershipConfig {
+			members: vec![root_key],
+			phantom: Default::default(),
+		}),
+		#[cfg(feature = "governance")]
+		democracy: Some(DemocracyConfig::default()),
+		#[cfg(feature = "governance")]
+		treasury: Some(TreasuryConfig::default()),
+		litentry: Some(litentry_genesis(&endowed_accounts)),
 	}
 }
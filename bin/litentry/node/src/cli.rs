@@ -1,4 +1,4 @@
-use sc_cli::{RunCmd, Subcommand};
+use sc_cli::RunCmd;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -9,3 +9,21 @@ pub struct Cli {
 	#[structopt(flatten)]
 	pub run: RunCmd,
 }
+
+#[derive(Debug, StructOpt)]
+pub enum Subcommand {
+	#[structopt(flatten)]
+	Base(sc_cli::Subcommand),
+
+	/// The custom benchmark subcommmand benchmarking runtime pallets.
+	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
+	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// The custom try-runtime subcommand testing the runtime upgrade against a state snapshot.
+	#[structopt(name = "try-runtime", about = "Test the runtime upgrade against an exported state.")]
+	TryRuntime(try_runtime_cli::TryRuntimeCmd),
+
+	/// Generate the session keys of a validator into the keystore.
+	#[structopt(name = "generate-keys")]
+	GenerateKeys(crate::keys::GenerateKeysCmd),
+}
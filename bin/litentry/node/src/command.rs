@@ -15,13 +15,19 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::chain_spec;
-use crate::cli::Cli;
+use crate::cli::{Cli, Subcommand};
 use crate::service;
 use sc_cli::SubstrateCli;
+use std::path::Path;
+
+/// The staging configuration of `--chain staging`.
+const DEFAULT_STAGING_CONFIG: &str = "staging.json";
+/// Prefix of `--chain staging:<path>`, which reads the staging configuration at `path`.
+const STAGING_PREFIX: &str = "staging:";
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> &'static str {
-		"Substrate Node"
+		"Litentry Node"
 	}
 
 	fn impl_version() -> &'static str {
@@ -37,7 +43,7 @@ impl SubstrateCli for Cli {
 	}
 
 	fn support_url() -> &'static str {
-		"support.anonymous.an"
+		"https://litentry.com"
 	}
 
 	fn copyright_start_year() -> i32 {
@@ -52,6 +58,10 @@ impl SubstrateCli for Cli {
 		Ok(match id {
 			"dev" => Box::new(chain_spec::development_config()),
 			"" | "local" => Box::new(chain_spec::local_testnet_config()),
+			"staging" => Box::new(chain_spec::staging_config(Path::new(DEFAULT_STAGING_CONFIG))?),
+			id if id.starts_with(STAGING_PREFIX) => Box::new(
+				chain_spec::staging_config(Path::new(&id[STAGING_PREFIX.len()..]))?,
+			),
 			path => Box::new(chain_spec::ChainSpec::from_json_file(
 				std::path::PathBuf::from(path),
 			)?),
@@ -64,10 +74,39 @@ pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
 
 	match &cli.subcommand {
-		Some(subcommand) => {
+		Some(Subcommand::Base(subcommand)) => {
 			let runner = cli.create_runner(subcommand)?;
 			runner.run_subcommand(subcommand, |config| Ok(new_full_start!(config).0))
 		}
+		Some(Subcommand::Benchmark(cmd)) => {
+			if cfg!(feature = "runtime-benchmarks") {
+				let runner = cli.create_runner(cmd)?;
+
+				runner.sync_run(|config| cmd.run::<litentry_runtime::opaque::Block, service::Executor>(config))
+			} else {
+				println!("Benchmarking wasn't enabled when building the node. \
+				You can enable it with `--features runtime-benchmarks`.");
+				Ok(())
+			}
+		}
+		Some(Subcommand::TryRuntime(cmd)) => {
+			if cfg!(feature = "try-runtime") {
+				let runner = cli.create_runner(cmd)?;
+
+				runner.sync_run(|config| cmd.run::<litentry_runtime::opaque::Block, service::Executor>(
+					config,
+					litentry_runtime::WASM_BINARY,
+				))
+			} else {
+				println!("Runtime upgrade testing wasn't enabled when building the node. \
+				You can enable it with `--features try-runtime`.");
+				Ok(())
+			}
+		}
+		Some(Subcommand::GenerateKeys(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node(
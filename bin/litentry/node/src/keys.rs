@@ -0,0 +1,72 @@
+//! The `generate-keys` subcommand.
+
+use crate::chain_spec::AuthorityKeys;
+use litentry_runtime::AccountId;
+use sc_cli::{CliConfiguration, Error, KeystoreParams, Result, SharedParams};
+use sc_service::{config::KeystoreConfig, Configuration};
+use sp_core::crypto::{Pair, Ss58Codec};
+use structopt::StructOpt;
+
+/// Generate the session keys of a validator into the node keystore and print them as an
+/// authority of a staging configuration.
+#[derive(Debug, StructOpt, Clone)]
+pub struct GenerateKeysCmd {
+	/// The stash account of the validator, as an SS58 address.
+	#[structopt(long, parse(try_from_str = parse_account))]
+	pub stash: AccountId,
+
+	/// The controller account of the validator, as an SS58 address.
+	#[structopt(long, parse(try_from_str = parse_account))]
+	pub controller: AccountId,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+fn parse_account(address: &str) -> std::result::Result<AccountId, String> {
+	AccountId::from_ss58check(address).map_err(|e| format!("Invalid SS58 address {}: {:?}", address, e))
+}
+
+impl GenerateKeysCmd {
+	/// Run the command
+	pub fn run(&self, config: Configuration) -> Result<()> {
+		let (path, password) = match config.keystore {
+			KeystoreConfig::Path { path, password } => (path, password),
+			KeystoreConfig::InMemory => return Err("The keys need a keystore on disk".into()),
+		};
+		let keystore = sc_keystore::Store::open(path, password)
+			.map_err(|e| Error::Other(format!("Error opening the keystore: {}", e)))?;
+		let keystore = keystore.write();
+		let key_error = |e: sc_keystore::Error| Error::Other(format!("Error generating a key: {}", e));
+
+		let keys = AuthorityKeys {
+			stash: self.stash.clone(),
+			controller: self.controller.clone(),
+			grandpa: keystore.generate::<sp_finality_grandpa::AuthorityPair>().map_err(key_error)?.public(),
+			babe: keystore.generate::<sp_consensus_babe::AuthorityPair>().map_err(key_error)?.public(),
+			im_online: keystore.generate::<pallet_im_online::sr25519::AuthorityPair>()
+				.map_err(key_error)?
+				.public(),
+		};
+
+		let fragment = serde_json::to_string_pretty(&keys)
+			.map_err(|e| Error::Other(format!("Error printing the keys: {}", e)))?;
+		println!("{}", fragment);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for GenerateKeysCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
@@ -5,6 +5,8 @@ mod chain_spec;
 #[macro_use]
 mod service;
 mod cli;
+mod keys;
+mod rpc;
 mod command;
 
 fn main() -> sc_cli::Result<()> {
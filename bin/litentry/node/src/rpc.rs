@@ -0,0 +1,24 @@
+//! A collection of node-specific RPC methods.
+
+use std::{fmt, sync::Arc};
+
+use litentry_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+
+/// Instantiate all full RPC extensions.
+pub fn create_full<C, M>(client: Arc<C>) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: litentry_rpc::LitentryRuntimeApi<Block, AccountId, Hash, Balance, BlockNumber>,
+	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
+	M: jsonrpc_core::Metadata + Default,
+{
+	use litentry_rpc::{Litentry, LitentryApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	io.extend_with(
+		LitentryApi::to_delegate(Litentry::new(client))
+	);
+
+	io
+}
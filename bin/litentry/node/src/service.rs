@@ -9,7 +9,6 @@ use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration,
 use sp_inherents::InherentDataProviders;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
-use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use sc_finality_grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider, StorageAndProofProvider};
 
 // Our native executor instance.
@@ -17,6 +16,7 @@ native_executor_instance!(
 	pub Executor,
 	litentry_runtime::api::dispatch,
 	litentry_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
 );
 
 /// Starts a `ServiceBuilder` for a full service.
@@ -26,8 +26,8 @@ native_executor_instance!(
 macro_rules! new_full_start {
 	($config:expr) => {{
 		use std::sync::Arc;
-		use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-		
+		type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
+
 		let mut import_setup = None;
 		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
 
@@ -47,26 +47,32 @@ macro_rules! new_full_start {
 
 				let (grandpa_block_import, grandpa_link) =
 					sc_finality_grandpa::block_import(client.clone(), &(client.clone() as Arc<_>), select_chain)?;
+				let justification_import = grandpa_block_import.clone();
 
-				let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
-					grandpa_block_import.clone(), client.clone(),
-				);
+				let (block_import, babe_link) = sc_consensus_babe::block_import(
+					sc_consensus_babe::Config::get_or_compute(&*client)?,
+					grandpa_block_import,
+					client.clone(),
+				)?;
 
 				let spawner = |future| spawn_task_handle.spawn_blocking("import-queue-worker", future);
 
-				let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
-					sc_consensus_aura::slot_duration(&*client)?,
-					aura_block_import,
-					Some(Box::new(grandpa_block_import.clone())),
+				let import_queue = sc_consensus_babe::import_queue(
+					babe_link.clone(),
+					block_import.clone(),
+					Some(Box::new(justification_import)),
 					None,
 					client,
 					inherent_data_providers.clone(),
 					spawner,
 				)?;
 
-				import_setup = Some((grandpa_block_import, grandpa_link));
+				import_setup = Some((block_import, grandpa_link, babe_link));
 
 				Ok(import_queue)
+			})?
+			.with_rpc_extensions(|builder| -> std::result::Result<RpcExtension, _> {
+				Ok(crate::rpc::create_full(builder.client().clone()))
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
@@ -82,7 +88,7 @@ pub fn new_full(config: Configuration) -> Result<impl AbstractService, ServiceEr
 
 	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config);
 
-	let (block_import, grandpa_link) =
+	let (block_import, grandpa_link, babe_link) =
 		import_setup.take()
 			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
@@ -105,22 +111,24 @@ pub fn new_full(config: Configuration) -> Result<impl AbstractService, ServiceEr
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
-			sc_consensus_aura::slot_duration(&*client)?,
+		let babe_config = sc_consensus_babe::BabeParams {
+			keystore: service.keystore(),
 			client,
 			select_chain,
+			env: proposer,
 			block_import,
-			proposer,
-			service.network(),
-			inherent_data_providers.clone(),
+			sync_oracle: service.network(),
+			inherent_data_providers: inherent_data_providers.clone(),
 			force_authoring,
-			service.keystore(),
+			babe_link,
 			can_author_with,
-		)?;
+		};
 
-		// the AURA authoring task is considered essential, i.e. if it
+		let babe = sc_consensus_babe::start_babe(babe_config)?;
+
+		// the BABE authoring task is considered essential, i.e. if it
 		// fails we take down the service with it.
-		service.spawn_essential_task("aura", aura);
+		service.spawn_essential_task("babe-proposer", babe);
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -209,11 +217,17 @@ pub fn new_light(config: Configuration) -> Result<impl AbstractService, ServiceE
 			let finality_proof_request_builder =
 				finality_proof_import.create_finality_proof_request_builder();
 
+			let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+				sc_consensus_babe::Config::get_or_compute(&*client)?,
+				grandpa_block_import,
+				client.clone(),
+			)?;
+
 			let spawner = |future| spawn_task_handle.spawn_blocking("import-queue-worker", future);
 
-			let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
-				sc_consensus_aura::slot_duration(&*client)?,
-				grandpa_block_import,
+			let import_queue = sc_consensus_babe::import_queue(
+				babe_link,
+				babe_block_import,
 				None,
 				Some(Box::new(finality_proof_import)),
 				client,
@@ -0,0 +1,71 @@
+//! Runtime API definition required by the Litentry RPC extensions.
+//!
+//! This API should be imported and implemented by the runtime,
+//! of a node that wants to use the custom RPC extension
+//! adding identity and token queries.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// An authorized token together with the identities it links.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TokenDetails<Hash, Balance, BlockNumber> {
+	/// The token id.
+	pub id: Hash,
+	/// The identity currently holding the token.
+	pub owner_identity: Hash,
+	/// The identity that issued the token.
+	pub issuer_identity: Hash,
+	/// The cost of the token.
+	pub cost: Balance,
+	/// The token payload.
+	pub data: Vec<u8>,
+	/// The datatype of the payload.
+	pub datatype: u64,
+	/// The first block at which the token is no longer valid.
+	pub expired: BlockNumber,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The API to query Litentry identities and tokens.
+	#[api_version(3)]
+	pub trait LitentryApi<AccountId, Hash, Balance, BlockNumber> where
+		AccountId: Codec,
+		Hash: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The identities owned by `account`.
+		fn identities_of(account: AccountId) -> Vec<Hash>;
+
+		/// At most `count` tokens held by `identity`, starting at index `start`.
+		fn tokens_of_identity(identity: Hash, start: u64, count: u64) -> Vec<Hash>;
+
+		/// At most `count` tokens issued by `identity`, starting at index `start`.
+		fn tokens_issued_by(identity: Hash, start: u64, count: u64) -> Vec<Hash>;
+
+		/// The details of `token`, or `None` if it does not exist.
+		fn token_details(token: Hash) -> Option<TokenDetails<Hash, Balance, BlockNumber>>;
+
+		/// Whether `proof` shows that `identity` committed to the attribute `name` with `value`,
+		/// salted with `salt`. The proof is the list of sibling hashes from the attribute leaf
+		/// up to the attributes root of the identity.
+		fn verify_attribute(
+			identity: Hash,
+			name: Vec<u8>,
+			value: Vec<u8>,
+			salt: Vec<u8>,
+			proof: Vec<Hash>,
+		) -> bool;
+
+		/// The id of the next token `issuer` issues, if its payload is `data`.
+		fn next_token_id(issuer: Hash, data: Vec<u8>) -> Hash;
+	}
+}
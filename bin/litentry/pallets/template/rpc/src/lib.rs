@@ -0,0 +1,221 @@
+//! Node-specific RPC methods for querying Litentry identities and tokens.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use self::gen_client::Client as LitentryClient;
+pub use litentry_rpc_runtime_api::{
+	self as runtime_api, LitentryApi as LitentryRuntimeApi, TokenDetails,
+};
+
+const RUNTIME_ERROR: i64 = 1;
+
+/// The maximum number of tokens returned by a single paged query.
+pub const MAX_PAGE_SIZE: u64 = 1_000;
+
+/// Litentry RPC methods.
+#[rpc]
+pub trait LitentryApi<BlockHash, AccountId, Hash, Balance, BlockNumber> {
+	/// Returns the identities owned by `account`.
+	#[rpc(name = "litentry_identitiesOf")]
+	fn identities_of(&self, account: AccountId, at: Option<BlockHash>) -> Result<Vec<Hash>>;
+
+	/// Returns up to `count` tokens held by `identity`, starting at index `start`.
+	///
+	/// `count` may not exceed `MAX_PAGE_SIZE`.
+	#[rpc(name = "litentry_tokensOfIdentity")]
+	fn tokens_of_identity(
+		&self,
+		identity: Hash,
+		start: u64,
+		count: u64,
+		at: Option<BlockHash>,
+	) -> Result<Vec<Hash>>;
+
+	/// Returns up to `count` tokens issued by `identity`, starting at index `start`.
+	///
+	/// `count` may not exceed `MAX_PAGE_SIZE`.
+	#[rpc(name = "litentry_tokensIssuedBy")]
+	fn tokens_issued_by(
+		&self,
+		identity: Hash,
+		start: u64,
+		count: u64,
+		at: Option<BlockHash>,
+	) -> Result<Vec<Hash>>;
+
+	/// Returns the details of `token`, or `None` if it does not exist.
+	#[rpc(name = "litentry_tokenDetails")]
+	fn token_details(
+		&self,
+		token: Hash,
+		at: Option<BlockHash>,
+	) -> Result<Option<TokenDetails<Hash, Balance, BlockNumber>>>;
+
+	/// Returns whether `proof` shows that `identity` committed to the attribute `name` with
+	/// `value`, salted with `salt`.
+	#[rpc(name = "litentry_verifyAttribute")]
+	fn verify_attribute(
+		&self,
+		identity: Hash,
+		name: Bytes,
+		value: Bytes,
+		salt: Bytes,
+		proof: Vec<Hash>,
+		at: Option<BlockHash>,
+	) -> Result<bool>;
+
+	/// Returns the id of the next token `issuer` issues, if its payload is `data`.
+	#[rpc(name = "litentry_nextTokenId")]
+	fn next_token_id(&self, issuer: Hash, data: Bytes, at: Option<BlockHash>) -> Result<Hash>;
+}
+
+/// An implementation of Litentry specific RPC methods.
+pub struct Litentry<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Litentry<C, B> {
+	/// Create new `Litentry` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Litentry {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block, AccountId, Hash, Balance, BlockNumber>
+	LitentryApi<<Block as BlockT>::Hash, AccountId, Hash, Balance, BlockNumber>
+	for Litentry<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: LitentryRuntimeApi<Block, AccountId, Hash, Balance, BlockNumber>,
+	AccountId: Codec,
+	Hash: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	fn identities_of(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<Hash>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.identities_of(&at, account).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn tokens_of_identity(
+		&self,
+		identity: Hash,
+		start: u64,
+		count: u64,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<Hash>> {
+		check_page_size(count)?;
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.tokens_of_identity(&at, identity, start, count).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn tokens_issued_by(
+		&self,
+		identity: Hash,
+		start: u64,
+		count: u64,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<Hash>> {
+		check_page_size(count)?;
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.tokens_issued_by(&at, identity, start, count).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn token_details(
+		&self,
+		token: Hash,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<TokenDetails<Hash, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.token_details(&at, token).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn verify_attribute(
+		&self,
+		identity: Hash,
+		name: Bytes,
+		value: Bytes,
+		salt: Bytes,
+		proof: Vec<Hash>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<bool> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.verify_attribute(&at, identity, name.to_vec(), value.to_vec(), salt.to_vec(), proof)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn next_token_id(
+		&self,
+		issuer: Hash,
+		data: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Hash> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.next_token_id(&at, issuer, data.to_vec()).map_err(runtime_error_into_rpc_err)
+	}
+}
+
+/// Rejects pages larger than `MAX_PAGE_SIZE`.
+fn check_page_size(count: u64) -> Result<()> {
+	if count > MAX_PAGE_SIZE {
+		return Err(Error {
+			code: ErrorCode::InvalidParams,
+			message: format!(
+				"Requested page size is greater than maximum allowed: {} > {}",
+				count, MAX_PAGE_SIZE
+			),
+			data: None,
+		});
+	}
+	Ok(())
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
@@ -0,0 +1,391 @@
+//! Litentry pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks};
+use frame_support::{dispatch::Dispatchable, traits::Currency};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+use sp_std::convert::TryFrom;
+
+use crate::Module as Litentry;
+
+const SEED: u32 = 0;
+/// The largest number of identities or tokens already held when a call is benchmarked.
+const MAX_EXISTING: u32 = 1000;
+/// The largest number of external accounts linked to an identity.
+const MAX_LINKED: u32 = 100;
+
+// Support Functions
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+    let who: T::AccountId = account(name, index, SEED);
+    let _ = <pallet_balances::Module<T>>::make_free_balance_be(
+        &who,
+        T::Balance::max_value() / 2u32.into(),
+    );
+    who
+}
+
+// Register an identity with a deterministic id for `owner`.
+fn register<T: Trait>(owner: &T::AccountId, index: u32) -> Result<T::Hash, &'static str> {
+    let identity_id = T::Hashing::hash_of(&(b"identity", index));
+    Litentry::<T>::register_identity_with_id(RawOrigin::Signed(owner.clone()).into(), identity_id)?;
+    Ok(identity_id)
+}
+
+// Make `identity_id` of `owner` an issuer with a datatype accepting the largest payloads.
+fn make_issuer<T: Trait>(owner: &T::AccountId, identity_id: T::Hash) -> Result<(), &'static str> {
+    // Registered directly, as the runtime may require a verification the benchmark cannot get.
+    let bond = T::IssuerBond::get();
+    <pallet_balances::Module<T>>::reserve(owner, bond)?;
+    <Issuers<T>>::insert(
+        identity_id,
+        IssuerInfo { account: owner.clone(), bond, issued: 0, slashes: 0 },
+    );
+    Litentry::<T>::register_datatype(
+        RawOrigin::Signed(owner.clone()).into(),
+        identity_id,
+        0,
+        b"benchmark".to_vec(),
+        T::MaxPayloadLength::get(),
+    )?;
+    Ok(())
+}
+
+// Link `count` external accounts to `identity_id`. Written directly, as they need no signature.
+fn link<T: Trait>(identity_id: T::Hash, count: u32) -> Result<(), &'static str> {
+    for index in 0..count {
+        let account = ExternalAccount::Ethereum(H160::from_low_u64_be(index as u64));
+        LinkedAccounts::<T>::insert(&identity_id, &account)?;
+        <ExternalAccountIdentity<T>>::insert(&account, identity_id);
+    }
+    Ok(())
+}
+
+// An issuer identity with a datatype accepting the largest payloads, and a receiver identity
+// already holding `existing` tokens of it.
+fn setup_tokens<T: Trait>(
+    existing: u32,
+) -> Result<(T::AccountId, T::Hash, T::AccountId, T::Hash), &'static str> {
+    let issuer = funded_account::<T>("issuer", 0);
+    let issuer_identity = register::<T>(&issuer, 0)?;
+    let receiver = funded_account::<T>("receiver", 0);
+    let receiver_identity = register::<T>(&receiver, 1)?;
+    make_issuer::<T>(&issuer, issuer_identity)?;
+    for _ in 0..existing {
+        issue::<T>(&issuer, issuer_identity, receiver_identity, 0)?;
+    }
+    Ok((issuer, issuer_identity, receiver, receiver_identity))
+}
+
+// A block far enough ahead for tokens to expire at, `offset` blocks apart so that the tokens
+// of a benchmark do not fill an expiry bucket.
+fn expiry<T: Trait>(offset: u32) -> T::BlockNumber {
+    <frame_system::Module<T>>::block_number() + 1000u32.into() + offset.into()
+}
+
+// Issue a token with a payload of `payload_len` bytes and return its id.
+fn issue<T: Trait>(
+    issuer: &T::AccountId,
+    issuer_identity: T::Hash,
+    receiver_identity: T::Hash,
+    payload_len: u32,
+) -> Result<T::Hash, &'static str> {
+    let expired = expiry::<T>(IssuedTokens::<T>::len(&issuer_identity) as u32);
+    let data = BoundedVec::try_from(vec![0; payload_len as usize])
+        .map_err(|_| "payload exceeds MaxPayloadLength")?;
+    Litentry::<T>::issue_token(
+        RawOrigin::Signed(issuer.clone()).into(),
+        receiver_identity,
+        issuer_identity,
+        1000u32.into(),
+        data,
+        0,
+        expired,
+    )?;
+    let position = IssuedTokens::<T>::len(&issuer_identity) - 1;
+    IssuedTokens::<T>::get(&issuer_identity, position).ok_or("token was not issued")
+}
+
+benchmarks! {
+    _ { }
+
+    register_identity {
+        let i in 0 .. MAX_EXISTING;
+        let caller = funded_account::<T>("caller", 0);
+        for index in 0..i {
+            register::<T>(&caller, index)?;
+        }
+    }: _(RawOrigin::Signed(caller))
+
+    register_identity_with_id {
+        let i in 0 .. MAX_EXISTING;
+        let caller = funded_account::<T>("caller", 0);
+        for index in 0..i {
+            register::<T>(&caller, index)?;
+        }
+        let identity_id = T::Hashing::hash_of(&(b"identity", i));
+    }: _(RawOrigin::Signed(caller), identity_id)
+
+    issue_token {
+        let i in 0 .. MAX_EXISTING;
+        let p in 0 .. T::MaxPayloadLength::get();
+        let (issuer, issuer_identity, _, receiver_identity) = setup_tokens::<T>(i)?;
+        let expired = expiry::<T>(i);
+        let data = BoundedVec::try_from(vec![0; p as usize])
+            .map_err(|_| "payload exceeds MaxPayloadLength")?;
+    }: _(
+        RawOrigin::Signed(issuer),
+        receiver_identity,
+        issuer_identity,
+        1000u32.into(),
+        data,
+        0,
+        expired
+    )
+
+    transfer_token {
+        let i in 1 .. MAX_EXISTING;
+        let (_, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(i)?;
+        // The first token is swapped with the last one of the holder's set.
+        let token_id = OwnedTokens::<T>::get(&receiver_identity, 0).ok_or("no token to transfer")?;
+    }: _(RawOrigin::Signed(receiver), issuer_identity, token_id)
+
+    issue_tokens_batch {
+        let n in 1 .. T::MaxBatchSize::get();
+        // The payload length of the whole batch, all in its first token.
+        let p in 0 .. T::MaxPayloadLength::get();
+        let (issuer, issuer_identity, _, receiver_identity) = setup_tokens::<T>(0)?;
+        let mut tokens: Vec<_> = (0..n)
+            .map(|index| TokenIssuance {
+                to: receiver_identity,
+                cost: 1000u32.into(),
+                data: vec![],
+                datatype: 0,
+                expired: expiry::<T>(index),
+            })
+            .collect();
+        tokens[0].data = vec![0; p as usize];
+        let tokens = BoundedVec::try_from(tokens).map_err(|_| "batch exceeds MaxBatchSize")?;
+    }: _(RawOrigin::Signed(issuer), issuer_identity, tokens)
+
+    register_datatype {
+        let n in 0 .. T::MaxDataTypeNameLength::get();
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        let name = BoundedVec::try_from(vec![b'a'; n as usize])
+            .map_err(|_| "name exceeds MaxDataTypeNameLength")?;
+    }: _(
+        RawOrigin::Signed(caller),
+        identity_id,
+        0,
+        name,
+        T::MaxPayloadLength::get()
+    )
+
+    transfer_tokens_batch {
+        let n in 1 .. T::MaxBatchSize::get();
+        let (_, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(n)?;
+        let transfers = Litentry::<T>::tokens_of_identity(receiver_identity, 0, n.into())
+            .into_iter()
+            .map(|token_id| (issuer_identity, token_id))
+            .collect::<Vec<_>>();
+        let transfers = BoundedVec::try_from(transfers).map_err(|_| "batch exceeds MaxBatchSize")?;
+    }: _(RawOrigin::Signed(receiver), transfers)
+
+    accept_token {
+        let (issuer, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(0)?;
+        let token_id = issue::<T>(&issuer, issuer_identity, receiver_identity, 0)?;
+    }: _(RawOrigin::Signed(receiver), token_id)
+
+    request_authentication {
+        let u in 0 .. T::MaxUrlLength::get();
+        let (issuer, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(0)?;
+        // The cost of the token is paid on its first request.
+        let token_id = issue::<T>(&issuer, issuer_identity, receiver_identity, 0)?;
+        Litentry::<T>::accept_token(RawOrigin::Signed(receiver.clone()).into(), token_id)?;
+        // The quota of the token is spent too.
+        Litentry::<T>::set_token_quota(
+            RawOrigin::Signed(issuer).into(),
+            token_id,
+            Some((u32::max_value(), 1000u32.into())),
+        )?;
+        let url = BoundedVec::try_from(vec![b'a'; u as usize]).map_err(|_| "URL exceeds MaxUrlLength")?;
+    }: _(RawOrigin::Signed(receiver), token_id, url)
+
+    set_token_quota {
+        let (issuer, issuer_identity, _, receiver_identity) = setup_tokens::<T>(0)?;
+        let token_id = issue::<T>(&issuer, issuer_identity, receiver_identity, 0)?;
+        let period: T::BlockNumber = 1000u32.into();
+    }: _(RawOrigin::Signed(issuer), token_id, Some((u32::max_value(), period)))
+
+    submit_authentication_response {
+        let (issuer, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(0)?;
+        let token_id = issue::<T>(&issuer, issuer_identity, receiver_identity, 0)?;
+        Litentry::<T>::accept_token(RawOrigin::Signed(receiver.clone()).into(), token_id)?;
+        let url = BoundedVec::try_from(vec![b'a'; T::MaxUrlLength::get() as usize])
+            .map_err(|_| "URL exceeds MaxUrlLength")?;
+        Litentry::<T>::request_authentication(RawOrigin::Signed(receiver).into(), token_id, url)?;
+        // The sender is the last of the largest set of authenticators.
+        let authenticators: Vec<T::AccountId> = (0..T::MaxAuthenticators::get())
+            .map(|index| account("authenticator", index, SEED))
+            .collect();
+        let authenticator = authenticators[authenticators.len() - 1].clone();
+        <Authenticators<T>>::put(authenticators);
+    }: _(RawOrigin::Signed(authenticator), token_id, 200)
+
+    set_authenticators {
+        let a in 0 .. T::MaxAuthenticators::get();
+        let authenticators: Vec<T::AccountId> = (0..a)
+            .map(|index| account("authenticator", index, SEED))
+            .collect();
+        let authenticators = BoundedVec::try_from(authenticators)
+            .map_err(|_| "authenticators exceed MaxAuthenticators")?;
+        let origin = T::AuthenticatorOrigin::successful_origin();
+        let call = Call::<T>::set_authenticators(authenticators);
+    }: { call.dispatch(origin)? }
+
+    recall_token {
+        let (issuer, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(0)?;
+        let payload_len = T::MaxPayloadLength::get();
+        let token_id = issue::<T>(&issuer, issuer_identity, receiver_identity, payload_len)?;
+        // Its cost is returned and its quota removed too.
+        Litentry::<T>::accept_token(RawOrigin::Signed(receiver).into(), token_id)?;
+        Litentry::<T>::set_token_quota(
+            RawOrigin::Signed(issuer.clone()).into(),
+            token_id,
+            Some((u32::max_value(), 1000u32.into())),
+        )?;
+    }: _(RawOrigin::Signed(issuer), token_id)
+
+    recall_all_identity_token {
+        let t in 0 .. MAX_EXISTING;
+        let (issuer, issuer_identity, _, _) = setup_tokens::<T>(t)?;
+    }: _(RawOrigin::Signed(issuer), issuer_identity, t)
+
+    transfer_identity {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        let delegate: T::AccountId = account("delegate", 0, SEED);
+        Litentry::<T>::add_delegate(RawOrigin::Signed(caller.clone()).into(), identity_id, delegate)?;
+        let receiver: T::AccountId = account("receiver", 0, SEED);
+    }: _(RawOrigin::Signed(caller), identity_id, receiver)
+
+    add_delegate {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        let delegate: T::AccountId = account("delegate", 0, SEED);
+    }: _(RawOrigin::Signed(caller), identity_id, delegate)
+
+    remove_delegate {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        let delegate: T::AccountId = account("delegate", 0, SEED);
+        Litentry::<T>::add_delegate(
+            RawOrigin::Signed(caller.clone()).into(),
+            identity_id,
+            delegate.clone(),
+        )?;
+    }: _(RawOrigin::Signed(caller), identity_id, delegate)
+
+    destroy_identity {
+        let i in 0 .. MAX_EXISTING;
+        let o in 0 .. MAX_EXISTING;
+        let l in 0 .. MAX_LINKED;
+        let (issuer, issuer_identity, receiver, receiver_identity) = setup_tokens::<T>(i)?;
+        // The identity also holds tokens of another issuer.
+        make_issuer::<T>(&receiver, receiver_identity)?;
+        for _ in 0..o {
+            issue::<T>(&receiver, receiver_identity, issuer_identity, 0)?;
+        }
+        link::<T>(issuer_identity, l)?;
+    }: _(RawOrigin::Signed(issuer), issuer_identity, i, o, l)
+
+    link_account {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        // The runtime can only sign with the sr25519 and ed25519 keys of the keystore.
+        let public = sp_io::crypto::sr25519_generate(KEY_TYPE, None);
+        let message = Litentry::<T>::link_message(identity_id, Litentry::<T>::link_nonce(identity_id));
+        let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &public, &message)
+            .ok_or("the keystore cannot sign the link message")?;
+    }: _(
+        RawOrigin::Signed(caller),
+        identity_id,
+        ExternalAccount::Sr25519(public),
+        ExternalSignature::Sr25519(signature)
+    )
+
+    unlink {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        link::<T>(identity_id, 1)?;
+        let account = ExternalAccount::Ethereum(H160::from_low_u64_be(0));
+    }: _(RawOrigin::Signed(caller), identity_id, account)
+
+    register_issuer {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        T::IssuerVerifier::set_verified(&caller);
+    }: _(RawOrigin::Signed(caller), identity_id)
+
+    unregister_issuer {
+        let (issuer, issuer_identity, _, _) = setup_tokens::<T>(0)?;
+    }: _(RawOrigin::Signed(issuer), issuer_identity)
+
+    slash_issuer {
+        let (_, issuer_identity, _, _) = setup_tokens::<T>(0)?;
+        let origin = T::SlashOrigin::successful_origin();
+        // Slashing the whole bond unregisters the issuer too.
+        let call = Call::<T>::slash_issuer(issuer_identity, T::IssuerBond::get());
+    }: { call.dispatch(origin)? }
+
+    set_attributes_root {
+        let caller = funded_account::<T>("caller", 0);
+        let identity_id = register::<T>(&caller, 0)?;
+        let attributes_root = T::Hashing::hash(b"attributes");
+    }: _(RawOrigin::Signed(caller), identity_id, attributes_root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{ExtBuilder, Test};
+    use frame_support::assert_ok;
+    use sp_core::{testing::KeyStore, traits::KeystoreExt};
+
+    #[test]
+    fn test_benchmarks() {
+        let mut ext = ExtBuilder::build();
+        // `link_account` signs with a key of the keystore.
+        ext.register_extension(KeystoreExt(KeyStore::new()));
+        ext.execute_with(|| {
+            assert_ok!(test_benchmark_register_identity::<Test>());
+            assert_ok!(test_benchmark_register_identity_with_id::<Test>());
+            assert_ok!(test_benchmark_issue_token::<Test>());
+            assert_ok!(test_benchmark_transfer_token::<Test>());
+            assert_ok!(test_benchmark_issue_tokens_batch::<Test>());
+            assert_ok!(test_benchmark_register_datatype::<Test>());
+            assert_ok!(test_benchmark_transfer_tokens_batch::<Test>());
+            assert_ok!(test_benchmark_accept_token::<Test>());
+            assert_ok!(test_benchmark_request_authentication::<Test>());
+            assert_ok!(test_benchmark_set_token_quota::<Test>());
+            assert_ok!(test_benchmark_submit_authentication_response::<Test>());
+            assert_ok!(test_benchmark_set_authenticators::<Test>());
+            assert_ok!(test_benchmark_recall_token::<Test>());
+            assert_ok!(test_benchmark_recall_all_identity_token::<Test>());
+            assert_ok!(test_benchmark_transfer_identity::<Test>());
+            assert_ok!(test_benchmark_add_delegate::<Test>());
+            assert_ok!(test_benchmark_remove_delegate::<Test>());
+            assert_ok!(test_benchmark_destroy_identity::<Test>());
+            assert_ok!(test_benchmark_link_account::<Test>());
+            assert_ok!(test_benchmark_unlink::<Test>());
+            assert_ok!(test_benchmark_register_issuer::<Test>());
+            assert_ok!(test_benchmark_unregister_issuer::<Test>());
+            assert_ok!(test_benchmark_slash_issuer::<Test>());
+            assert_ok!(test_benchmark_set_attributes_root::<Test>());
+        });
+    }
+}
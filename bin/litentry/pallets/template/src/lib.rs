@@ -1,47 +1,273 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
-use frame_support::sp_runtime::{traits::Hash, RuntimeDebug};
-use frame_support::traits::Randomness;
+use frame_support::sp_runtime::{
+    offchain::{http, storage::StorageValueRef, Duration},
+    traits::{Hash, SaturatedConversion, Saturating, Zero},
+    RuntimeDebug,
+};
+use frame_support::traits::{
+    BalanceStatus, EnsureOrigin, GetStorageVersion, OnUnbalanced, ReservableCurrency, StorageVersion,
+};
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
-    StorageMap, StorageValue,
+    debug, decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult},
+    ensure,
+    storage::migration::{
+        get_storage_value, remove_storage_prefix, take_storage_item, take_storage_value,
+        StorageKeyIterator,
+    },
+    traits::Get,
+    transactional,
+    weights::Weight,
+    Blake2_128Concat, BoundedVec, IterableStorageMap, StorageDoubleMap, StorageHasher, StorageMap,
+    StorageValue,
+};
+use frame_system::{
+    self as system, ensure_signed,
+    offchain::{AppCrypto, CreateSignedTransaction, ForAny, SendSignedTransaction, SignMessage, Signer},
 };
-use frame_system::{self as system, ensure_signed};
+use litentry_utils::{merkle, EnumerableSet, EnumerableValueSet};
 use pallet_balances;
-use sp_std::prelude::*;
-// use litentry_weights::Linear;
+use sp_core::{crypto::KeyTypeId, ecdsa, ed25519, sr25519, H160};
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+pub use weights::WeightInfo;
 
+mod benchmarking;
 #[cfg(test)]
 mod mock;
+pub mod weights;
 
 // mod claim;
 #[cfg(test)]
 mod tests;
 
+/// Key type of the offchain worker accounts that answer authentication requests.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"lit!");
+
+/// The maximum number of authentication requests an offchain worker run handles.
+const MAX_AUTHENTICATIONS_PER_RUN: usize = 8;
+
+/// Tags the messages external accounts sign to be linked, so that they mean nothing elsewhere.
+const LINK_MESSAGE_TAG: &[u8] = b"litentry:link_account";
+
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        MultiSignature, MultiSigner,
+    };
+    app_crypto!(sr25519, KEY_TYPE);
+
+    /// Authenticator key for runtimes signing with `MultiSignature`.
+    pub struct AuthenticatorId;
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for AuthenticatorId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
 #[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
 pub struct Identity<Hash> {
     id: Hash,
+    /// The Merkle root of the identity's attribute commitments, see `attribute_commitment`.
+    /// The default hash when the identity has no attributes.
+    attributes_root: Hash,
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
-pub struct AuthorizedToken<Hash, Balance> {
-    id: Hash,
-    cost: Balance,
-    data: u64,
-    datatype: u64,
-    expired: u64,
+pub struct AuthorizedToken<Hash, Balance, BlockNumber> {
+    pub id: Hash,
+    /// The price a controller of the holding identity agrees to with `accept_token`.
+    pub cost: Balance,
+    /// The payload, validated against the issuer's `DataTypeSchema` for `datatype`.
+    pub data: Vec<u8>,
+    pub datatype: u64,
+    /// The first block at which the token is no longer valid.
+    pub expired: BlockNumber,
 }
 
-type AuthorizedTokenOf<T> =
-    AuthorizedToken<<T as system::Trait>::Hash, <T as pallet_balances::Trait>::Balance>;
+pub type AuthorizedTokenOf<T> = AuthorizedToken<
+    <T as system::Trait>::Hash,
+    <T as pallet_balances::Trait>::Balance,
+    <T as system::Trait>::BlockNumber,
+>;
 type IdentityOf<T> = Identity<<T as system::Trait>::Hash>;
 
-pub trait Trait: pallet_balances::Trait + system::Trait {
+/// A limit on the authentication requests of a token per period.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct UsageQuota<BlockNumber> {
+    /// The requests allowed per period.
+    pub limit: u32,
+    /// The requests left in the current period.
+    pub remaining: u32,
+    /// The length of a period in blocks.
+    pub period: BlockNumber,
+    /// The block at which `remaining` is reset to `limit`.
+    pub next_reset: BlockNumber,
+}
+
+/// A token to issue in `issue_tokens_batch`, with the arguments of `issue_token`.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub struct TokenIssuance<Hash, Balance, BlockNumber> {
+    /// The identity receiving the token.
+    pub to: Hash,
+    pub cost: Balance,
+    pub data: Vec<u8>,
+    pub datatype: u64,
+    pub expired: BlockNumber,
+}
+
+pub type TokenIssuanceOf<T> = TokenIssuance<
+    <T as system::Trait>::Hash,
+    <T as pallet_balances::Trait>::Balance,
+    <T as system::Trait>::BlockNumber,
+>;
+
+/// An identity registered to issue tokens, with the bond at stake for its conduct.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct IssuerInfo<AccountId, Balance> {
+    /// The account the bond is reserved from.
+    pub account: AccountId,
+    /// The bond, less what was slashed.
+    pub bond: Balance,
+    /// The number of tokens issued since registration.
+    pub issued: u64,
+    /// The number of times the issuer was slashed.
+    pub slashes: u32,
+}
+
+type IssuerInfoOf<T> = IssuerInfo<<T as system::Trait>::AccountId, <T as pallet_balances::Trait>::Balance>;
+
+/// A payload type registered by an issuer identity.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct DataTypeSchema {
+    id: u64,
+    name: Vec<u8>,
+    /// The maximum length in bytes of a token payload of this type.
+    max_size: u32,
+}
+
+/// An account of another chain that can be linked to an identity.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ExternalAccount {
+    Sr25519(sr25519::Public),
+    Ed25519(ed25519::Public),
+    /// An Ethereum address, the last 20 bytes of the keccak-256 hash of the public key.
+    Ethereum(H160),
+}
+
+/// A signature proving control of an `ExternalAccount`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ExternalSignature {
+    Sr25519(sr25519::Signature),
+    Ed25519(ed25519::Signature),
+    /// A recoverable secp256k1 signature of the message, as signed by Ethereum wallets with
+    /// `personal_sign`.
+    Ecdsa(ecdsa::Signature),
+}
+
+/// The token proof an offchain worker signs and posts to the URL of an authentication request.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub struct AuthenticationProof<AccountId, Hash, BlockNumber> {
+    requester: AccountId,
+    token_id: Hash,
+    owner_identity: Hash,
+    issuer_identity: Hash,
+    block_number: BlockNumber,
+}
+
+// The storage layouts stored in the `StorageVersion` item, before the module storage version
+// replaced it. Release `Vn_0_0` is storage version `n`.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+enum Releases {
+    /// Identities and tokens indexed by `Count`/`Array`/`Index` storage triplets.
+    V1_0_0,
+    /// Identities and tokens indexed by `EnumerableSet`s.
+    V2_0_0,
+    /// Identities with a Merkle root of attribute commitments.
+    V3_0_0,
+    /// Token ids derived from the issuer identity, its token count and the payload.
+    V4_0_0,
+}
+
+impl Releases {
+    fn storage_version(self) -> StorageVersion {
+        match self {
+            Releases::V1_0_0 => StorageVersion::new(1),
+            Releases::V2_0_0 => StorageVersion::new(2),
+            Releases::V3_0_0 => StorageVersion::new(3),
+            Releases::V4_0_0 => StorageVersion::new(4),
+        }
+    }
+}
+
+/// Decides which accounts may register their identities as issuers.
+pub trait IssuerVerifier<AccountId> {
+    /// Whether `who` may register its identities as issuers.
+    fn is_verified(who: &AccountId) -> bool;
+
+    /// Make `who` verified, so that `register_issuer` can be benchmarked.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn set_verified(who: &AccountId);
+}
+
+/// Every account may register issuers.
+impl<AccountId> IssuerVerifier<AccountId> for () {
+    fn is_verified(_: &AccountId) -> bool {
+        true
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn set_verified(_: &AccountId) {}
+}
+
+pub trait Trait: pallet_balances::Trait + system::Trait + CreateSignedTransaction<Call<Self>> {
     // Add other types and constants required to configure this pallet.
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type Randomness: Randomness<Self::Hash>;
+    /// The key an offchain worker signs token proofs and responses with.
+    type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+    /// The number of blocks an offchain worker waits before retrying an unanswered request.
+    type GracePeriod: Get<Self::BlockNumber>;
+    /// The number of blocks an authentication request waits for an answer before it is dropped.
+    type AuthenticationTimeout: Get<Self::BlockNumber>;
+    /// The maximum length of a datatype schema name.
+    type MaxDataTypeNameLength: Get<u32>;
+    /// The maximum length of a token payload, whatever its schema allows.
+    type MaxPayloadLength: Get<u32>;
+    /// The base deposit reserved from the issuing account for storing a token.
+    type TokenDepositBase: Get<Self::Balance>;
+    /// The deposit reserved from the issuing account per byte of token payload.
+    type TokenDepositPerByte: Get<Self::Balance>;
+    /// The maximum length of the URL of an authentication request.
+    type MaxUrlLength: Get<u32>;
+    /// The bond reserved from the owner of an identity registering it as an issuer.
+    type IssuerBond: Get<Self::Balance>;
+    /// Handler for the slashed part of issuer bonds.
+    type Slashed: OnUnbalanced<pallet_balances::NegativeImbalance<Self>>;
+    /// Decides which accounts may register issuers. Genesis issuers are not checked.
+    type IssuerVerifier: IssuerVerifier<Self::AccountId>;
+    /// The maximum number of tokens issued or transferred by a batch call.
+    type MaxBatchSize: Get<u32>;
+    /// The maximum number of accounts reporting authentication responses.
+    type MaxAuthenticators: Get<u32>;
+    /// The maximum number of tokens that expire at a block. Tokens may not be issued to expire
+    /// at a block that has as many already.
+    type MaxExpiriesPerBlock: Get<u32>;
+    /// The maximum number of token quotas reset in a block. The rest are reset in the following
+    /// blocks.
+    type MaxQuotaResetsPerBlock: Get<u32>;
+    /// The maximum number of authentication requests dropped in a block for timing out. The
+    /// rest are dropped in the following blocks.
+    type MaxTimeoutsPerBlock: Get<u32>;
+    /// The origin allowed to set the accounts reporting authentication responses.
+    type AuthenticatorOrigin: EnsureOrigin<Self::Origin>;
+    /// The origin allowed to slash issuer bonds.
+    type SlashOrigin: EnsureOrigin<Self::Origin>;
+    /// Weights of the calls of this pallet.
+    type WeightInfo: WeightInfo;
 }
 
 decl_event!(
@@ -49,52 +275,249 @@ decl_event!(
         where
             <T as frame_system::Trait>::AccountId,
             <T as frame_system::Trait>::Hash,
-            //<T as balances::Trait>::Balance
+            <T as pallet_balances::Trait>::Balance,
+            <T as frame_system::Trait>::BlockNumber
         {
             IdentityCreated(AccountId, Hash),
             AuthorizedTokenCreated(Hash, Hash, Hash),
             AuthorizedTokenTransferred(AccountId, Hash, Hash),
             AuthenticaterRequest(AccountId, Hash, Vec<u8>),
+            /// A token was recalled by the owner of its issuer identity. (issuer account, issuer identity, token)
+            AuthorizedTokenRecalled(AccountId, Hash, Hash),
+            /// All tokens issued by an identity were recalled. (issuer account, issuer identity, recalled count)
+            IdentityAuthorizedTokensRecalled(AccountId, Hash, u64),
+            /// A token reached its expiry block and was removed. (token)
+            AuthorizedTokenExpired(Hash),
+            /// A controller of the holder of a token accepted its cost. (payer, token)
+            AuthorizedTokenAccepted(AccountId, Hash),
+            /// The cost of a token was reserved from the account accepting it. (payer, token, cost)
+            AuthorizedTokenCostReserved(AccountId, Hash, Balance),
+            /// The reserved cost of a token was paid to its issuer. (payer, payee, token, cost)
+            AuthorizedTokenCostPaid(AccountId, AccountId, Hash, Balance),
+            /// The reserved cost of a removed token was returned. (payer, token, cost)
+            AuthorizedTokenCostRefunded(AccountId, Hash, Balance),
+            /// An issuer identity registered a payload datatype. (issuer identity, datatype)
+            DataTypeRegistered(Hash, u64),
+            /// A storage deposit was reserved for a token. (depositor, token, deposit)
+            AuthorizedTokenDepositReserved(AccountId, Hash, Balance),
+            /// The storage deposit of a removed token was returned. (depositor, token, deposit)
+            AuthorizedTokenDepositRefunded(AccountId, Hash, Balance),
+            /// The owner of an identity handed it to another account. (old owner, new owner, identity)
+            IdentityTransferred(AccountId, AccountId, Hash),
+            /// An account may now issue and transfer tokens for an identity. (identity, delegate)
+            IdentityDelegateAdded(Hash, AccountId),
+            /// An account may no longer act for an identity. (identity, delegate)
+            IdentityDelegateRemoved(Hash, AccountId),
+            /// An identity was destroyed with the tokens it issued and held. (owner, identity, removed token count)
+            IdentityDestroyed(AccountId, Hash, u64),
+            /// An external account was linked to an identity. (identity, account)
+            AccountLinked(Hash, ExternalAccount),
+            /// An external account was unlinked from an identity. (identity, account)
+            AccountUnlinked(Hash, ExternalAccount),
+            /// The owner of an identity committed to a new set of attributes. (identity, attributes root)
+            IdentityAttributesUpdated(Hash, Hash),
+            /// An identity was registered as an issuer. (bonding account, identity, bond)
+            IssuerRegistered(AccountId, Hash, Balance),
+            /// An identity stopped being an issuer and the rest of its bond was returned. (bonding account, identity, returned bond)
+            IssuerUnregistered(AccountId, Hash, Balance),
+            /// Part of the bond of an issuer was slashed. (identity, slashed amount)
+            IssuerSlashed(Hash, Balance),
+            /// The issuer of a token limited its requests per period. (token, limit, period)
+            TokenQuotaSet(Hash, u32, BlockNumber),
+            /// The issuer of a token lifted its request limit. (token)
+            TokenQuotaRemoved(Hash),
+            /// An authenticator reported the response of a request's URL. (requester, token, HTTP status)
+            AuthenticationResponse(AccountId, Hash, u16),
+            /// An authentication request was not answered in time and was dropped. (requester, token)
+            AuthenticationTimedOut(AccountId, Hash),
             //ACTION: Create a `Transferred` event here
         }
 );
 
 decl_storage! {
-    trait Store for Module<T: Trait> as LitentryStorage {
+    trait Store for Module<T: Trait> as LitentryStorage storage_version(6) {
         // Identity: Declare storage and getter functions here
         Identities get(fn identity): map hasher(blake2_128_concat) T::Hash => IdentityOf<T>;
         IdentityOwner get(fn owner_of_identity): map hasher(blake2_128_concat) T::Hash => Option<T::AccountId>;
 
-        IdentitiesCount get(fn identities_count): u64;
-        IdentitiesArray get(fn identity_by_index): map hasher(blake2_128_concat) u64 => T::Hash;
-        IdentitiesIndex get(fn identity_index): map hasher(blake2_128_concat) T::Hash => u64;
-
-        OwnedIdentitiesCount get(fn identities_count_of_owner): map hasher(blake2_128_concat) T::AccountId => u64;
-        OwnedIdentitiesArray get(fn identity_by_index_of_owner): map hasher(blake2_128_concat) (T::AccountId, u64) => T::Hash;
-        OwnedIdentitiesIndex get(fn identity_index_of_owner): map hasher(blake2_128_concat) T::Hash => u64;
-
         // AuthorizedToken: Declare storage and getter functions here
         AuthorizedTokens get(fn token): map hasher(blake2_128_concat) T::Hash => AuthorizedTokenOf<T>;
         AuthorizedTokenOwner get(fn owner_identity_of_token): map hasher(blake2_128_concat) T::Hash => Option<T::Hash>;
         AuthorizedTokenIdentity get(fn issuer_identity_of_token): map hasher(blake2_128_concat) T::Hash => Option<T::Hash>;
 
-        AuthorizedTokensCount get(fn tokens_count): u64;
-        AuthorizedTokensArray get(fn token_by_index): map hasher(blake2_128_concat) u64 => T::Hash;
-        AuthorizedTokensIndex get(fn token_index): map hasher(blake2_128_concat) T::Hash => u64;
-
-        OwnedAuthorizedTokensCount get(fn owned_tokens_count_of_identity): map hasher(blake2_128_concat) T::Hash => u64;
-        OwnedAuthorizedTokensArray get(fn owned_token_by_index_of_identity): map hasher(blake2_128_concat) (T::Hash, u64) => T::Hash;
-        OwnedAuthorizedTokensIndex get(fn token_index_of_owner_identity): map hasher(blake2_128_concat) T::Hash => u64;
-
-        // Identity to token map
-        IdentityAuthorizedTokensCount get(fn issued_tokens_count_of_identity): map hasher(blake2_128_concat) T::Hash => u64;
-        IdentityAuthorizedTokensArray get(fn issued_token_by_index_of_identity): map hasher(blake2_128_concat) (T::Hash, u64) => T::Hash;
-        IdentityAuthorizedTokensIndex get(fn token_index_of_issuer_identity): map hasher(blake2_128_concat) T::Hash => u64;
+        // Enumerable sets of identities and tokens, see `AllIdentities` and its siblings
+        AllIdentitiesCount: u64;
+        AllIdentitiesItems: map hasher(blake2_128_concat) u64 => Option<T::Hash>;
+        AllIdentitiesIndex: map hasher(blake2_128_concat) T::Hash => Option<u64>;
+
+        OwnerIdentitiesCount: map hasher(blake2_128_concat) T::AccountId => u64;
+        OwnerIdentitiesItems: double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) u64 => Option<T::Hash>;
+        OwnerIdentitiesIndex: double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::Hash => Option<u64>;
+
+        AllTokensCount: u64;
+        AllTokensItems: map hasher(blake2_128_concat) u64 => Option<T::Hash>;
+        AllTokensIndex: map hasher(blake2_128_concat) T::Hash => Option<u64>;
+
+        OwnedTokensCount: map hasher(blake2_128_concat) T::Hash => u64;
+        OwnedTokensItems: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<T::Hash>;
+        OwnedTokensIndex: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::Hash => Option<u64>;
+
+        IssuedTokensCount: map hasher(blake2_128_concat) T::Hash => u64;
+        IssuedTokensItems: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<T::Hash>;
+        IssuedTokensIndex: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::Hash => Option<u64>;
+
+        // Expiry block to the tokens that expire at it, swept in `on_initialize`; the tokens a
+        // block has no room for are moved to the next one
+        AuthorizedTokensExpiringAt get(fn tokens_expiring_at): map hasher(blake2_128_concat) T::BlockNumber => Vec<T::Hash>;
+
+        // Tokens with a cost that no controller of their holder accepted yet
+        TokensAwaitingAcceptance get(fn awaits_acceptance): map hasher(blake2_128_concat) T::Hash => bool;
+        // Token to the account and amount reserved for its cost, until it is used
+        AuthorizedTokenCostReserves get(fn reserved_cost_of_token): map hasher(blake2_128_concat) T::Hash => Option<(T::AccountId, T::Balance)>;
+
+        // Token to the account and amount reserved as its storage deposit
+        AuthorizedTokenDeposits get(fn deposit_of_token): map hasher(blake2_128_concat) T::Hash => Option<(T::AccountId, T::Balance)>;
+
+        // Issuer identity and datatype id to the payload schema
+        DataTypeSchemas get(fn datatype_schema): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<DataTypeSchema>;
+
+        // Token to the limit on its authentication requests; tokens without one are unlimited
+        TokenQuotas get(fn token_quota): map hasher(blake2_128_concat) T::Hash => Option<UsageQuota<T::BlockNumber>>;
+        // Block to the tokens whose quota is reset at it, swept in `on_initialize`
+        TokenQuotaResetsAt get(fn quota_resets_at): map hasher(blake2_128_concat) T::BlockNumber => Vec<T::Hash>;
+
+        // Token to the requester, URL and deadline of its unanswered authentication request
+        PendingAuthentications get(fn pending_authentication): map hasher(blake2_128_concat) T::Hash => Option<(T::AccountId, BoundedVec<u8, T::MaxUrlLength>, T::BlockNumber)>;
+        // Deadline to the tokens whose authentication request times out at it, swept in `on_initialize`
+        AuthenticationsTimingOutAt get(fn authentications_timing_out_at): map hasher(blake2_128_concat) T::BlockNumber => Vec<T::Hash>;
+        // Accounts allowed to report authentication responses
+        Authenticators get(fn authenticators): Vec<T::AccountId>;
+
+        // External accounts linked to each identity, see `LinkedAccounts`
+        LinkedAccountsCount: map hasher(blake2_128_concat) T::Hash => u64;
+        LinkedAccountsItems: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) u64 => Option<ExternalAccount>;
+        LinkedAccountsIndex: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) ExternalAccount => Option<u64>;
+        // External account to the identity it is linked to
+        ExternalAccountIdentity get(fn identity_of_external_account): map hasher(blake2_128_concat) ExternalAccount => Option<T::Hash>;
+        // Identity to the number of accounts ever linked to it, signed in each link message
+        LinkNonce get(fn link_nonce): map hasher(blake2_128_concat) T::Hash => u64;
+
+        // Account to the number of identities it registered with `register_identity`
+        IdentityNonce get(fn identity_nonce): map hasher(blake2_128_concat) T::AccountId => u64;
+        // Issuer identity to the number of tokens it ever issued, kept when the identity is
+        // destroyed so that token ids are not reused
+        IssuedTokenNonce get(fn issued_token_nonce): map hasher(blake2_128_concat) T::Hash => u64;
+
+        // Issuer identity to its bond and reputation
+        Issuers get(fn issuer): map hasher(blake2_128_concat) T::Hash => Option<IssuerInfoOf<T>>;
+
+        // Identity and account to whether the account may issue and transfer tokens for the identity
+        IdentityDelegates get(fn is_delegate): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId => bool;
 
         Nonce: u64;
     }
+    add_extra_genesis {
+        /// Identities as (owner, identity id).
+        config(identities): Vec<(T::AccountId, T::Hash)>;
+        /// Identities registered as issuers. Their bond is reserved from their owner.
+        config(issuers): Vec<T::Hash>;
+        /// Tokens as (token id, owner identity, issuer identity, cost, data, datatype, expiry
+        /// block). Their cost counts as paid and no deposit is reserved for them.
+        config(tokens): Vec<(T::Hash, T::Hash, T::Hash, T::Balance, Vec<u8>, u64, T::BlockNumber)>;
+        build(|config: &GenesisConfig<T>| {
+            for (owner, identity_id) in &config.identities {
+                assert!(
+                    !<IdentityOwner<T>>::contains_key(identity_id),
+                    "Duplicate identity in genesis",
+                );
+                let identity = Identity { id: *identity_id, attributes_root: Default::default() };
+                Module::<T>::insert_identity(owner, *identity_id, identity)
+                    .expect("Identity sets cannot overflow at genesis; qed");
+            }
+            for identity_id in &config.issuers {
+                let owner = <IdentityOwner<T>>::get(identity_id)
+                    .expect("Genesis issuer of an unknown identity");
+                let bond = T::IssuerBond::get();
+                <pallet_balances::Module<T>>::reserve(&owner, bond)
+                    .expect("Genesis issuer cannot pay its bond");
+                <Issuers<T>>::insert(identity_id, IssuerInfo { account: owner, bond, issued: 0, slashes: 0 });
+            }
+            for (token_id, owner_identity, issuer_identity, cost, data, datatype, expired) in &config.tokens {
+                assert!(
+                    !<AuthorizedTokenOwner<T>>::contains_key(token_id),
+                    "Duplicate token in genesis",
+                );
+                assert!(
+                    <IdentityOwner<T>>::contains_key(owner_identity)
+                        && <IdentityOwner<T>>::contains_key(issuer_identity),
+                    "Genesis token of an unknown identity",
+                );
+                let token = AuthorizedToken {
+                    id: *token_id,
+                    cost: *cost,
+                    data: data.clone(),
+                    datatype: *datatype,
+                    expired: *expired,
+                };
+                Module::<T>::insert_token(*owner_identity, *issuer_identity, *token_id, token)
+                    .expect("Token sets cannot overflow at genesis; qed");
+            }
+        });
+    }
 }
 
+/// Every registered identity.
+pub type AllIdentities<T> = EnumerableValueSet<
+    <T as system::Trait>::Hash,
+    AllIdentitiesCount,
+    AllIdentitiesItems<T>,
+    AllIdentitiesIndex<T>,
+>;
+
+/// The identities owned by each account.
+pub type OwnerIdentities<T> = EnumerableSet<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::Hash,
+    OwnerIdentitiesCount<T>,
+    OwnerIdentitiesItems<T>,
+    OwnerIdentitiesIndex<T>,
+>;
+
+/// The external accounts linked to each identity.
+pub type LinkedAccounts<T> = EnumerableSet<
+    <T as system::Trait>::Hash,
+    ExternalAccount,
+    LinkedAccountsCount<T>,
+    LinkedAccountsItems<T>,
+    LinkedAccountsIndex<T>,
+>;
+
+/// Every token.
+pub type AllTokens<T> = EnumerableValueSet<
+    <T as system::Trait>::Hash,
+    AllTokensCount,
+    AllTokensItems<T>,
+    AllTokensIndex<T>,
+>;
+
+/// The tokens held by each identity.
+pub type OwnedTokens<T> = EnumerableSet<
+    <T as system::Trait>::Hash,
+    <T as system::Trait>::Hash,
+    OwnedTokensCount<T>,
+    OwnedTokensItems<T>,
+    OwnedTokensIndex<T>,
+>;
+
+/// The tokens issued by each identity.
+pub type IssuedTokens<T> = EnumerableSet<
+    <T as system::Trait>::Hash,
+    <T as system::Trait>::Hash,
+    IssuedTokensCount<T>,
+    IssuedTokensItems<T>,
+    IssuedTokensIndex<T>,
+>;
+
 // The pallet's errors
 decl_error! {
     pub enum Error for Module<T: Trait> {
@@ -102,6 +525,66 @@ decl_error! {
         NoneValue,
         /// Value reached maximum and cannot be incremented further
         StorageOverflow,
+        /// The token does not exist
+        TokenNotExist,
+        /// The identity does not exist
+        IdentityNotExist,
+        /// The sender does not own the identity
+        NotIdentityOwner,
+        /// The token has expired
+        TokenExpired,
+        /// The expiry block is not in the future
+        InvalidExpiry,
+        /// The datatype is already registered for this identity
+        DataTypeExists,
+        /// The datatype is not registered for the issuer identity
+        DataTypeNotExist,
+        /// The datatype allows payloads larger than `MaxPayloadLength`
+        DataTypeTooLarge,
+        /// The payload is larger than its datatype allows
+        PayloadTooLarge,
+        /// The token already has an unanswered authentication request
+        AuthenticationPending,
+        /// The token has no unanswered authentication request
+        NoPendingAuthentication,
+        /// The sender is not an authenticator
+        NotAuthenticator,
+        /// The sender neither owns the identity nor is one of its delegates
+        NotIdentityController,
+        /// The account is already a delegate of the identity
+        DelegateExists,
+        /// The account is not a delegate of the identity
+        DelegateNotExist,
+        /// The external account is already linked to an identity
+        AccountAlreadyLinked,
+        /// The external account is not linked to the identity
+        AccountNotLinked,
+        /// The signature does not match the external account
+        InvalidLinkSignature,
+        /// The identity is not a registered issuer
+        NotIssuer,
+        /// The identity is already a registered issuer
+        IssuerExists,
+        /// The owner of the identity is not verified to register issuers
+        IssuerNotVerified,
+        /// The token has no authentication requests left in the current period
+        QuotaExhausted,
+        /// The quota period is zero
+        InvalidQuotaPeriod,
+        /// A token appears more than once in the batch
+        DuplicateToken,
+        /// The cost of the token was not accepted by a controller of its holder
+        TokenNotAccepted,
+        /// The token has no cost left to accept
+        TokenAlreadyAccepted,
+        /// The identity has more tokens than the call was given as witness
+        TooManyTokens,
+        /// The identity has more linked accounts than the call was given as witness
+        TooManyLinkedAccounts,
+        /// The identity is a registered issuer, which must be unregistered to be transferred
+        IssuerNotTransferable,
+        /// As many tokens as `MaxExpiriesPerBlock` already expire at the expiry block
+        TooManyExpiries,
     }
 }
 
@@ -113,98 +596,823 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        /// The maximum length of a datatype schema name.
+        const MaxDataTypeNameLength: u32 = T::MaxDataTypeNameLength::get();
+
+        /// The maximum length of a token payload.
+        const MaxPayloadLength: u32 = T::MaxPayloadLength::get();
+
+        /// The base deposit reserved for storing a token.
+        const TokenDepositBase: T::Balance = T::TokenDepositBase::get();
+
+        /// The deposit reserved per byte of token payload.
+        const TokenDepositPerByte: T::Balance = T::TokenDepositPerByte::get();
+
+        /// The maximum length of the URL of an authentication request.
+        const MaxUrlLength: u32 = T::MaxUrlLength::get();
+
+        /// The bond reserved for registering an issuer.
+        const IssuerBond: T::Balance = T::IssuerBond::get();
+
+        /// The maximum number of tokens issued or transferred by a batch call.
+        const MaxBatchSize: u32 = T::MaxBatchSize::get();
+
+        /// The maximum number of accounts reporting authentication responses.
+        const MaxAuthenticators: u32 = T::MaxAuthenticators::get();
+
+        /// The maximum number of tokens that expire at a block.
+        const MaxExpiriesPerBlock: u32 = T::MaxExpiriesPerBlock::get();
+
+        /// The maximum number of token quotas reset in a block.
+        const MaxQuotaResetsPerBlock: u32 = T::MaxQuotaResetsPerBlock::get();
+
+        /// The maximum number of timed out authentication requests dropped in a block.
+        const MaxTimeoutsPerBlock: u32 = T::MaxTimeoutsPerBlock::get();
+
+        /// The number of blocks an authentication request waits for an answer.
+        const AuthenticationTimeout: T::BlockNumber = T::AuthenticationTimeout::get();
+
+        fn pre_upgrade() -> Result<(), &'static str> {
+            ensure!(
+                Self::storage_version_before_upgrade() <= Self::current_storage_version(),
+                "LitentryStorage: the on-chain storage version is ahead of the code",
+            );
+            Ok(())
+        }
+
+        fn on_runtime_upgrade() -> Weight {
+            let mut weight = T::DbWeight::get().reads_writes(2, 2);
+            // Chains that predate any storage version are on the first layout.
+            let mut version = Self::storage_version_before_upgrade();
+            if version <= 1 {
+                weight += Self::migrate_to_token_payloads();
+                weight += Self::migrate_to_enumerable_sets();
+                version = StorageVersion::new(2);
+            }
+            if version == 2 {
+                weight += Self::migrate_to_attribute_roots();
+                version = StorageVersion::new(3);
+            }
+            if version == 3 {
+                weight += Self::migrate_to_deterministic_token_ids();
+                version = StorageVersion::new(4);
+            }
+            if version == 4 {
+                weight += Self::migrate_to_authentication_deadlines();
+                version = StorageVersion::new(5);
+            }
+            if version == 5 {
+                weight += Self::migrate_to_value_sets();
+            }
+            take_storage_value::<Releases>(b"LitentryStorage", b"StorageVersion", &[]);
+            // Written even when no migration ran, so that the version on chain matches the code.
+            Self::put_on_chain_storage_version(Self::current_storage_version());
+            weight
+        }
+
+        fn post_upgrade() -> Result<(), &'static str> {
+            ensure!(
+                Self::on_chain_storage_version() == Self::current_storage_version(),
+                "LitentryStorage: the storage version was not upgraded",
+            );
+            for identity_id in AllIdentities::<T>::iter() {
+                ensure!(
+                    <IdentityOwner<T>>::contains_key(identity_id),
+                    "LitentryStorage: an identity has no owner",
+                );
+            }
+            for token_id in AllTokens::<T>::iter() {
+                ensure!(
+                    <AuthorizedTokenOwner<T>>::contains_key(token_id),
+                    "LitentryStorage: a token has no owner identity",
+                );
+            }
+            Ok(())
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            // At most `MaxExpiriesPerBlock` tokens, see `mint_token` and `expiry_bucket`.
+            let expiring = <AuthorizedTokensExpiringAt<T>>::take(now);
+            let expiring_count = expiring.len() as Weight;
+            let mut expired_count: Weight = 0;
+            for token_id in expiring {
+                // The token may have been recalled in the meantime.
+                if <AuthorizedTokenOwner<T>>::contains_key(token_id) && Self::burn_token(token_id).is_ok() {
+                    expired_count += 1;
+                    Self::deposit_event(RawEvent::AuthorizedTokenExpired(token_id));
+                }
+            }
+
+            let mut timing_out = <AuthenticationsTimingOutAt<T>>::take(now);
+            let max_timeouts = T::MaxTimeoutsPerBlock::get() as usize;
+            let timeouts_carried_over = timing_out.len() > max_timeouts;
+            if timeouts_carried_over {
+                let rest = timing_out.split_off(max_timeouts);
+                let next = now.saturating_add(1u32.into());
+                <AuthenticationsTimingOutAt<T>>::append_or_insert(next, &rest[..]);
+            }
+            let timing_out_count = timing_out.len() as Weight;
+            let mut timed_out_count: Weight = 0;
+            for token_id in timing_out {
+                // The request may have been answered, or renewed, in the meantime. Requests
+                // carried over from earlier blocks are past their deadline.
+                if let Some((requester, _, deadline)) = Self::pending_authentication(token_id) {
+                    if deadline <= now {
+                        <PendingAuthentications<T>>::remove(token_id);
+                        timed_out_count += 1;
+                        Self::deposit_event(RawEvent::AuthenticationTimedOut(requester, token_id));
+                    }
+                }
+            }
+
+            let mut resetting = <TokenQuotaResetsAt<T>>::take(now);
+            let max_resets = T::MaxQuotaResetsPerBlock::get() as usize;
+            let resets_carried_over = resetting.len() > max_resets;
+            if resets_carried_over {
+                let rest = resetting.split_off(max_resets);
+                let next = now.saturating_add(1u32.into());
+                <TokenQuotaResetsAt<T>>::append_or_insert(next, &rest[..]);
+            }
+            let resetting_count = resetting.len() as Weight;
+            let mut reset_count: Weight = 0;
+            for token_id in resetting {
+                // The quota may have been changed, or the token removed, in the meantime. Resets
+                // carried over from earlier blocks are late.
+                if let Some(mut quota) = Self::token_quota(token_id).filter(|q| q.next_reset <= now) {
+                    quota.remaining = quota.limit;
+                    quota.next_reset = now.saturating_add(quota.period);
+                    <TokenQuotaResetsAt<T>>::append_or_insert(quota.next_reset, &[token_id][..]);
+                    <TokenQuotas<T>>::insert(token_id, quota);
+                    reset_count += 1;
+                }
+            }
+
+            // Every entry of the buckets is read, even when it is skipped.
+            T::DbWeight::get().reads_writes(
+                3 + expiring_count + 16 * expired_count + timing_out_count + resetting_count
+                    + reset_count,
+                3 + 24 * expired_count
+                    + timeouts_carried_over as Weight
+                    + timed_out_count
+                    + resets_carried_over as Weight
+                    + 2 * reset_count,
+            )
+        }
+
         // public functions
-        #[weight = 700]
+        #[weight = T::WeightInfo::register_identity()]
         pub fn register_identity(origin) -> DispatchResult {
             let sender = ensure_signed(origin)?;
-            let random_hash = Self::get_hash(&sender);
+            let nonce = Self::identity_nonce(&sender);
+            let identity_id = T::Hashing::hash_of(&(&sender, nonce));
             let new_identity = Identity {
-                id: random_hash
+                id: identity_id,
+                attributes_root: Default::default(),
             };
-            Self::mint_identity(sender, random_hash, new_identity)
-
-            // <Nonce<T>>::mutate(|n| *n += 1);
-            // Ok(())
+            Self::mint_identity(sender.clone(), identity_id, new_identity)?;
+            <IdentityNonce<T>>::insert(&sender, nonce + 1);
+            Ok(())
         }
 
-        #[weight = 700]
+        #[weight = T::WeightInfo::register_identity_with_id()]
         fn register_identity_with_id(origin, identity_id: T::Hash) -> DispatchResult {
             let sender = ensure_signed(origin)?;
 
             let new_identity = Identity {
                 id: identity_id,
+                attributes_root: Default::default(),
             };
-            // sp_runtime::print("new identity registered");
 
             Self::mint_identity(sender, identity_id, new_identity)
-
-            // Ok(())
         }
 
-        #[weight = 1200]
+        #[weight = T::WeightInfo::issue_token(data.len() as u32)]
         fn issue_token(
             origin,
             to: T::Hash,
             identity_id: T::Hash,
             cost: T::Balance,
-            data: u64,
+            data: BoundedVec<u8, T::MaxPayloadLength>,
             datatype:u64,
-            expired: u64) -> DispatchResult {
+            expired: T::BlockNumber) -> DispatchResult {
 
             let _sender = ensure_signed(origin)?;
-            let id = Self::get_hash(&_sender);
-
-            let new_token = AuthorizedToken {
-                id,
-                cost,
-                data,
-                datatype,
-                expired
-            };
 
-            Self::mint_token(to, identity_id, id, new_token)?;
-            <frame_system::Module<T>>::inc_account_nonce(&_sender);
+            Self::ensure_identity_controller(&_sender, identity_id)?;
+            ensure!(<Issuers<T>>::contains_key(identity_id), Error::<T>::NotIssuer);
+            let schema = Self::datatype_schema(identity_id, datatype)
+                .ok_or(Error::<T>::DataTypeNotExist)?;
+            ensure!(data.len() <= schema.max_size as usize, Error::<T>::PayloadTooLarge);
+
+            let data = data.into_inner();
+            Self::issue(&_sender, identity_id, TokenIssuance { to, cost, data, datatype, expired })
+        }
+
+        /// Issue `tokens` from `identity_id`, as many `issue_token` calls. Either every token is
+        /// issued or none is.
+        #[weight = T::WeightInfo::issue_tokens_batch(
+            tokens.len() as u32,
+            tokens.iter().fold(0u32, |total, token| total.saturating_add(token.data.len() as u32)),
+        )]
+        #[transactional]
+        fn issue_tokens_batch(
+            origin,
+            identity_id: T::Hash,
+            tokens: BoundedVec<TokenIssuanceOf<T>, T::MaxBatchSize>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_controller(&sender, identity_id)?;
+            ensure!(<Issuers<T>>::contains_key(identity_id), Error::<T>::NotIssuer);
 
+            // A failing token rolls back the ones issued before it.
+            for token in tokens {
+                let schema = Self::datatype_schema(identity_id, token.datatype)
+                    .ok_or(Error::<T>::DataTypeNotExist)?;
+                ensure!(token.data.len() <= schema.max_size as usize, Error::<T>::PayloadTooLarge);
+                Self::issue(&sender, identity_id, token)?;
+            }
             Ok(())
         }
 
-        #[weight = 900]
+        /// Register a payload datatype that `identity_id` can issue tokens for.
+        #[weight = T::WeightInfo::register_datatype(name.len() as u32)]
+        fn register_datatype(
+            origin,
+            identity_id: T::Hash,
+            datatype: u64,
+            name: BoundedVec<u8, T::MaxDataTypeNameLength>,
+            max_size: u32,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(
+                !<DataTypeSchemas<T>>::contains_key(identity_id, datatype),
+                Error::<T>::DataTypeExists
+            );
+            ensure!(max_size <= T::MaxPayloadLength::get(), Error::<T>::DataTypeTooLarge);
+
+            <DataTypeSchemas<T>>::insert(identity_id, datatype, DataTypeSchema {
+                id: datatype,
+                name: name.into_inner(),
+                max_size,
+            });
+
+            Self::deposit_event(RawEvent::DataTypeRegistered(identity_id, datatype));
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::transfer_token()]
         fn transfer_token(origin, to: T::Hash, token_id: T::Hash ) -> DispatchResult {
             let sender = ensure_signed(origin)?;
 
-            Self::token_transfer_from(sender.clone(), to, token_id)?;
-            <frame_system::Module<T>>::inc_account_nonce(&sender);
+            Self::ensure_token_active(token_id)?;
+            Self::token_transfer_from(sender, to, token_id)?;
+
+            Ok(())
+        }
+
+        /// Transfer tokens to identities, given as (identity, token) pairs, as many
+        /// `transfer_token` calls. Either every token is transferred or none is.
+        #[weight = T::WeightInfo::transfer_tokens_batch(transfers.len() as u32)]
+        #[transactional]
+        fn transfer_tokens_batch(
+            origin,
+            transfers: BoundedVec<(T::Hash, T::Hash), T::MaxBatchSize>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            // A failing transfer rolls back the ones made before it. A token is moved once at
+            // most, so that a batch cannot pass it on from an identity it just reached.
+            let mut seen = BTreeSet::new();
+            for (to, token_id) in transfers {
+                ensure!(seen.insert(token_id), Error::<T>::DuplicateToken);
+                Self::ensure_token_active(token_id)?;
+                Self::token_transfer_from(sender.clone(), to, token_id)?;
+            }
+            Ok(())
+        }
+
+        /// Agree to pay the cost of `token_id`, which is reserved from the sender until the token
+        /// is first used. Only a controller of the identity holding the token may do this.
+        #[weight = T::WeightInfo::accept_token()]
+        fn accept_token(origin, token_id: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let token = Self::ensure_token_active(token_id)?;
+            let owner_identity =
+                Self::owner_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
+            Self::ensure_identity_controller(&sender, owner_identity)?;
+            ensure!(Self::awaits_acceptance(token_id), Error::<T>::TokenAlreadyAccepted);
+
+            <pallet_balances::Module<T>>::reserve(&sender, token.cost)?;
+            <AuthorizedTokenCostReserves<T>>::insert(token_id, (sender.clone(), token.cost));
+            <TokensAwaitingAcceptance<T>>::remove(token_id);
+
+            Self::deposit_event(RawEvent::AuthorizedTokenCostReserved(sender.clone(), token_id, token.cost));
+            Self::deposit_event(RawEvent::AuthorizedTokenAccepted(sender, token_id));
+            Ok(())
+        }
+
+        /// Ask the offchain workers to post a proof of `token_id` to `url`. Only a controller of
+        /// the identity holding the token may do this, once its cost is accepted. The weight
+        /// grows with the length of `url`, which is kept in storage until the request is answered.
+        #[weight = T::WeightInfo::request_authentication(url.len() as u32)]
+        fn request_authentication(
+            origin,
+            token_id: T::Hash,
+            url: BoundedVec<u8, T::MaxUrlLength>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_token_active(token_id)?;
+            let owner_identity =
+                Self::owner_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
+            Self::ensure_identity_controller(&sender, owner_identity)?;
+            ensure!(!Self::awaits_acceptance(token_id), Error::<T>::TokenNotAccepted);
+            ensure!(
+                !<PendingAuthentications<T>>::contains_key(token_id),
+                Error::<T>::AuthenticationPending
+            );
+            let quota = match Self::token_quota(token_id) {
+                Some(mut quota) => {
+                    ensure!(quota.remaining > 0, Error::<T>::QuotaExhausted);
+                    quota.remaining -= 1;
+                    Some(quota)
+                }
+                None => None,
+            };
+            Self::settle_token_cost(token_id)?;
+
+            if let Some(quota) = quota {
+                <TokenQuotas<T>>::insert(token_id, quota);
+            }
+            // An unanswered request no longer keeps others out once it times out.
+            let deadline = <frame_system::Module<T>>::block_number()
+                .saturating_add(T::AuthenticationTimeout::get());
+            <AuthenticationsTimingOutAt<T>>::append_or_insert(deadline, &[token_id][..]);
+            <PendingAuthentications<T>>::insert(token_id, (sender.clone(), url.clone(), deadline));
+            Self::deposit_event(RawEvent::AuthenticaterRequest(sender, token_id, url.into_inner()));
+            Ok(())
+        }
+
+        /// Allow `limit` authentication requests of `token_id` per `period` blocks, starting
+        /// now, or lift the limit with `None`. Only the issuer of the token may limit it.
+        #[weight = T::WeightInfo::set_token_quota()]
+        fn set_token_quota(origin, token_id: T::Hash, quota: Option<(u32, T::BlockNumber)>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let issuer_identity =
+                Self::issuer_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
+            Self::ensure_identity_controller(&sender, issuer_identity)?;
+
+            match quota {
+                Some((limit, period)) => {
+                    ensure!(!period.is_zero(), Error::<T>::InvalidQuotaPeriod);
+                    let next_reset = <frame_system::Module<T>>::block_number().saturating_add(period);
+                    <TokenQuotaResetsAt<T>>::append_or_insert(next_reset, &[token_id][..]);
+                    <TokenQuotas<T>>::insert(token_id, UsageQuota { limit, remaining: limit, period, next_reset });
+                    Self::deposit_event(RawEvent::TokenQuotaSet(token_id, limit, period));
+                }
+                None => {
+                    // The pending reset finds no quota and is skipped.
+                    if <TokenQuotas<T>>::take(token_id).is_some() {
+                        Self::deposit_event(RawEvent::TokenQuotaRemoved(token_id));
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Report the HTTP status the URL of a pending authentication request answered with.
+        #[weight = T::WeightInfo::submit_authentication_response()]
+        fn submit_authentication_response(origin, token_id: T::Hash, status: u16) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::authenticators().contains(&sender), Error::<T>::NotAuthenticator);
+            let (requester, _url, _deadline) = <PendingAuthentications<T>>::take(token_id)
+                .ok_or(Error::<T>::NoPendingAuthentication)?;
+
+            Self::deposit_event(RawEvent::AuthenticationResponse(requester, token_id, status));
+            Ok(())
+        }
+
+        /// Set the accounts allowed to report authentication responses.
+        #[weight = T::WeightInfo::set_authenticators(authenticators.len() as u32)]
+        fn set_authenticators(
+            origin,
+            authenticators: BoundedVec<T::AccountId, T::MaxAuthenticators>,
+        ) -> DispatchResult {
+            T::AuthenticatorOrigin::ensure_origin(origin)?;
+
+            <Authenticators<T>>::put(authenticators.into_inner());
+            Ok(())
+        }
+
+        /// Answers pending authentication requests: posts a signed token proof to each request's
+        /// URL and reports the response status back on chain.
+        fn offchain_worker(block_number: T::BlockNumber) {
+            if let Err(e) = Self::fulfil_authentication_requests(block_number) {
+                debug::error!("Error: {}", e);
+            }
+        }
+
+        /// Recall a single token. Only the owner of the token's issuer identity may do this.
+        #[weight = T::WeightInfo::recall_token()]
+        fn recall_token(origin, token_id: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let issuer_identity =
+                Self::issuer_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
+            Self::ensure_identity_owner(&sender, issuer_identity)?;
+
+            Self::burn_token(token_id)?;
+
+            Self::deposit_event(RawEvent::AuthorizedTokenRecalled(sender, issuer_identity, token_id));
+            Ok(())
+        }
+
+        /// Recall every token issued by `identity_id`. Only the owner of the identity may do this.
+        ///
+        /// `max_tokens` is at least the number of tokens the identity issued; the call is charged
+        /// for that many.
+        #[weight = T::WeightInfo::recall_all_identity_token(*max_tokens)]
+        fn recall_all_identity_token(origin, identity_id: T::Hash, max_tokens: u32) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            let issued_tokens_count = IssuedTokens::<T>::len(&identity_id);
+            ensure!(issued_tokens_count <= max_tokens.into(), Error::<T>::TooManyTokens);
+
+            // Burn from the tail so that no swap is needed in the issuer's set.
+            for position in (0..issued_tokens_count).rev() {
+                if let Some(token_id) = IssuedTokens::<T>::get(&identity_id, position) {
+                    Self::burn_token(token_id)?;
+                }
+            }
+
+            Self::deposit_event(RawEvent::IdentityAuthorizedTokensRecalled(
+                sender,
+                identity_id,
+                issued_tokens_count,
+            ));
+            Ok(())
+        }
+
+        /// Hand `identity_id` over to the account `to`. Its tokens stay with it, its delegates are
+        /// revoked so that they cannot act for the new owner. An issuer must be unregistered
+        /// first, as its bond is reserved from the current owner.
+        #[weight = T::WeightInfo::transfer_identity()]
+        fn transfer_identity(origin, identity_id: T::Hash, to: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(!<Issuers<T>>::contains_key(identity_id), Error::<T>::IssuerNotTransferable);
+
+            OwnerIdentities::<T>::remove(&sender, &identity_id);
+            OwnerIdentities::<T>::insert(&to, &identity_id)?;
+            <IdentityOwner<T>>::insert(identity_id, &to);
+            <IdentityDelegates<T>>::remove_prefix(identity_id);
+
+            Self::deposit_event(RawEvent::IdentityTransferred(sender, to, identity_id));
+            Ok(())
+        }
+
+        /// Allow `delegate` to issue and transfer tokens on behalf of `identity_id`.
+        #[weight = T::WeightInfo::add_delegate()]
+        fn add_delegate(origin, identity_id: T::Hash, delegate: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(!Self::is_delegate(identity_id, &delegate), Error::<T>::DelegateExists);
+
+            <IdentityDelegates<T>>::insert(identity_id, &delegate, true);
+
+            Self::deposit_event(RawEvent::IdentityDelegateAdded(identity_id, delegate));
+            Ok(())
+        }
+
+        /// Revoke the right of `delegate` to act on behalf of `identity_id`.
+        #[weight = T::WeightInfo::remove_delegate()]
+        fn remove_delegate(origin, identity_id: T::Hash, delegate: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(Self::is_delegate(identity_id, &delegate), Error::<T>::DelegateNotExist);
+
+            <IdentityDelegates<T>>::remove(identity_id, &delegate);
+
+            Self::deposit_event(RawEvent::IdentityDelegateRemoved(identity_id, delegate));
+            Ok(())
+        }
+
+        /// Destroy `identity_id`, revoking every token it issued or holds.
+        ///
+        /// `max_issued`, `max_owned` and `max_linked` are at least the numbers of tokens the
+        /// identity issued, of tokens it holds and of accounts linked to it; the call is charged
+        /// for that many.
+        #[weight = T::WeightInfo::destroy_identity(*max_issued, *max_owned, *max_linked)]
+        fn destroy_identity(
+            origin,
+            identity_id: T::Hash,
+            max_issued: u32,
+            max_owned: u32,
+            max_linked: u32,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(
+                IssuedTokens::<T>::len(&identity_id) <= max_issued.into()
+                    && OwnedTokens::<T>::len(&identity_id) <= max_owned.into(),
+                Error::<T>::TooManyTokens
+            );
+            ensure!(
+                LinkedAccounts::<T>::len(&identity_id) <= max_linked.into(),
+                Error::<T>::TooManyLinkedAccounts
+            );
+
+            // Burn from the tail so that no swap is needed in the identity's sets.
+            let mut removed_count: u64 = 0;
+            for position in (0..IssuedTokens::<T>::len(&identity_id)).rev() {
+                if let Some(token_id) = IssuedTokens::<T>::get(&identity_id, position) {
+                    Self::burn_token(token_id)?;
+                    removed_count += 1;
+                }
+            }
+            // Tokens it issued to itself are gone already.
+            for position in (0..OwnedTokens::<T>::len(&identity_id)).rev() {
+                if let Some(token_id) = OwnedTokens::<T>::get(&identity_id, position) {
+                    Self::burn_token(token_id)?;
+                    removed_count += 1;
+                }
+            }
+
+            for account in LinkedAccounts::<T>::iter(identity_id) {
+                <ExternalAccountIdentity<T>>::remove(account);
+            }
+            LinkedAccounts::<T>::clear(&identity_id);
+            if let Some(issuer) = <Issuers<T>>::get(identity_id) {
+                Self::remove_issuer(identity_id, issuer);
+            }
+            <IdentityDelegates<T>>::remove_prefix(identity_id);
+            <DataTypeSchemas<T>>::remove_prefix(identity_id);
+            AllIdentities::<T>::remove(&identity_id);
+            OwnerIdentities::<T>::remove(&sender, &identity_id);
+            <Identities<T>>::remove(identity_id);
+            <IdentityOwner<T>>::remove(identity_id);
+
+            Self::deposit_event(RawEvent::IdentityDestroyed(sender, identity_id, removed_count));
+            Ok(())
+        }
+
+        /// Link the external `account` to `identity_id`. `signature` is the account's signature
+        /// of `link_message(identity_id, link_nonce(identity_id))`.
+        #[weight = T::WeightInfo::link_account()]
+        fn link_account(
+            origin,
+            identity_id: T::Hash,
+            account: ExternalAccount,
+            signature: ExternalSignature,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(
+                !<ExternalAccountIdentity<T>>::contains_key(&account),
+                Error::<T>::AccountAlreadyLinked
+            );
+            let nonce = Self::link_nonce(identity_id);
+            let message = Self::link_message(identity_id, nonce);
+            ensure!(
+                Self::verify_external_signature(&account, &signature, &message),
+                Error::<T>::InvalidLinkSignature
+            );
+
+            LinkedAccounts::<T>::insert(&identity_id, &account)?;
+            <ExternalAccountIdentity<T>>::insert(&account, identity_id);
+            // A signature is only good for one link.
+            <LinkNonce<T>>::insert(identity_id, nonce + 1);
+
+            Self::deposit_event(RawEvent::AccountLinked(identity_id, account));
+            Ok(())
+        }
+
+        /// Unlink the external `account` from `identity_id`.
+        #[weight = T::WeightInfo::unlink()]
+        fn unlink(origin, identity_id: T::Hash, account: ExternalAccount) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(
+                Self::identity_of_external_account(&account) == Some(identity_id),
+                Error::<T>::AccountNotLinked
+            );
+
+            LinkedAccounts::<T>::remove(&identity_id, &account);
+            <ExternalAccountIdentity<T>>::remove(&account);
+
+            Self::deposit_event(RawEvent::AccountUnlinked(identity_id, account));
+            Ok(())
+        }
+
+        /// Register `identity_id` as a token issuer, reserving `IssuerBond` from the sender.
+        #[weight = T::WeightInfo::register_issuer()]
+        fn register_issuer(origin, identity_id: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            ensure!(!<Issuers<T>>::contains_key(identity_id), Error::<T>::IssuerExists);
+            ensure!(T::IssuerVerifier::is_verified(&sender), Error::<T>::IssuerNotVerified);
+
+            let bond = T::IssuerBond::get();
+            <pallet_balances::Module<T>>::reserve(&sender, bond)?;
+            <Issuers<T>>::insert(identity_id, IssuerInfo { account: sender.clone(), bond, issued: 0, slashes: 0 });
 
+            Self::deposit_event(RawEvent::IssuerRegistered(sender, identity_id, bond));
             Ok(())
         }
 
-        #[weight = 100]
-        fn request_authentication(origin, token_id: T::Hash, url:Vec<u8>) -> DispatchResult {
+        /// Stop `identity_id` from issuing tokens and return the rest of its bond. The tokens it
+        /// issued stay valid.
+        #[weight = T::WeightInfo::unregister_issuer()]
+        fn unregister_issuer(origin, identity_id: T::Hash) -> DispatchResult {
             let sender = ensure_signed(origin)?;
 
-            Self::deposit_event(RawEvent::AuthenticaterRequest(sender, token_id, url));
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            let issuer = Self::issuer(identity_id).ok_or(Error::<T>::NotIssuer)?;
+
+            Self::remove_issuer(identity_id, issuer);
             Ok(())
         }
 
-        // fn recall_token(origin, token: T::Hash) -> DispatchResult {}
+        /// Slash up to `amount` of the bond of the issuer `identity_id`. An issuer whose bond
+        /// falls below `IssuerBond` is unregistered and the rest of its bond returned.
+        #[weight = T::WeightInfo::slash_issuer()]
+        fn slash_issuer(origin, identity_id: T::Hash, amount: T::Balance) -> DispatchResult {
+            T::SlashOrigin::ensure_origin(origin)?;
+
+            let mut issuer = Self::issuer(identity_id).ok_or(Error::<T>::NotIssuer)?;
+            let amount = amount.min(issuer.bond);
+            let (imbalance, missing) = <pallet_balances::Module<T>>::slash_reserved(&issuer.account, amount);
+            T::Slashed::on_unbalanced(imbalance);
+            let slashed = amount - missing;
+            issuer.bond -= slashed;
+            issuer.slashes = issuer.slashes.saturating_add(1);
+            Self::deposit_event(RawEvent::IssuerSlashed(identity_id, slashed));
+
+            if issuer.bond < T::IssuerBond::get() {
+                Self::remove_issuer(identity_id, issuer);
+            } else {
+                <Issuers<T>>::insert(identity_id, issuer);
+            }
+            Ok(())
+        }
 
-        // fn recall_all_identity_token(origin, identity_id: T::Hash ) -> DispatchResult {}
+        /// Commit `identity_id` to the attributes under the Merkle root `attributes_root`.
+        ///
+        /// The leaves of the tree are `attribute_commitment`s, the root of no attributes is the
+        /// default hash.
+        #[weight = T::WeightInfo::set_attributes_root()]
+        fn set_attributes_root(origin, identity_id: T::Hash, attributes_root: T::Hash) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
 
-        // fn update_token(origin, token: T::Hash, identity_data: String) -> DispatchResult {}
+            Self::ensure_identity_owner(&sender, identity_id)?;
+            <Identities<T>>::mutate(identity_id, |identity| identity.attributes_root = attributes_root);
 
+            Self::deposit_event(RawEvent::IdentityAttributesUpdated(identity_id, attributes_root));
+            Ok(())
+        }
     }
 }
 
 impl<T: Trait> Module<T> {
-    // fn is_token_owner(token: &T::Hash, identity: &T::Hash) -> bool {
-    //     <IdentityAuthorizedTokensIndex<T>>::get(token).contains(who)
-    // }
-    fn get_hash(sender: &T::AccountId) -> T::Hash {
-        let nonce = <frame_system::Module<T>>::account_nonce(&sender);
-        let random_hash_bytes = (T::Randomness::random_seed(), &sender, nonce)
-            // .using_encoded(blake2_256);(
-            .using_encoded(T::Hashing::hash);
-        T::Hash::from(random_hash_bytes)
+    /// The identities owned by `account`.
+    pub fn identities_of(account: &T::AccountId) -> Vec<T::Hash> {
+        OwnerIdentities::<T>::iter(account.clone()).collect()
+    }
+
+    /// At most `count` tokens held by `identity`, starting at index `start`.
+    pub fn tokens_of_identity(identity: T::Hash, start: u64, count: u64) -> Vec<T::Hash> {
+        OwnedTokens::<T>::range(&identity, start, count)
+    }
+
+    /// At most `count` tokens issued by `identity`, starting at index `start`.
+    pub fn tokens_issued_by(identity: T::Hash, start: u64, count: u64) -> Vec<T::Hash> {
+        IssuedTokens::<T>::range(&identity, start, count)
+    }
+
+    /// The message an external account signs to be linked to `identity_id`, whose link nonce is
+    /// `nonce`. It is bound to this pallet and to the chain by its genesis hash.
+    pub fn link_message(identity_id: T::Hash, nonce: u64) -> Vec<u8> {
+        let genesis_hash = <frame_system::Module<T>>::block_hash(T::BlockNumber::zero());
+        (LINK_MESSAGE_TAG, genesis_hash, identity_id, nonce).encode()
+    }
+
+    /// The salted commitment to the attribute `name` with `value`, a leaf of the attribute tree
+    /// of an identity. The salt keeps low-entropy values from being guessed from the commitment.
+    pub fn attribute_commitment(name: &[u8], value: &[u8], salt: &[u8]) -> T::Hash {
+        T::Hashing::hash_of(&(name, value, salt))
+    }
+
+    /// Whether `proof` shows that `commitment` is one of the attributes of `identity_id`, see
+    /// `litentry_utils::merkle`. The other attributes are not revealed.
+    pub fn verify_attribute(identity_id: T::Hash, commitment: T::Hash, proof: &[T::Hash]) -> bool {
+        let root = Self::identity(identity_id).attributes_root;
+        root != T::Hash::default() && merkle::verify::<T::Hashing>(&root, commitment, proof)
+    }
+
+    /// A token with its owner and issuer identities, if it exists.
+    pub fn token_details(token_id: T::Hash) -> Option<(AuthorizedTokenOf<T>, T::Hash, T::Hash)> {
+        let owner_identity = Self::owner_identity_of_token(token_id)?;
+        let issuer_identity = Self::issuer_identity_of_token(token_id)?;
+        Some((Self::token(token_id), owner_identity, issuer_identity))
+    }
+
+    fn ensure_identity_owner(who: &T::AccountId, identity_id: T::Hash) -> DispatchResult {
+        let owner = Self::owner_of_identity(identity_id).ok_or(Error::<T>::IdentityNotExist)?;
+        ensure!(&owner == who, Error::<T>::NotIdentityOwner);
+        Ok(())
+    }
+
+    /// Make sure `who` owns `identity_id` or is one of its delegates.
+    fn ensure_identity_controller(who: &T::AccountId, identity_id: T::Hash) -> DispatchResult {
+        let owner = Self::owner_of_identity(identity_id).ok_or(Error::<T>::IdentityNotExist)?;
+        ensure!(
+            &owner == who || Self::is_delegate(identity_id, who),
+            Error::<T>::NotIdentityController
+        );
+        Ok(())
+    }
+
+    fn verify_external_signature(
+        account: &ExternalAccount,
+        signature: &ExternalSignature,
+        message: &[u8],
+    ) -> bool {
+        match (account, signature) {
+            (ExternalAccount::Sr25519(public), ExternalSignature::Sr25519(sig)) => {
+                sp_io::crypto::sr25519_verify(sig, message, public)
+            }
+            (ExternalAccount::Ed25519(public), ExternalSignature::Ed25519(sig)) => {
+                sp_io::crypto::ed25519_verify(sig, message, public)
+            }
+            (ExternalAccount::Ethereum(address), ExternalSignature::Ecdsa(sig)) => {
+                let digest = Self::ethereum_message_digest(message);
+                match sp_io::crypto::secp256k1_ecdsa_recover(sig.as_ref(), &digest) {
+                    Ok(public) => {
+                        address.as_bytes() == &sp_io::hashing::keccak_256(&public)[12..]
+                    }
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// The hash an Ethereum wallet signs for `message` with `personal_sign`:
+    /// `keccak256("\x19Ethereum Signed Message:\n" ++ len(message) ++ message)`, where the length
+    /// is written in decimal.
+    fn ethereum_message_digest(message: &[u8]) -> [u8; 32] {
+        let mut length = Vec::new();
+        let mut len = message.len();
+        loop {
+            length.push(b'0' + (len % 10) as u8);
+            len /= 10;
+            if len == 0 {
+                break;
+            }
+        }
+        length.reverse();
+
+        let mut prefixed = b"\x19Ethereum Signed Message:\n".to_vec();
+        prefixed.extend_from_slice(&length);
+        prefixed.extend_from_slice(message);
+        sp_io::hashing::keccak_256(&prefixed)
+    }
+
+    fn ensure_token_active(token_id: T::Hash) -> Result<AuthorizedTokenOf<T>, DispatchError> {
+        ensure!(
+            <AuthorizedTokenOwner<T>>::contains_key(token_id),
+            Error::<T>::TokenNotExist
+        );
+        let token = Self::token(token_id);
+        ensure!(
+            token.expired > <frame_system::Module<T>>::block_number(),
+            Error::<T>::TokenExpired
+        );
+        Ok(token)
+    }
+
+    /// The id of the token `issuer_identity` issues as its `nonce`-th, with payload `data`.
+    pub fn token_id(issuer_identity: T::Hash, nonce: u64, data: &[u8]) -> T::Hash {
+        T::Hashing::hash_of(&(issuer_identity, nonce, T::Hashing::hash(data)))
+    }
+
+    /// The id of the next token `issuer_identity` issues, if its payload is `data`.
+    pub fn next_token_id(issuer_identity: T::Hash, data: &[u8]) -> T::Hash {
+        Self::token_id(issuer_identity, Self::issued_token_nonce(issuer_identity), data)
     }
 
     fn mint_identity(
@@ -217,38 +1425,66 @@ impl<T: Trait> Module<T> {
             "Identity already exists"
         );
 
-        let owned_identities_count = Self::identities_count_of_owner(&to);
-        let new_owned_identities_count = owned_identities_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new identity to owner")?;
+        Self::insert_identity(&to, identity_id, new_identity)?;
+        Self::deposit_event(RawEvent::IdentityCreated(to, identity_id));
 
-        let all_identities_count = Self::identities_count();
-        let new_all_identities_count = all_identities_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new identity to total supply")?;
+        Ok(())
+    }
 
+    /// Store an identity and add it to the identity sets.
+    fn insert_identity(
+        owner: &T::AccountId,
+        identity_id: T::Hash,
+        new_identity: IdentityOf<T>,
+    ) -> DispatchResult {
+        AllIdentities::<T>::insert(&identity_id)?;
+        OwnerIdentities::<T>::insert(owner, &identity_id)?;
         <Identities<T>>::insert(identity_id, new_identity);
-        <IdentityOwner<T>>::insert(identity_id, &to);
-
-        <IdentitiesArray<T>>::insert(all_identities_count, identity_id);
-
-        <IdentitiesIndex<T>>::insert(identity_id, all_identities_count);
-        <IdentitiesCount>::put(new_all_identities_count);
+        <IdentityOwner<T>>::insert(identity_id, owner);
+        Ok(())
+    }
 
-        <OwnedIdentitiesArray<T>>::insert((to.clone(), owned_identities_count), identity_id);
-        <OwnedIdentitiesIndex<T>>::insert(identity_id, owned_identities_count);
-        <OwnedIdentitiesCount<T>>::insert(&to, new_owned_identities_count);
-        <frame_system::Module<T>>::inc_account_nonce(&to);
-        Self::deposit_event(RawEvent::IdentityCreated(to, identity_id));
+    /// The storage deposit of a token with a payload of `payload_len` bytes.
+    fn token_deposit(payload_len: usize) -> T::Balance {
+        T::TokenDepositBase::get()
+            .saturating_add(T::TokenDepositPerByte::get().saturating_mul((payload_len as u32).into()))
+    }
 
+    /// Mint a checked token of `issuer_identity`, at the expense of `issuer`, and count it in the
+    /// issuer's reputation.
+    fn issue(
+        issuer: &T::AccountId,
+        issuer_identity: T::Hash,
+        token: TokenIssuanceOf<T>,
+    ) -> DispatchResult {
+        let nonce = Self::issued_token_nonce(issuer_identity);
+        let id = Self::token_id(issuer_identity, nonce, &token.data);
+        let new_token = AuthorizedToken {
+            id,
+            cost: token.cost,
+            data: token.data,
+            datatype: token.datatype,
+            expired: token.expired,
+        };
+
+        Self::mint_token(issuer, token.to, issuer_identity, id, new_token)?;
+        <IssuedTokenNonce<T>>::insert(issuer_identity, nonce + 1);
+        <Issuers<T>>::mutate(issuer_identity, |issuer| {
+            if let Some(issuer) = issuer {
+                issuer.issued = issuer.issued.saturating_add(1);
+            }
+        });
         Ok(())
     }
 
+    /// Reserve the deposit of `new_token` and store it. Nothing is kept if any step fails.
+    #[transactional]
     fn mint_token(
+        depositor: &T::AccountId,
         receiver_identity: T::Hash,
         issuer_identity: T::Hash,
         token_id: T::Hash,
-        new_token: AuthorizedToken<T::Hash, T::Balance>,
+        new_token: AuthorizedTokenOf<T>,
     ) -> DispatchResult {
         ensure!(
             <IdentityOwner<T>>::contains_key(issuer_identity),
@@ -258,40 +1494,37 @@ impl<T: Trait> Module<T> {
             !<AuthorizedTokenOwner<T>>::contains_key(token_id),
             "Token already exists"
         );
+        ensure!(
+            new_token.expired > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidExpiry
+        );
+        ensure!(
+            <AuthorizedTokensExpiringAt<T>>::decode_len(new_token.expired).unwrap_or(0)
+                < T::MaxExpiriesPerBlock::get() as usize,
+            Error::<T>::TooManyExpiries
+        );
+        ensure!(
+            <IdentityOwner<T>>::contains_key(receiver_identity),
+            Error::<T>::IdentityNotExist
+        );
 
-        let owned_tokens_count = Self::owned_tokens_count_of_identity(receiver_identity);
-        let new_owned_tokens_count = owned_tokens_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new token to owner")?;
-
-        let identity_tokens_count = Self::owned_tokens_count_of_identity(issuer_identity);
-        let new_identity_tokens_count = identity_tokens_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new token to identity")?;
-
-        let all_tokens_count = Self::tokens_count();
-        let new_all_tokens_count = all_tokens_count
-            .checked_add(1)
-            .ok_or("Overflow adding a new token to total supply")?;
-
-        <AuthorizedTokens<T>>::insert(token_id, new_token);
-        <AuthorizedTokenOwner<T>>::insert(token_id, receiver_identity);
-        <AuthorizedTokenIdentity<T>>::insert(token_id, issuer_identity);
-
-        <AuthorizedTokensArray<T>>::insert(all_tokens_count, token_id);
-        <AuthorizedTokensIndex<T>>::insert(token_id, all_tokens_count);
-        <AuthorizedTokensCount>::put(new_all_tokens_count);
+        // The issuing account pays for the storage of the token.
+        let deposit = Self::token_deposit(new_token.data.len());
+        <pallet_balances::Module<T>>::reserve(depositor, deposit)?;
 
-        <OwnedAuthorizedTokensArray<T>>::insert((receiver_identity, owned_tokens_count), token_id);
-        <OwnedAuthorizedTokensIndex<T>>::insert(token_id, owned_tokens_count);
-        <OwnedAuthorizedTokensCount<T>>::insert(receiver_identity, new_owned_tokens_count);
+        // The receiver pays for the token only once it accepts it, see `accept_token`.
+        if !new_token.cost.is_zero() {
+            <TokensAwaitingAcceptance<T>>::insert(token_id, true);
+        }
 
-        <IdentityAuthorizedTokensArray<T>>::insert(
-            (issuer_identity, identity_tokens_count),
+        <AuthorizedTokenDeposits<T>>::insert(token_id, (depositor.clone(), deposit));
+        Self::deposit_event(RawEvent::AuthorizedTokenDepositReserved(
+            depositor.clone(),
             token_id,
-        );
-        <IdentityAuthorizedTokensIndex<T>>::insert(token_id, identity_tokens_count);
-        <IdentityAuthorizedTokensCount<T>>::insert(issuer_identity, new_identity_tokens_count);
+            deposit,
+        ));
+
+        Self::insert_token(receiver_identity, issuer_identity, token_id, new_token)?;
 
         Self::deposit_event(RawEvent::AuthorizedTokenCreated(
             receiver_identity,
@@ -302,6 +1535,27 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// Store a token and add it to the token sets and its expiry bucket.
+    fn insert_token(
+        receiver_identity: T::Hash,
+        issuer_identity: T::Hash,
+        token_id: T::Hash,
+        new_token: AuthorizedTokenOf<T>,
+    ) -> DispatchResult {
+        AllTokens::<T>::insert(&token_id)?;
+        OwnedTokens::<T>::insert(&receiver_identity, &token_id)?;
+        IssuedTokens::<T>::insert(&issuer_identity, &token_id)?;
+
+        <AuthorizedTokensExpiringAt<T>>::append_or_insert(
+            Self::expiry_bucket(new_token.expired),
+            &[token_id][..],
+        );
+        <AuthorizedTokens<T>>::insert(token_id, new_token);
+        <AuthorizedTokenOwner<T>>::insert(token_id, receiver_identity);
+        <AuthorizedTokenIdentity<T>>::insert(token_id, issuer_identity);
+        Ok(())
+    }
+
     fn token_transfer_from(
         from_account: T::AccountId,
         receiver_identity: T::Hash,
@@ -309,58 +1563,455 @@ impl<T: Trait> Module<T> {
     ) -> DispatchResult {
         let sender_identity =
             Self::owner_identity_of_token(token_id).ok_or("No owner identity for this token")?;
-        let owner_account =
-            Self::owner_of_identity(sender_identity).ok_or("No owner account for this token")?;
+        Self::ensure_identity_controller(&from_account, sender_identity)?;
+        // A token held by no identity could never be moved or used again.
         ensure!(
-            owner_account == from_account,
-            "sender do not own this token"
+            <IdentityOwner<T>>::contains_key(receiver_identity),
+            Error::<T>::IdentityNotExist
         );
 
-        let owned_token_count_sender = Self::owned_tokens_count_of_identity(&sender_identity);
-        let owned_token_count_receiver = Self::owned_tokens_count_of_identity(&receiver_identity);
+        OwnedTokens::<T>::remove(&sender_identity, &token_id);
+        OwnedTokens::<T>::insert(&receiver_identity, &token_id)?;
+        <AuthorizedTokenOwner<T>>::insert(token_id, receiver_identity);
 
-        let new_owned_token_count_receiver = owned_token_count_receiver
-            .checked_add(1)
-            .ok_or("Transfer causes overflow of 'to' token balance")?;
+        Self::deposit_event(RawEvent::AuthorizedTokenTransferred(
+            from_account,
+            receiver_identity,
+            token_id,
+        ));
+
+        Ok(())
+    }
+
+    fn burn_token(token_id: T::Hash) -> DispatchResult {
+        let owner_identity =
+            Self::owner_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
+        let issuer_identity =
+            Self::issuer_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
 
-        let new_owned_token_count_sender = owned_token_count_sender
-            .checked_sub(1)
-            .ok_or("Transfer causes underflow of 'from' token balance")?;
+        AllTokens::<T>::remove(&token_id);
+        OwnedTokens::<T>::remove(&owner_identity, &token_id);
+        IssuedTokens::<T>::remove(&issuer_identity, &token_id);
 
-        let token_index_sender = Self::token_index_of_owner_identity(token_id);
-        if token_index_sender != new_owned_token_count_sender {
-            let last_token_id = Self::owned_token_by_index_of_identity((
-                sender_identity,
-                new_owned_token_count_sender,
+        <AuthorizedTokens<T>>::remove(token_id);
+        <AuthorizedTokenOwner<T>>::remove(token_id);
+        <AuthorizedTokenIdentity<T>>::remove(token_id);
+
+        <PendingAuthentications<T>>::remove(token_id);
+        <TokenQuotas<T>>::remove(token_id);
+        <TokensAwaitingAcceptance<T>>::remove(token_id);
+
+        if let Some((payer, cost)) = <AuthorizedTokenCostReserves<T>>::take(token_id) {
+            <pallet_balances::Module<T>>::unreserve(&payer, cost);
+            Self::deposit_event(RawEvent::AuthorizedTokenCostRefunded(payer, token_id, cost));
+        }
+        if let Some((depositor, deposit)) = <AuthorizedTokenDeposits<T>>::take(token_id) {
+            <pallet_balances::Module<T>>::unreserve(&depositor, deposit);
+            Self::deposit_event(RawEvent::AuthorizedTokenDepositRefunded(
+                depositor, token_id, deposit,
             ));
-            <OwnedAuthorizedTokensArray<T>>::insert(
-                (sender_identity, token_index_sender),
-                last_token_id,
+        }
+
+        Ok(())
+    }
+
+    /// The storage version on chain, taken from the legacy `Releases` of chains that stored one.
+    fn storage_version_before_upgrade() -> StorageVersion {
+        get_storage_value::<Releases>(b"LitentryStorage", b"StorageVersion", &[])
+            .map(Releases::storage_version)
+            .unwrap_or_else(Self::on_chain_storage_version)
+    }
+
+    /// Give the tokens of `Releases::V1_0_0`, whose `data`, `datatype` and `expired` were all
+    /// `u64`s, a payload of the SCALE encoding of their `data` and an expiry block, and add them
+    /// to their expiry buckets.
+    fn migrate_to_token_payloads() -> Weight {
+        <AuthorizedTokens<T>>::translate(
+            |_, (id, cost, data, datatype, expired): (T::Hash, T::Balance, u64, u64, u64)| {
+                Some(AuthorizedToken {
+                    id,
+                    cost,
+                    data: data.encode(),
+                    datatype,
+                    expired: expired.saturated_into(),
+                })
+            },
+        );
+        let mut migrated = 0;
+        for (token_id, token) in <AuthorizedTokens<T>>::iter() {
+            <AuthorizedTokensExpiringAt<T>>::append_or_insert(
+                Self::expiry_bucket(token.expired),
+                &[token_id][..],
             );
-            <OwnedAuthorizedTokensIndex<T>>::insert(last_token_id, token_index_sender);
+            migrated += 1;
         }
+        T::DbWeight::get().reads_writes(1 + 2 * migrated, 2 * migrated)
+    }
 
-        <AuthorizedTokenOwner<T>>::insert(token_id, receiver_identity);
-        <OwnedAuthorizedTokensIndex<T>>::insert(token_id, owned_token_count_receiver);
+    /// The block whose `AuthorizedTokensExpiringAt` bucket a token expiring at `expired` is
+    /// swept from: the first block from `expired`, and from the next block for tokens that
+    /// expired already, whose bucket has room. Only tokens stored without `mint_token`, which
+    /// rejects full buckets, may be swept later than they expire.
+    fn expiry_bucket(expired: T::BlockNumber) -> T::BlockNumber {
+        let max_expiries = T::MaxExpiriesPerBlock::get() as usize;
+        let mut bucket =
+            expired.max(<frame_system::Module<T>>::block_number().saturating_add(1u32.into()));
+        while <AuthorizedTokensExpiringAt<T>>::decode_len(bucket).unwrap_or(0) >= max_expiries {
+            bucket = bucket.saturating_add(1u32.into());
+        }
+        bucket
+    }
 
-        <OwnedAuthorizedTokensArray<T>>::remove((
-            sender_identity,
-            new_owned_token_count_sender,
-        ));
-        <OwnedAuthorizedTokensArray<T>>::insert(
-            (receiver_identity, owned_token_count_receiver),
-            token_id,
+    /// Move the `Count`/`Array`/`Index` storage triplets of `Releases::V1_0_0` into
+    /// `EnumerableSet`s, keeping the order of their values.
+    fn migrate_to_enumerable_sets() -> Weight {
+        let identities_count =
+            take_storage_value::<u64>(b"LitentryStorage", b"IdentitiesCount", &[]).unwrap_or(0);
+        let owned_identities_counts =
+            StorageKeyIterator::<T::AccountId, u64, Blake2_128Concat>::new(
+                b"LitentryStorage",
+                b"OwnedIdentitiesCount",
+            )
+            .drain()
+            .collect();
+        let tokens_count =
+            take_storage_value::<u64>(b"LitentryStorage", b"AuthorizedTokensCount", &[]).unwrap_or(0);
+        let owned_tokens_counts = StorageKeyIterator::<T::Hash, u64, Blake2_128Concat>::new(
+            b"LitentryStorage",
+            b"OwnedAuthorizedTokensCount",
+        )
+        .drain()
+        .collect();
+        let issued_tokens_counts = StorageKeyIterator::<T::Hash, u64, Blake2_128Concat>::new(
+            b"LitentryStorage",
+            b"IdentityAuthorizedTokensCount",
+        )
+        .drain()
+        .collect();
+
+        let mut migrated = 0;
+        migrated += Self::migrate_set(
+            |_: &(), identity_id| AllIdentities::<T>::insert(identity_id),
+            vec![((), identities_count)],
+            b"IdentitiesArray",
+        );
+        migrated += Self::migrate_set(
+            OwnerIdentities::<T>::insert,
+            owned_identities_counts,
+            b"OwnedIdentitiesArray",
+        );
+        migrated += Self::migrate_set(
+            |_: &(), token_id| AllTokens::<T>::insert(token_id),
+            vec![((), tokens_count)],
+            b"AuthorizedTokensArray",
+        );
+        migrated += Self::migrate_set(
+            OwnedTokens::<T>::insert,
+            owned_tokens_counts,
+            b"OwnedAuthorizedTokensArray",
+        );
+        migrated += Self::migrate_set(
+            IssuedTokens::<T>::insert,
+            issued_tokens_counts,
+            b"IdentityAuthorizedTokensArray",
         );
 
-        <OwnedAuthorizedTokensCount<T>>::insert(sender_identity, new_owned_token_count_sender);
-        <OwnedAuthorizedTokensCount<T>>::insert(receiver_identity, new_owned_token_count_receiver);
+        // The positions are now kept by the sets.
+        for item in &[
+            &b"IdentitiesIndex"[..],
+            b"OwnedIdentitiesIndex",
+            b"AuthorizedTokensIndex",
+            b"OwnedAuthorizedTokensIndex",
+            b"IdentityAuthorizedTokensIndex",
+        ] {
+            remove_storage_prefix(b"LitentryStorage", item, &[]);
+        }
 
-        Self::deposit_event(RawEvent::AuthorizedTokenTransferred(
-            from_account,
-            receiver_identity,
-            token_id,
-        ));
+        T::DbWeight::get().reads_writes(3 * migrated, 5 * migrated)
+    }
 
+    /// Give the identities of `Releases::V2_0_0` an empty attribute tree.
+    fn migrate_to_attribute_roots() -> Weight {
+        let migrated = AllIdentities::<T>::len();
+        <Identities<T>>::translate(|_, id: T::Hash| {
+            Some(Identity { id, attributes_root: Default::default() })
+        });
+        T::DbWeight::get().reads_writes(migrated, migrated)
+    }
+
+    /// Give the tokens of `Releases::V3_0_0`, whose ids were random, the ids `token_id` derives,
+    /// numbering the tokens of each issuer in the order of its `IssuedTokens`.
+    fn migrate_to_deterministic_token_ids() -> Weight {
+        let mut migrated = 0;
+        for issuer_identity in AllIdentities::<T>::iter() {
+            let old_ids: Vec<T::Hash> = IssuedTokens::<T>::iter(issuer_identity).collect();
+            for old_id in old_ids {
+                if !<AuthorizedTokenOwner<T>>::contains_key(old_id) {
+                    continue;
+                }
+                let nonce = Self::issued_token_nonce(issuer_identity);
+                let mut token = <AuthorizedTokens<T>>::take(old_id);
+                let new_id = Self::token_id(issuer_identity, nonce, &token.data);
+                <IssuedTokenNonce<T>>::insert(issuer_identity, nonce + 1);
+                Self::rekey_token(old_id, new_id, &mut token);
+                <AuthorizedTokens<T>>::insert(new_id, token);
+                migrated += 1;
+            }
+        }
+        T::DbWeight::get().reads_writes(1 + 12 * migrated, 1 + 18 * migrated)
+    }
+
+    /// Give the pending authentication requests of storage version 4, which waited for an answer
+    /// indefinitely, a deadline of `AuthenticationTimeout` blocks from now.
+    fn migrate_to_authentication_deadlines() -> Weight {
+        let deadline = <frame_system::Module<T>>::block_number()
+            .saturating_add(T::AuthenticationTimeout::get());
+        <PendingAuthentications<T>>::translate(
+            |_, (requester, url): (T::AccountId, BoundedVec<u8, T::MaxUrlLength>)| {
+                Some((requester, url, deadline))
+            },
+        );
+        let migrated: Vec<T::Hash> =
+            <PendingAuthentications<T>>::iter().map(|(token_id, _)| token_id).collect();
+        let count = migrated.len() as Weight;
+        if !migrated.is_empty() {
+            <AuthenticationsTimingOutAt<T>>::append_or_insert(deadline, &migrated[..]);
+        }
+        T::DbWeight::get().reads_writes(1 + 2 * count, 1 + count)
+    }
+
+    /// Move the identity and token sets of storage version 5, partitioned by `()`, into
+    /// `EnumerableValueSet`s, keeping the order of their values.
+    fn migrate_to_value_sets() -> Weight {
+        let migrated = Self::migrate_value_set(
+            AllIdentities::<T>::insert,
+            b"AllIdentitiesCount",
+            b"AllIdentitiesItems",
+            b"AllIdentitiesIndex",
+        ) + Self::migrate_value_set(
+            AllTokens::<T>::insert,
+            b"AllTokensCount",
+            b"AllTokensItems",
+            b"AllTokensIndex",
+        );
+        T::DbWeight::get().reads_writes(2 + 2 * migrated, 4 + 4 * migrated)
+    }
+
+    /// Move the values of the `()` partition of a set with the storage items `count`, `items`
+    /// and `index` into the set of the same items with `insert`. Returns the number of values
+    /// moved.
+    fn migrate_value_set(
+        insert: fn(&T::Hash) -> Result<bool, &'static str>,
+        count: &[u8],
+        items: &[u8],
+        index: &[u8],
+    ) -> u64 {
+        let partition = ().using_encoded(Blake2_128Concat::hash);
+        let len = take_storage_value::<u64>(b"LitentryStorage", count, &partition).unwrap_or(0);
+        let values: Vec<T::Hash> = (0..len)
+            .filter_map(|position| {
+                let mut key = partition.clone();
+                key.extend(position.using_encoded(Blake2_128Concat::hash));
+                take_storage_value(b"LitentryStorage", items, &key)
+            })
+            .collect();
+        // The old positions go before the new ones are written under the same item.
+        remove_storage_prefix(b"LitentryStorage", index, &partition);
+        for value in &values {
+            // Cannot overflow, the set has at most `len` values.
+            let _ = insert(value);
+        }
+        values.len() as u64
+    }
+
+    /// Move every storage entry of the token `old_id`, but its `AuthorizedTokens` entry, to
+    /// `new_id`.
+    fn rekey_token(old_id: T::Hash, new_id: T::Hash, token: &mut AuthorizedTokenOf<T>) {
+        token.id = new_id;
+        if let Some(owner_identity) = <AuthorizedTokenOwner<T>>::take(old_id) {
+            OwnedTokens::<T>::remove(&owner_identity, &old_id);
+            // Cannot overflow, a value was just removed.
+            let _ = OwnedTokens::<T>::insert(&owner_identity, &new_id);
+            <AuthorizedTokenOwner<T>>::insert(new_id, owner_identity);
+        }
+        if let Some(issuer_identity) = <AuthorizedTokenIdentity<T>>::take(old_id) {
+            IssuedTokens::<T>::remove(&issuer_identity, &old_id);
+            let _ = IssuedTokens::<T>::insert(&issuer_identity, &new_id);
+            <AuthorizedTokenIdentity<T>>::insert(new_id, issuer_identity);
+        }
+        AllTokens::<T>::remove(&old_id);
+        let _ = AllTokens::<T>::insert(&new_id);
+
+        let replaced = <AuthorizedTokensExpiringAt<T>>::mutate(token.expired, |tokens| {
+            tokens.iter_mut().find(|id| **id == old_id).map(|id| *id = new_id).is_some()
+        });
+        if !replaced {
+            // The token is swept later than it expires, see `expiry_bucket`. The old id stays
+            // in its bucket, which skips it as it has no owner anymore.
+            <AuthorizedTokensExpiringAt<T>>::append_or_insert(
+                Self::expiry_bucket(token.expired),
+                &[new_id][..],
+            );
+        }
+        if <TokensAwaitingAcceptance<T>>::take(old_id) {
+            <TokensAwaitingAcceptance<T>>::insert(new_id, true);
+        }
+        if let Some(reserve) = <AuthorizedTokenCostReserves<T>>::take(old_id) {
+            <AuthorizedTokenCostReserves<T>>::insert(new_id, reserve);
+        }
+        if let Some(deposit) = <AuthorizedTokenDeposits<T>>::take(old_id) {
+            <AuthorizedTokenDeposits<T>>::insert(new_id, deposit);
+        }
+        if let Some(request) = <PendingAuthentications<T>>::take(old_id) {
+            <AuthenticationsTimingOutAt<T>>::mutate(request.2, |tokens| {
+                for id in tokens.iter_mut().filter(|id| **id == old_id) {
+                    *id = new_id;
+                }
+            });
+            <PendingAuthentications<T>>::insert(new_id, request);
+        }
+    }
+
+    /// Move the values of a `Releases::V1_0_0` `Array` storage item, given the value count of
+    /// each key, into a set with `insert`. Returns the number of values moved.
+    fn migrate_set<K: Encode>(
+        insert: fn(&K, &T::Hash) -> Result<bool, &'static str>,
+        counts: Vec<(K, u64)>,
+        array: &[u8],
+    ) -> u64 {
+        let mut migrated = 0;
+        for (key, count) in counts {
+            for position in 0..count {
+                let value = take_storage_item::<_, T::Hash, Blake2_128Concat>(
+                    b"LitentryStorage",
+                    array,
+                    (&key, position),
+                );
+                if let Some(value) = value {
+                    // Cannot overflow, the set has at most `count` values.
+                    let _ = insert(&key, &value);
+                    migrated += 1;
+                }
+            }
+        }
+        migrated
+    }
+
+    /// Unregister an issuer and return the rest of its bond.
+    fn remove_issuer(identity_id: T::Hash, issuer: IssuerInfoOf<T>) {
+        <pallet_balances::Module<T>>::unreserve(&issuer.account, issuer.bond);
+        <Issuers<T>>::remove(identity_id);
+        Self::deposit_event(RawEvent::IssuerUnregistered(issuer.account, identity_id, issuer.bond));
+    }
+
+    /// Pay the reserved cost of a token to the owner of its issuer identity, if still unpaid.
+    fn settle_token_cost(token_id: T::Hash) -> DispatchResult {
+        let (payer, cost) = match Self::reserved_cost_of_token(token_id) {
+            Some(reserve) => reserve,
+            None => return Ok(()),
+        };
+        let issuer_identity =
+            Self::issuer_identity_of_token(token_id).ok_or(Error::<T>::TokenNotExist)?;
+        let payee =
+            Self::owner_of_identity(issuer_identity).ok_or(Error::<T>::IdentityNotExist)?;
+
+        <pallet_balances::Module<T>>::repatriate_reserved(&payer, &payee, cost, BalanceStatus::Free)?;
+        <AuthorizedTokenCostReserves<T>>::remove(token_id);
+
+        Self::deposit_event(RawEvent::AuthorizedTokenCostPaid(payer, payee, token_id, cost));
         Ok(())
     }
+
+    fn fulfil_authentication_requests(block_number: T::BlockNumber) -> Result<(), &'static str> {
+        let signer = Signer::<T, T::AuthorityId>::any_account();
+        if !signer.can_sign() {
+            return Err(
+                "No local accounts available. Consider adding one via `author_insertKey` RPC.",
+            );
+        }
+
+        for (token_id, (requester, url, _deadline)) in
+            <PendingAuthentications<T>>::iter().take(MAX_AUTHENTICATIONS_PER_RUN)
+        {
+            if !Self::acquire_authentication_lock(token_id, block_number) {
+                continue;
+            }
+
+            let status =
+                match Self::post_token_proof(&signer, requester, token_id, &url, block_number) {
+                    Ok(status) => status,
+                    Err(e) => {
+                        debug::warn!("Failed to post proof of token {:?}: {:?}", token_id, e);
+                        continue;
+                    }
+                };
+
+            match signer.send_signed_transaction(|_account| {
+                Call::submit_authentication_response(token_id, status)
+            }) {
+                Some((acc, Ok(()))) => debug::info!(
+                    "[{:?}] Reported status {} for token {:?}",
+                    acc.id,
+                    status,
+                    token_id
+                ),
+                Some((acc, Err(()))) => {
+                    debug::error!("[{:?}] Failed to submit transaction", acc.id)
+                }
+                None => debug::error!("No local account available to submit transaction"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make sure a request is only answered once every `GracePeriod` blocks, even when
+    /// several offchain workers run concurrently.
+    fn acquire_authentication_lock(token_id: T::Hash, block_number: T::BlockNumber) -> bool {
+        let mut key = b"litentry::authentication::".to_vec();
+        key.extend_from_slice(token_id.as_ref());
+        let val = StorageValueRef::persistent(&key);
+        let res = val.mutate(|last_sent: Option<Option<T::BlockNumber>>| match last_sent {
+            Some(Some(block)) if block_number < block + T::GracePeriod::get() => Err(()),
+            _ => Ok(block_number),
+        });
+        match res {
+            Ok(Ok(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Post a signed proof of `token_id` to `url` and return the response status code.
+    fn post_token_proof(
+        signer: &Signer<T, T::AuthorityId, ForAny>,
+        requester: T::AccountId,
+        token_id: T::Hash,
+        url: &[u8],
+        block_number: T::BlockNumber,
+    ) -> Result<u16, http::Error> {
+        let proof = AuthenticationProof {
+            requester,
+            token_id,
+            owner_identity: Self::owner_identity_of_token(token_id).unwrap_or_default(),
+            issuer_identity: Self::issuer_identity_of_token(token_id).unwrap_or_default(),
+            block_number,
+        };
+        let (account, signature) = signer
+            .sign_message(&proof.encode())
+            .ok_or(http::Error::Unknown)?;
+        let body = (proof, account.public, signature).encode();
+
+        let url = sp_std::str::from_utf8(url).map_err(|_| http::Error::Unknown)?;
+        let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(2_000));
+        let pending = http::Request::post(url, vec![body])
+            .add_header("Content-Type", "application/octet-stream")
+            .deadline(deadline)
+            .send()
+            .map_err(|_| http::Error::IoError)?;
+        let response = pending
+            .try_wait(deadline)
+            .map_err(|_| http::Error::DeadlineReached)??;
+
+        Ok(response.code)
+    }
 }
@@ -1,13 +1,16 @@
 // Creating mock runtime here
 
-use crate::{Module, Trait};
+use crate::{Call, Module, Trait};
+use codec::{Encode, Decode};
 use sp_core::H256;
 use frame_support::{impl_outer_origin, parameter_types, weights::Weight, impl_outer_event};
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+	traits::{BlakeTwo256, IdentityLookup, Extrinsic as ExtrinsicT},
+	testing::{Header, TestSignature, TestXt, UintAuthorityId},
+	Perbill,
 };
 use sp_io::TestExternalities;
-use randomness_collective_flip;
+use pallet_balances as balances;
 
 use frame_system as system;
 
@@ -18,7 +21,7 @@ impl_outer_origin! {
 // For testing the pallet, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of pallets we want to use.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
 pub struct Test;
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
@@ -59,18 +62,101 @@ impl balances::Trait for Test {
 	type AccountStore = frame_system::Module<Test>;
 }
 
+pub type Extrinsic = TestXt<Call<Test>, ()>;
+
+impl frame_system::offchain::SigningTypes for Test {
+	type Public = UintAuthorityId;
+	type Signature = TestSignature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test where
+	Call<Test>: From<LocalCall>,
+{
+	type OverarchingCall = Call<Test>;
+	type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Test where
+	Call<Test>: From<LocalCall>,
+{
+	fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: Call<Test>,
+		_public: UintAuthorityId,
+		_account: u64,
+		nonce: u64,
+	) -> Option<(Call<Test>, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (nonce, ())))
+	}
+}
+
+/// Signs with the `UintAuthorityId` keys set through `UintAuthorityId::set_all_keys`.
+pub struct TestAuthId;
+impl frame_system::offchain::AppCrypto<UintAuthorityId, TestSignature> for TestAuthId {
+	type RuntimeAppPublic = UintAuthorityId;
+	type GenericSignature = TestSignature;
+	type GenericPublic = UintAuthorityId;
+}
+
+parameter_types! {
+	pub const GracePeriod: u64 = 5;
+	pub const AuthenticationTimeout: u64 = 20;
+	pub const MaxDataTypeNameLength: u32 = 16;
+	pub const MaxPayloadLength: u32 = 64;
+	pub const TokenDepositBase: u64 = 2;
+	pub const TokenDepositPerByte: u64 = 1;
+	pub const MaxUrlLength: u32 = 64;
+	pub const IssuerBond: u64 = 10;
+	pub const MaxBatchSize: u32 = 4;
+	pub const MaxAuthenticators: u32 = 4;
+	pub const MaxExpiriesPerBlock: u32 = 4;
+	pub const MaxQuotaResetsPerBlock: u32 = 2;
+	pub const MaxTimeoutsPerBlock: u32 = 2;
+}
+
 impl Trait for Test {
 	type Event = TestEvent;
-	type Randomness = randomness_collective_flip::Module<Test>;
+	type AuthorityId = TestAuthId;
+	type GracePeriod = GracePeriod;
+	type AuthenticationTimeout = AuthenticationTimeout;
+	type MaxDataTypeNameLength = MaxDataTypeNameLength;
+	type MaxPayloadLength = MaxPayloadLength;
+	type TokenDepositBase = TokenDepositBase;
+	type TokenDepositPerByte = TokenDepositPerByte;
+	type MaxUrlLength = MaxUrlLength;
+	type IssuerBond = IssuerBond;
+	type Slashed = ();
+	type MaxBatchSize = MaxBatchSize;
+	type MaxAuthenticators = MaxAuthenticators;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type MaxQuotaResetsPerBlock = MaxQuotaResetsPerBlock;
+	type MaxTimeoutsPerBlock = MaxTimeoutsPerBlock;
+	type IssuerVerifier = TestIssuerVerifier;
+	type AuthenticatorOrigin = system::EnsureRoot<u64>;
+	type SlashOrigin = system::EnsureRoot<u64>;
+	type WeightInfo = ();
+}
+
+/// Every account but 3 may register issuers.
+pub struct TestIssuerVerifier;
+impl crate::IssuerVerifier<u64> for TestIssuerVerifier {
+	fn is_verified(who: &u64) -> bool {
+		*who != 3
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_verified(_: &u64) {}
 }
 
 pub struct ExtBuilder;
 
 impl ExtBuilder {
 	pub fn build() -> TestExternalities {
-		let storage = system::GenesisConfig::default()
+		let mut storage = system::GenesisConfig::default()
 			.build_storage::<Test>()
 			.unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 100), (2, 100), (3, 100)],
+		}.assimilate_storage(&mut storage).unwrap();
 		TestExternalities::from(storage)
 	}
 }
@@ -94,3 +180,5 @@ impl_outer_event! {
 }
 
 pub type LitentryPallet = Module<Test>;
+pub type System = system::Module<Test>;
+pub type Balances = balances::Module<Test>;
@@ -1,32 +1,1445 @@
 // Tests to be written here
 
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
-use sp_core::H256;
+use crate::{
+	AllIdentities, AllTokens, AuthenticationProof, Call, Error, ExternalAccount, ExternalSignature,
+	Identity, IssuedTokens, LinkedAccounts, OwnedTokens, TokenIssuance, TokenIssuanceOf, mock::*,
+};
+use codec::{Encode, Decode};
+use frame_support::{
+	assert_ok, assert_noop, BoundedVec,
+	traits::{Get, GetStorageVersion, OnInitialize, OffchainWorker, StorageVersion},
+};
+use sp_core::{
+	H256,
+	offchain::{OffchainExt, TransactionPoolExt, testing},
+};
+use sp_runtime::testing::{TestSignature, UintAuthorityId};
+use sp_std::convert::TryFrom;
 
 #[test]
 fn it_works_for_default_value() {
 	ExtBuilder::build().execute_with(|| {
-		// Just a dummy test for the dummy function `do_something`
-		// calling the `do_something` function with a value 42
-		let destination:u64 = 2;
-		let dumbData:u64 = 1;
+		let dumb_data: u64 = 1;
+		let datatype: u64 = 1;
 		assert_ok!(LitentryPallet::register_identity(Origin::signed(1)));
 		// asserting that the stored value is equal to what we stored
-		assert_eq!(StructStorage::identities_count(), 1 as u64);
+		assert_eq!(AllIdentities::<Test>::len(), 1 as u64);
 
 		let identity_id = H256::from_low_u64_be(16);
+		let receiver_identity = H256::from_low_u64_be(17);
 
 		assert_ok!(LitentryPallet::register_identity_with_id(Origin::signed(1), identity_id));
+		assert_ok!(LitentryPallet::register_identity_with_id(Origin::signed(2), receiver_identity));
 
-		assert_eq!(StructStorage::identities_count(), 2 as u64);
+		assert_eq!(AllIdentities::<Test>::len(), 3 as u64);
 
-		assert_eq!(StructStorage::identity_by_index(1 as u64), identity_id);
+		assert_eq!(AllIdentities::<Test>::get(1), Some(identity_id));
 
-		assert_ok!(LitentryPallet::issue_token(Origin::signed(1), destination, identity_id, dumbData, dumbData, dumbData, dumbData));
+		assert_ok!(LitentryPallet::register_datatype(Origin::signed(1), identity_id, datatype, bounded(b"email".to_vec()), 32));
+		assert_ok!(LitentryPallet::register_issuer(Origin::signed(1), identity_id));
+		assert_ok!(LitentryPallet::issue_token(Origin::signed(1), receiver_identity, identity_id, dumb_data, bounded(b"data".to_vec()), datatype, dumb_data));
 
-		assert_eq!(StructStorage::tokens_count_of_owner(2), 1 as u64);
+		assert_eq!(OwnedTokens::<Test>::len(&receiver_identity), 1 as u64);
 
-		assert_eq!(StructStorage::tokens_count_of_identity(identity_id), 1 as u64);
+		assert_eq!(IssuedTokens::<Test>::len(&identity_id), 1 as u64);
+	});
+}
+
+fn setup_identities() -> (H256, H256) {
+	let issuer_identity = H256::from_low_u64_be(16);
+	let receiver_identity = H256::from_low_u64_be(17);
+	assert_ok!(LitentryPallet::register_identity_with_id(Origin::signed(1), issuer_identity));
+	assert_ok!(LitentryPallet::register_identity_with_id(Origin::signed(2), receiver_identity));
+	assert_ok!(LitentryPallet::register_datatype(Origin::signed(1), issuer_identity, 1, bounded(b"email".to_vec()), 8));
+	assert_ok!(LitentryPallet::register_issuer(Origin::signed(1), issuer_identity));
+	(issuer_identity, receiver_identity)
+}
+
+fn issue(issuer_identity: H256, receiver_identity: H256) -> H256 {
+	issue_expiring_at(issuer_identity, receiver_identity, 100)
+}
+
+fn issue_expiring_at(issuer_identity: H256, receiver_identity: H256, expired: u64) -> H256 {
+	issue_with(issuer_identity, receiver_identity, 0, expired)
+}
+
+fn issue_with(issuer_identity: H256, receiver_identity: H256, cost: u64, expired: u64) -> H256 {
+	assert_ok!(LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, cost, bounded(b"data".to_vec()), 1, expired));
+	let count = IssuedTokens::<Test>::len(&issuer_identity);
+	IssuedTokens::<Test>::get(&issuer_identity, count - 1).unwrap()
+}
+
+fn bounded_url(url: &[u8]) -> BoundedVec<u8, MaxUrlLength> {
+	BoundedVec::try_from(url.to_vec()).unwrap()
+}
+
+fn bounded<T, S: Get<u32>>(items: Vec<T>) -> BoundedVec<T, S> {
+	BoundedVec::try_from(items).unwrap()
+}
+
+#[test]
+fn recall_token_keeps_indexes_consistent() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let first = issue(issuer_identity, receiver_identity);
+		let second = issue(issuer_identity, receiver_identity);
+		let third = issue(issuer_identity, receiver_identity);
+
+		assert_ok!(LitentryPallet::recall_token(Origin::signed(1), first));
+
+		assert_eq!(AllTokens::<Test>::len(), 2);
+		assert_eq!(OwnedTokens::<Test>::len(&receiver_identity), 2);
+		assert_eq!(IssuedTokens::<Test>::len(&issuer_identity), 2);
+		assert_eq!(StructStorage::owner_identity_of_token(first), None);
+		assert_eq!(StructStorage::issuer_identity_of_token(first), None);
+
+		// The last token was swapped into the freed slot.
+		assert_eq!(AllTokens::<Test>::get(0), Some(third));
+		assert_eq!(AllTokens::<Test>::position(&third), Some(0));
+		assert_eq!(OwnedTokens::<Test>::get(&receiver_identity, 0), Some(third));
+		assert_eq!(OwnedTokens::<Test>::position(&receiver_identity, &third), Some(0));
+		assert_eq!(IssuedTokens::<Test>::get(&issuer_identity, 0), Some(third));
+		assert_eq!(IssuedTokens::<Test>::position(&issuer_identity, &third), Some(0));
+		assert_eq!(OwnedTokens::<Test>::get(&receiver_identity, 1), Some(second));
+	});
+}
+
+#[test]
+fn recall_token_requires_issuer_owner() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+
+		assert_noop!(
+			LitentryPallet::recall_token(Origin::signed(2), token),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_noop!(
+			LitentryPallet::recall_token(Origin::signed(1), H256::from_low_u64_be(99)),
+			Error::<Test>::TokenNotExist
+		);
+	});
+}
+
+#[test]
+fn recall_all_identity_token_works() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		issue(issuer_identity, receiver_identity);
+		issue(issuer_identity, receiver_identity);
+
+		assert_noop!(
+			LitentryPallet::recall_all_identity_token(Origin::signed(2), issuer_identity, 2),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_noop!(
+			LitentryPallet::recall_all_identity_token(Origin::signed(1), issuer_identity, 1),
+			Error::<Test>::TooManyTokens
+		);
+		assert_ok!(LitentryPallet::recall_all_identity_token(Origin::signed(1), issuer_identity, 2));
+
+		assert_eq!(AllTokens::<Test>::len(), 0);
+		assert_eq!(OwnedTokens::<Test>::len(&receiver_identity), 0);
+		assert_eq!(IssuedTokens::<Test>::len(&issuer_identity), 0);
+	});
+}
+
+#[test]
+fn recall_all_identity_token_is_charged_per_token() {
+	use frame_support::weights::GetDispatchInfo;
+
+	let identity = H256::from_low_u64_be(16);
+	let few = Call::<Test>::recall_all_identity_token(identity, 1).get_dispatch_info();
+	let many = Call::<Test>::recall_all_identity_token(identity, 100).get_dispatch_info();
+	assert!(many.weight > few.weight);
+}
+
+#[test]
+fn issue_token_rejects_past_expiry() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		System::set_block_number(5);
+
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, 1, bounded(b"data".to_vec()), 1, 5),
+			Error::<Test>::InvalidExpiry
+		);
+	});
+}
+
+#[test]
+fn expired_token_is_rejected_and_swept() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let expiring = issue_expiring_at(issuer_identity, receiver_identity, 3);
+		let lasting = issue_expiring_at(issuer_identity, receiver_identity, 10);
+
+		System::set_block_number(2);
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), expiring, bounded_url(b"https://litentry.com")));
+
+		System::set_block_number(3);
+		assert_noop!(
+			LitentryPallet::request_authentication(Origin::signed(2), expiring, bounded_url(b"https://litentry.com")),
+			Error::<Test>::TokenExpired
+		);
+		assert_noop!(
+			LitentryPallet::transfer_token(Origin::signed(2), issuer_identity, expiring),
+			Error::<Test>::TokenExpired
+		);
+
+		LitentryPallet::on_initialize(3);
+
+		assert_eq!(StructStorage::owner_identity_of_token(expiring), None);
+		assert_eq!(StructStorage::tokens_expiring_at(3), Vec::<H256>::new());
+		assert_eq!(AllTokens::<Test>::len(), 1);
+		assert_eq!(AllTokens::<Test>::get(0), Some(lasting));
+		assert_eq!(OwnedTokens::<Test>::len(&receiver_identity), 1);
+		assert_eq!(IssuedTokens::<Test>::len(&issuer_identity), 1);
+	});
+}
+
+#[test]
+fn tokens_may_not_expire_at_a_full_block() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		for _ in 0..4 {
+			issue_expiring_at(issuer_identity, receiver_identity, 3);
+		}
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 1, 3),
+			Error::<Test>::TooManyExpiries
+		);
+		issue_expiring_at(issuer_identity, receiver_identity, 4);
+
+		System::set_block_number(3);
+		LitentryPallet::on_initialize(3);
+
+		assert_eq!(AllTokens::<Test>::len(), 1);
+		assert_eq!(StructStorage::tokens_expiring_at(4).len(), 1);
+	});
+}
+
+#[test]
+fn recalled_token_is_skipped_by_sweep() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue_expiring_at(issuer_identity, receiver_identity, 3);
+		assert_ok!(LitentryPallet::recall_token(Origin::signed(1), token));
+
+		System::set_block_number(3);
+		LitentryPallet::on_initialize(3);
+
+		assert_eq!(AllTokens::<Test>::len(), 0);
+	});
+}
+
+#[test]
+fn token_cost_is_reserved_on_acceptance_then_paid_on_use() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue_with(issuer_identity, receiver_identity, 30, 100);
+		let url = bounded_url(b"https://litentry.com");
+
+		// Nothing is taken from the receiver without its consent.
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert!(StructStorage::awaits_acceptance(token));
+		assert_noop!(
+			LitentryPallet::request_authentication(Origin::signed(2), token, url.clone()),
+			Error::<Test>::TokenNotAccepted
+		);
+		assert_noop!(
+			LitentryPallet::accept_token(Origin::signed(1), token),
+			Error::<Test>::NotIdentityController
+		);
+
+		assert_ok!(LitentryPallet::accept_token(Origin::signed(2), token));
+		assert_eq!(Balances::reserved_balance(2), 30);
+		assert_eq!(Balances::free_balance(2), 70);
+		assert_eq!(StructStorage::reserved_cost_of_token(token), Some((2, 30)));
+		assert_noop!(
+			LitentryPallet::accept_token(Origin::signed(2), token),
+			Error::<Test>::TokenAlreadyAccepted
+		);
+
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), token, url.clone()));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::free_balance(2), 70);
+		// The issuer keeps its storage deposit of 2 + 4 bytes and its bond of 10 reserved.
+		assert_eq!(Balances::free_balance(1), 114);
+		assert_eq!(StructStorage::reserved_cost_of_token(token), None);
+
+		// Only the first use is charged.
+		assert_ok!(LitentryPallet::set_authenticators(Origin::ROOT, bounded(vec![7])));
+		assert_ok!(LitentryPallet::submit_authentication_response(Origin::signed(7), token, 200));
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), token, url));
+		assert_eq!(Balances::free_balance(1), 114);
+	});
+}
+
+#[test]
+fn token_cost_is_refunded_on_recall() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue_with(issuer_identity, receiver_identity, 30, 100);
+		assert_ok!(LitentryPallet::accept_token(Origin::signed(2), token));
+
+		assert_ok!(LitentryPallet::recall_token(Origin::signed(1), token));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::free_balance(2), 100);
+		assert_eq!(StructStorage::reserved_cost_of_token(token), None);
+	});
+}
+
+#[test]
+fn accept_token_fails_when_receiver_cannot_pay() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue_with(issuer_identity, receiver_identity, 101, 100);
+
+		assert!(LitentryPallet::accept_token(Origin::signed(2), token).is_err());
+		assert!(StructStorage::awaits_acceptance(token));
+		assert_eq!(StructStorage::reserved_cost_of_token(token), None);
+		assert_eq!(Balances::reserved_balance(2), 0);
+	});
+}
+
+#[test]
+fn register_datatype_validates_schema() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, _) = setup_identities();
+
+		assert_noop!(
+			LitentryPallet::register_datatype(Origin::signed(2), issuer_identity, 2, bounded(b"url".to_vec()), 8),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_noop!(
+			LitentryPallet::register_datatype(Origin::signed(1), issuer_identity, 1, bounded(b"url".to_vec()), 8),
+			Error::<Test>::DataTypeExists
+		);
+		// A name longer than MaxDataTypeNameLength cannot even be decoded into a call.
+		assert!(BoundedVec::<u8, MaxDataTypeNameLength>::try_from(vec![0u8; 17]).is_err());
+		let call_index =
+			Call::<Test>::register_datatype(issuer_identity, 2, bounded(b"url".to_vec()), 8).encode()[0];
+		let encoded = (call_index, issuer_identity, 2u64, vec![0u8; 17], 8u32).encode();
+		assert!(Call::<Test>::decode(&mut &encoded[..]).is_err());
+		assert_noop!(
+			LitentryPallet::register_datatype(Origin::signed(1), issuer_identity, 2, bounded(b"url".to_vec()), 65),
+			Error::<Test>::DataTypeTooLarge
+		);
+	});
+}
+
+#[test]
+fn issue_token_validates_payload_against_schema() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 2, 100),
+			Error::<Test>::DataTypeNotExist
+		);
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, 0, bounded(vec![0u8; 9]), 1, 100),
+			Error::<Test>::PayloadTooLarge
+		);
+	});
+}
+
+#[test]
+fn token_deposit_scales_with_payload_and_is_refunded() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		assert_ok!(LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, 0, bounded(vec![0u8; 8]), 1, 100));
+		let token = IssuedTokens::<Test>::get(&issuer_identity, 0).unwrap();
+
+		assert_eq!(StructStorage::deposit_of_token(token), Some((1, 10)));
+		// The deposit and the issuer bond.
+		assert_eq!(Balances::reserved_balance(1), 20);
+
+		assert_ok!(LitentryPallet::recall_token(Origin::signed(1), token));
+		assert_eq!(StructStorage::deposit_of_token(token), None);
+		assert_eq!(Balances::reserved_balance(1), 10);
+	});
+}
+
+#[test]
+fn failed_issue_keeps_no_deposit() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+
+		// Only the first of the two deposits of 2 + 4 bytes can be reserved.
+		assert_ok!(Balances::transfer(Origin::signed(1), 3, 80));
+		assert_noop!(
+			LitentryPallet::issue_tokens_batch(Origin::signed(1), issuer_identity, bounded(vec![issuance(receiver_identity, 0, 1); 2])),
+			pallet_balances::Error::<Test, pallet_balances::DefaultInstance>::InsufficientBalance
+		);
+		// Only the issuer bond.
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(IssuedTokens::<Test>::len(&issuer_identity), 0);
+	});
+}
+
+#[test]
+fn request_authentication_is_pending_until_answered() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+		let url = bounded_url(b"https://litentry.com/auth");
+
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), token, url.clone()));
+		assert_eq!(StructStorage::pending_authentication(token), Some((2, url.clone(), 20)));
+		assert_noop!(
+			LitentryPallet::request_authentication(Origin::signed(2), token, url),
+			Error::<Test>::AuthenticationPending
+		);
+
+		assert_noop!(
+			LitentryPallet::submit_authentication_response(Origin::signed(7), token, 200),
+			Error::<Test>::NotAuthenticator
+		);
+		assert_ok!(LitentryPallet::set_authenticators(Origin::ROOT, bounded(vec![7])));
+		assert_ok!(LitentryPallet::submit_authentication_response(Origin::signed(7), token, 200));
+		assert_eq!(StructStorage::pending_authentication(token), None);
+		assert_noop!(
+			LitentryPallet::submit_authentication_response(Origin::signed(7), token, 200),
+			Error::<Test>::NoPendingAuthentication
+		);
+	});
+}
+
+#[test]
+fn unanswered_authentication_request_times_out() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+		let url = bounded_url(b"https://litentry.com/auth");
+
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), token, url.clone()));
+		assert_eq!(StructStorage::authentications_timing_out_at(20), vec![token]);
+
+		System::set_block_number(19);
+		LitentryPallet::on_initialize(19);
+		assert!(StructStorage::pending_authentication(token).is_some());
+
+		System::set_block_number(20);
+		LitentryPallet::on_initialize(20);
+		assert_eq!(StructStorage::pending_authentication(token), None);
+		assert!(System::events().iter().any(|record| {
+			record.event == TestEvent::struct_storage(crate::RawEvent::AuthenticationTimedOut(2, token))
+		}));
+
+		// The token can be authenticated again.
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), token, url.clone()));
+		assert_eq!(StructStorage::pending_authentication(token), Some((2, url, 40)));
+	});
+}
+
+#[test]
+fn timeouts_past_the_block_limit_are_carried_over() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let tokens: Vec<H256> = (0..3).map(|_| issue(issuer_identity, receiver_identity)).collect();
+		for token in &tokens {
+			assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), *token, bounded_url(b"https://litentry.com")));
+		}
+
+		System::set_block_number(20);
+		LitentryPallet::on_initialize(20);
+		assert_eq!(StructStorage::pending_authentication(tokens[0]), None);
+		assert_eq!(StructStorage::pending_authentication(tokens[1]), None);
+		assert!(StructStorage::pending_authentication(tokens[2]).is_some());
+		assert_eq!(StructStorage::authentications_timing_out_at(21), vec![tokens[2]]);
+
+		System::set_block_number(21);
+		LitentryPallet::on_initialize(21);
+		assert_eq!(StructStorage::pending_authentication(tokens[2]), None);
+	});
+}
+
+#[test]
+fn runtime_upgrade_adds_authentication_deadlines() {
+	use frame_support::{Blake2_128Concat, StorageHasher, traits::OnRuntimeUpgrade};
+	use frame_support::storage::migration::put_storage_value;
+
+	ExtBuilder::build().execute_with(|| {
+		let token = H256::from_low_u64_be(1);
+		let url = bounded_url(b"https://litentry.com/auth");
+		// Storage version 4, whose requests had no deadline.
+		LitentryPallet::put_on_chain_storage_version(StorageVersion::new(4));
+		put_storage_value(
+			b"LitentryStorage",
+			b"PendingAuthentications",
+			&token.using_encoded(Blake2_128Concat::hash),
+			(2u64, url.clone()),
+		);
+
+		System::set_block_number(5);
+		LitentryPallet::on_runtime_upgrade();
+
+		assert_eq!(StructStorage::pending_authentication(token), Some((2, url, 25)));
+		assert_eq!(StructStorage::authentications_timing_out_at(25), vec![token]);
+		assert_eq!(LitentryPallet::on_chain_storage_version(), StorageVersion::new(6));
+	});
+}
+
+#[test]
+fn runtime_upgrade_moves_global_sets_into_value_items() {
+	use frame_support::{Blake2_128Concat, StorageHasher, traits::OnRuntimeUpgrade};
+	use frame_support::storage::migration::{get_storage_value, put_storage_value};
+
+	ExtBuilder::build().execute_with(|| {
+		let first = H256::from_low_u64_be(1);
+		let second = H256::from_low_u64_be(2);
+		// Storage version 5, whose global sets were kept under a `()` key.
+		LitentryPallet::put_on_chain_storage_version(StorageVersion::new(5));
+		let partition = ().using_encoded(Blake2_128Concat::hash);
+		put_storage_value(b"LitentryStorage", b"AllIdentitiesCount", &partition, 2u64);
+		for (position, identity_id) in [first, second].iter().enumerate() {
+			let mut item = partition.clone();
+			item.extend((position as u64).using_encoded(Blake2_128Concat::hash));
+			put_storage_value(b"LitentryStorage", b"AllIdentitiesItems", &item, *identity_id);
+			let mut index = partition.clone();
+			index.extend(identity_id.using_encoded(Blake2_128Concat::hash));
+			put_storage_value(b"LitentryStorage", b"AllIdentitiesIndex", &index, position as u64);
+		}
+
+		LitentryPallet::on_runtime_upgrade();
+
+		assert_eq!(AllIdentities::<Test>::iter().collect::<Vec<_>>(), vec![first, second]);
+		assert_eq!(AllIdentities::<Test>::position(&second), Some(1));
+		assert_eq!(AllTokens::<Test>::len(), 0);
+		let mut old_index = partition.clone();
+		old_index.extend(first.using_encoded(Blake2_128Concat::hash));
+		assert_eq!(
+			get_storage_value::<u64>(b"LitentryStorage", b"AllIdentitiesIndex", &old_index),
+			None,
+		);
+		assert_eq!(LitentryPallet::on_chain_storage_version(), StorageVersion::new(6));
+	});
+}
+
+#[test]
+fn offchain_worker_posts_signed_proof_and_reports_status() {
+	const AUTHENTICATOR: u64 = 7;
+	let (offchain, offchain_state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	UintAuthorityId::set_all_keys(vec![AUTHENTICATOR]);
+
+	let mut t = ExtBuilder::build();
+	t.register_extension(OffchainExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+
+	t.execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), token, bounded_url(b"https://litentry.com/auth")));
+
+		let proof = AuthenticationProof {
+			requester: 2,
+			token_id: token,
+			owner_identity: receiver_identity,
+			issuer_identity,
+			block_number: 1,
+		};
+		let signature = TestSignature(AUTHENTICATOR, proof.encode());
+		offchain_state.write().expect_request(0, testing::PendingRequest {
+			method: "POST".into(),
+			uri: "https://litentry.com/auth".into(),
+			headers: vec![("Content-Type".into(), "application/octet-stream".into())],
+			body: (proof, UintAuthorityId(AUTHENTICATOR), signature).encode(),
+			response: Some(vec![]),
+			sent: true,
+			..Default::default()
+		});
+
+		LitentryPallet::offchain_worker(1);
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert!(tx.signature.is_some());
+		assert_eq!(tx.call, Call::submit_authentication_response(token, 200));
+
+		// The request is not answered again within the grace period.
+		LitentryPallet::offchain_worker(2);
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn query_helpers_page_through_tokens() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let first = issue(issuer_identity, receiver_identity);
+		let second = issue(issuer_identity, receiver_identity);
+		let third = issue(issuer_identity, receiver_identity);
+
+		assert_eq!(LitentryPallet::identities_of(&1), vec![issuer_identity]);
+		assert_eq!(LitentryPallet::tokens_of_identity(receiver_identity, 0, 2), vec![first, second]);
+		assert_eq!(LitentryPallet::tokens_of_identity(receiver_identity, 2, 2), vec![third]);
+		assert_eq!(LitentryPallet::tokens_issued_by(issuer_identity, 3, 2), Vec::<H256>::new());
+
+		let (token, owner_identity, token_issuer) = LitentryPallet::token_details(second).unwrap();
+		assert_eq!(token.id, second);
+		assert_eq!(owner_identity, receiver_identity);
+		assert_eq!(token_issuer, issuer_identity);
+		assert!(LitentryPallet::token_details(H256::from_low_u64_be(99)).is_none());
+	});
+}
+
+#[test]
+fn runtime_upgrade_migrates_indexes_to_sets() {
+	use frame_support::{Blake2_128Concat, StorageHasher, traits::OnRuntimeUpgrade};
+	use frame_support::storage::migration::{get_storage_value, put_storage_value};
+
+	fn put_item<K: Encode, V: Encode>(item: &[u8], key: K, value: V) {
+		put_storage_value(b"LitentryStorage", item, &key.using_encoded(Blake2_128Concat::hash), value);
+	}
+
+	ExtBuilder::build().execute_with(|| {
+		let identity = H256::from_low_u64_be(16);
+		let other_identity = H256::from_low_u64_be(17);
+		let (first, second) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+		// Two identities of account 1 and two tokens held and issued by the first one.
+		put_storage_value(b"LitentryStorage", b"IdentitiesCount", &[], 2u64);
+		put_item(b"IdentitiesArray", 0u64, identity);
+		put_item(b"IdentitiesArray", 1u64, other_identity);
+		put_item(b"IdentitiesIndex", other_identity, 1u64);
+		put_item(b"OwnedIdentitiesCount", 1u64, 2u64);
+		put_item(b"OwnedIdentitiesArray", (1u64, 0u64), identity);
+		put_item(b"OwnedIdentitiesArray", (1u64, 1u64), other_identity);
+		put_storage_value(b"LitentryStorage", b"AuthorizedTokensCount", &[], 2u64);
+		put_item(b"AuthorizedTokensArray", 0u64, first);
+		put_item(b"AuthorizedTokensArray", 1u64, second);
+		put_item(b"OwnedAuthorizedTokensCount", identity, 2u64);
+		put_item(b"OwnedAuthorizedTokensArray", (identity, 0u64), first);
+		put_item(b"OwnedAuthorizedTokensArray", (identity, 1u64), second);
+		put_item(b"IdentityAuthorizedTokensCount", identity, 1u64);
+		put_item(b"IdentityAuthorizedTokensArray", (identity, 0u64), second);
+
+		LitentryPallet::on_runtime_upgrade();
+
+		assert_eq!(AllIdentities::<Test>::iter().collect::<Vec<_>>(), vec![identity, other_identity]);
+		assert_eq!(LitentryPallet::identities_of(&1), vec![identity, other_identity]);
+		assert_eq!(AllTokens::<Test>::iter().collect::<Vec<_>>(), vec![first, second]);
+		assert_eq!(LitentryPallet::tokens_of_identity(identity, 0, 10), vec![first, second]);
+		assert_eq!(LitentryPallet::tokens_issued_by(identity, 0, 10), vec![second]);
+
+		let old_index = other_identity.using_encoded(Blake2_128Concat::hash);
+		assert_eq!(get_storage_value::<u64>(b"LitentryStorage", b"IdentitiesIndex", &old_index), None);
+		assert_eq!(get_storage_value::<u64>(b"LitentryStorage", b"IdentitiesCount", &[]), None);
+
+		// The migration runs once.
+		put_storage_value(b"LitentryStorage", b"IdentitiesCount", &[], 1u64);
+		put_item(b"IdentitiesArray", 0u64, H256::from_low_u64_be(18));
+		LitentryPallet::on_runtime_upgrade();
+		assert_eq!(AllIdentities::<Test>::len(), 2);
+	});
+}
+
+#[test]
+fn runtime_upgrade_gives_tokens_payloads_and_expiry_blocks() {
+	use frame_support::{Blake2_128Concat, StorageHasher, traits::OnRuntimeUpgrade};
+	use frame_support::storage::migration::put_storage_value;
+
+	ExtBuilder::build().execute_with(|| {
+		let token = H256::from_low_u64_be(1);
+		let expired_token = H256::from_low_u64_be(2);
+		let crowded: Vec<H256> = (3..8).map(H256::from_low_u64_be).collect();
+		// Tokens of the first layout: `id`, `cost`, then `data`, `datatype` and `expired`
+		// as `u64`s.
+		let mut tokens = vec![(token, 100u64), (expired_token, 3)];
+		tokens.extend(crowded.iter().map(|id| (*id, 200)));
+		for (id, expired) in tokens.iter() {
+			put_storage_value(
+				b"LitentryStorage",
+				b"AuthorizedTokens",
+				&id.using_encoded(Blake2_128Concat::hash),
+				(*id, 5u64, 7u64, 1u64, *expired),
+			);
+		}
+
+		System::set_block_number(5);
+		LitentryPallet::on_runtime_upgrade();
+
+		let migrated = StructStorage::token(token);
+		assert_eq!(migrated.id, token);
+		assert_eq!(migrated.cost, 5);
+		assert_eq!(migrated.data, 7u64.encode());
+		assert_eq!(migrated.datatype, 1);
+		assert_eq!(migrated.expired, 100);
+		assert_eq!(StructStorage::tokens_expiring_at(100), vec![token]);
+		// A token that expired before the upgrade is swept with the next block.
+		assert_eq!(StructStorage::token(expired_token).expired, 3);
+		assert_eq!(StructStorage::tokens_expiring_at(6), vec![expired_token]);
+		// Buckets hold at most `MaxExpiriesPerBlock` tokens, the rest are swept later.
+		assert_eq!(StructStorage::tokens_expiring_at(200).len(), 4);
+		assert_eq!(StructStorage::tokens_expiring_at(201).len(), 1);
+		assert_eq!(LitentryPallet::on_chain_storage_version(), StorageVersion::new(6));
+	});
+}
+
+#[test]
+fn transfer_identity_moves_ownership() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+
+		assert_noop!(
+			LitentryPallet::transfer_identity(Origin::signed(2), issuer_identity, 3),
+			Error::<Test>::NotIdentityOwner
+		);
+		// The bond of an issuer stays with the account it was reserved from.
+		assert_noop!(
+			LitentryPallet::transfer_identity(Origin::signed(1), issuer_identity, 3),
+			Error::<Test>::IssuerNotTransferable
+		);
+		assert_ok!(LitentryPallet::unregister_issuer(Origin::signed(1), issuer_identity));
+		assert_ok!(LitentryPallet::transfer_identity(Origin::signed(1), issuer_identity, 3));
+
+		assert_eq!(StructStorage::owner_of_identity(issuer_identity), Some(3));
+		assert_eq!(LitentryPallet::identities_of(&1), Vec::<H256>::new());
+		assert_eq!(LitentryPallet::identities_of(&3), vec![issuer_identity]);
+		// The tokens issued by the identity are now recalled by its new owner.
+		assert_noop!(
+			LitentryPallet::recall_token(Origin::signed(1), token),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_ok!(LitentryPallet::recall_token(Origin::signed(3), token));
+	});
+}
+
+#[test]
+fn transfer_identity_revokes_delegates() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		assert_ok!(LitentryPallet::add_delegate(Origin::signed(1), issuer_identity, 2));
+		assert_ok!(LitentryPallet::unregister_issuer(Origin::signed(1), issuer_identity));
+
+		assert_ok!(LitentryPallet::transfer_identity(Origin::signed(1), issuer_identity, 3));
+
+		assert!(!StructStorage::is_delegate(issuer_identity, 2));
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(2), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 1, 100),
+			Error::<Test>::NotIdentityController
+		);
+	});
+}
+
+#[test]
+fn delegate_can_issue_and_transfer_tokens() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(3), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 1, 100),
+			Error::<Test>::NotIdentityController
+		);
+		assert_noop!(
+			LitentryPallet::add_delegate(Origin::signed(3), issuer_identity, 3),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_ok!(LitentryPallet::add_delegate(Origin::signed(1), issuer_identity, 3));
+		assert_noop!(
+			LitentryPallet::add_delegate(Origin::signed(1), issuer_identity, 3),
+			Error::<Test>::DelegateExists
+		);
+		assert_ok!(LitentryPallet::issue_token(Origin::signed(3), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 1, 100));
+
+		// A delegate of the holder moves the token on its behalf.
+		let token = IssuedTokens::<Test>::get(&issuer_identity, 0).unwrap();
+		assert_ok!(LitentryPallet::add_delegate(Origin::signed(2), receiver_identity, 3));
+		assert_ok!(LitentryPallet::transfer_token(Origin::signed(3), issuer_identity, token));
+		assert_eq!(StructStorage::owner_identity_of_token(token), Some(issuer_identity));
+
+		assert_ok!(LitentryPallet::remove_delegate(Origin::signed(1), issuer_identity, 3));
+		assert_noop!(
+			LitentryPallet::remove_delegate(Origin::signed(1), issuer_identity, 3),
+			Error::<Test>::DelegateNotExist
+		);
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(3), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 1, 100),
+			Error::<Test>::NotIdentityController
+		);
+	});
+}
+
+#[test]
+fn destroy_identity_revokes_its_tokens() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let issued = issue(issuer_identity, receiver_identity);
+		let held = issue(issuer_identity, issuer_identity);
+		assert_ok!(LitentryPallet::register_datatype(Origin::signed(2), receiver_identity, 1, bounded(b"email".to_vec()), 8));
+		assert_ok!(LitentryPallet::register_issuer(Origin::signed(2), receiver_identity));
+		assert_ok!(LitentryPallet::issue_token(Origin::signed(2), issuer_identity, receiver_identity, 0, bounded(b"data".to_vec()), 1, 100));
+		assert_ok!(LitentryPallet::add_delegate(Origin::signed(1), issuer_identity, 3));
+
+		assert_noop!(
+			LitentryPallet::destroy_identity(Origin::signed(2), issuer_identity, 2, 2, 0),
+			Error::<Test>::NotIdentityOwner
+		);
+		// It issued two tokens and holds two, one of its own.
+		assert_noop!(
+			LitentryPallet::destroy_identity(Origin::signed(1), issuer_identity, 1, 2, 0),
+			Error::<Test>::TooManyTokens
+		);
+		assert_noop!(
+			LitentryPallet::destroy_identity(Origin::signed(1), issuer_identity, 2, 1, 0),
+			Error::<Test>::TooManyTokens
+		);
+		let linked = ExternalAccount::Sr25519(sp_core::sr25519::Public::from_raw([1; 32]));
+		assert_ok!(LinkedAccounts::<Test>::insert(&issuer_identity, &linked));
+		<crate::ExternalAccountIdentity<Test>>::insert(&linked, issuer_identity);
+		assert_noop!(
+			LitentryPallet::destroy_identity(Origin::signed(1), issuer_identity, 2, 2, 0),
+			Error::<Test>::TooManyLinkedAccounts
+		);
+		assert_ok!(LitentryPallet::destroy_identity(Origin::signed(1), issuer_identity, 2, 2, 1));
+
+		assert_eq!(StructStorage::owner_of_identity(issuer_identity), None);
+		assert_eq!(AllIdentities::<Test>::iter().collect::<Vec<_>>(), vec![receiver_identity]);
+		assert_eq!(LitentryPallet::identities_of(&1), Vec::<H256>::new());
+		assert_eq!(StructStorage::owner_identity_of_token(issued), None);
+		assert_eq!(StructStorage::owner_identity_of_token(held), None);
+		assert_eq!(AllTokens::<Test>::len(), 0);
+		assert_eq!(StructStorage::datatype_schema(issuer_identity, 1), None);
+		assert!(!StructStorage::is_delegate(issuer_identity, 3));
+		assert_eq!(StructStorage::identity_of_external_account(&linked), None);
+		// Every deposit, unpaid cost and the issuer bond was returned.
+		assert_eq!(StructStorage::issuer(issuer_identity), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		// Only the bond of the remaining issuer.
+		assert_eq!(Balances::reserved_balance(2), 10);
+	});
+}
+
+fn ethereum_account(secret: &secp256k1::SecretKey) -> ExternalAccount {
+	let public = secp256k1::PublicKey::from_secret_key(secret);
+	let hash = sp_core::hashing::keccak_256(&public.serialize()[1..]);
+	ExternalAccount::Ethereum(sp_core::H160::from_slice(&hash[12..]))
+}
+
+// Sign `message` as an Ethereum wallet does with `personal_sign`.
+fn ethereum_sign(secret: &secp256k1::SecretKey, message: &[u8]) -> ExternalSignature {
+	let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+	prefixed.extend_from_slice(message);
+	let digest = sp_core::hashing::keccak_256(&prefixed);
+	let (signature, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&digest), secret);
+	let mut raw = [0u8; 65];
+	raw[..64].copy_from_slice(&signature.serialize());
+	raw[64] = recovery_id.serialize() + 27;
+	ExternalSignature::Ecdsa(sp_core::ecdsa::Signature::from_raw(raw))
+}
+
+#[test]
+fn link_account_verifies_signatures() {
+	use sp_core::Pair;
+	use sp_keyring::{AccountKeyring, Ed25519Keyring};
+
+	ExtBuilder::build().execute_with(|| {
+		let (identity, _) = setup_identities();
+		let message = LitentryPallet::link_message(identity, LitentryPallet::link_nonce(identity));
+
+		let sr25519_account = ExternalAccount::Sr25519(AccountKeyring::Alice.public());
+		let ed25519_account = ExternalAccount::Ed25519(Ed25519Keyring::Bob.public());
+		let ethereum_secret = secp256k1::SecretKey::parse(&[1u8; 32]).unwrap();
+		let ethereum_account = ethereum_account(&ethereum_secret);
+
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(2),
+				identity,
+				sr25519_account.clone(),
+				ExternalSignature::Sr25519(AccountKeyring::Alice.sign(&message)),
+			),
+			Error::<Test>::NotIdentityOwner
+		);
+		// Signed by another key, or for another nonce.
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(1),
+				identity,
+				sr25519_account.clone(),
+				ExternalSignature::Sr25519(AccountKeyring::Bob.sign(&message)),
+			),
+			Error::<Test>::InvalidLinkSignature
+		);
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(1),
+				identity,
+				ed25519_account.clone(),
+				ExternalSignature::Ed25519(Ed25519Keyring::Bob.sign(&LitentryPallet::link_message(identity, 1))),
+			),
+			Error::<Test>::InvalidLinkSignature
+		);
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(1),
+				identity,
+				ethereum_account.clone(),
+				ExternalSignature::Sr25519(AccountKeyring::Alice.sign(&message)),
+			),
+			Error::<Test>::InvalidLinkSignature
+		);
+		// Signed by the right key, but without the Ethereum message prefix.
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(1),
+				identity,
+				ethereum_account.clone(),
+				ExternalSignature::Ecdsa(sp_core::ecdsa::Pair::from_seed(&[1u8; 32]).sign(&message)),
+			),
+			Error::<Test>::InvalidLinkSignature
+		);
+
+		assert_ok!(LitentryPallet::link_account(
+			Origin::signed(1),
+			identity,
+			sr25519_account.clone(),
+			ExternalSignature::Sr25519(AccountKeyring::Alice.sign(&message)),
+		));
+		// Each link signs the next nonce of the identity.
+		assert_eq!(LitentryPallet::link_nonce(identity), 1);
+		assert_ok!(LitentryPallet::link_account(
+			Origin::signed(1),
+			identity,
+			ed25519_account.clone(),
+			ExternalSignature::Ed25519(Ed25519Keyring::Bob.sign(&LitentryPallet::link_message(identity, 1))),
+		));
+		assert_ok!(LitentryPallet::link_account(
+			Origin::signed(1),
+			identity,
+			ethereum_account.clone(),
+			ethereum_sign(&ethereum_secret, &LitentryPallet::link_message(identity, 2)),
+		));
+
+		assert_eq!(
+			LinkedAccounts::<Test>::iter(identity).collect::<Vec<_>>(),
+			vec![sr25519_account.clone(), ed25519_account, ethereum_account.clone()]
+		);
+		assert_eq!(StructStorage::identity_of_external_account(&ethereum_account), Some(identity));
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(1),
+				identity,
+				sr25519_account,
+				ExternalSignature::Sr25519(AccountKeyring::Alice.sign(&message)),
+			),
+			Error::<Test>::AccountAlreadyLinked
+		);
+	});
+}
+
+#[test]
+fn unlink_removes_link() {
+	use sp_keyring::AccountKeyring;
+
+	ExtBuilder::build().execute_with(|| {
+		let (identity, receiver_identity) = setup_identities();
+		let account = ExternalAccount::Sr25519(AccountKeyring::Alice.public());
+		let message = LitentryPallet::link_message(identity, LitentryPallet::link_nonce(identity));
+		assert_ok!(LitentryPallet::link_account(
+			Origin::signed(1),
+			identity,
+			account.clone(),
+			ExternalSignature::Sr25519(AccountKeyring::Alice.sign(&message)),
+		));
+
+		assert_noop!(
+			LitentryPallet::unlink(Origin::signed(2), receiver_identity, account.clone()),
+			Error::<Test>::AccountNotLinked
+		);
+		assert_ok!(LitentryPallet::unlink(Origin::signed(1), identity, account.clone()));
+
+		assert_eq!(StructStorage::identity_of_external_account(&account), None);
+		assert_eq!(LinkedAccounts::<Test>::len(&identity), 0);
+		assert_noop!(
+			LitentryPallet::unlink(Origin::signed(1), identity, account.clone()),
+			Error::<Test>::AccountNotLinked
+		);
+		// The signature of the first link cannot be replayed.
+		assert_noop!(
+			LitentryPallet::link_account(
+				Origin::signed(1),
+				identity,
+				account,
+				ExternalSignature::Sr25519(AccountKeyring::Alice.sign(&message)),
+			),
+			Error::<Test>::InvalidLinkSignature
+		);
+	});
+}
+
+#[test]
+fn request_authentication_is_charged_by_url_length() {
+	use frame_support::weights::GetDispatchInfo;
+
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+
+		let short = Call::<Test>::request_authentication(token, bounded_url(&[b'a'; 1])).get_dispatch_info();
+		let long = Call::<Test>::request_authentication(token, bounded_url(&[b'a'; 64])).get_dispatch_info();
+		assert!(long.weight > short.weight);
+
+		// A longer URL can neither be built nor decoded from a call.
+		assert!(BoundedVec::<u8, MaxUrlLength>::try_from(vec![b'a'; 65]).is_err());
+		let call_index = Call::<Test>::request_authentication(token, bounded_url(b"a")).encode()[0];
+		let encoded = (call_index, token, vec![b'a'; 65]).encode();
+		assert!(Call::<Test>::decode(&mut &encoded[..]).is_err());
+	});
+}
+
+#[test]
+fn genesis_config_builds_sets() {
+	let issuer_identity = H256::from_low_u64_be(16);
+	let receiver_identity = H256::from_low_u64_be(17);
+	let token = H256::from_low_u64_be(1);
+	let storage = crate::GenesisConfig::<Test> {
+		identities: vec![(1, issuer_identity), (2, receiver_identity)],
+		issuers: vec![],
+		tokens: vec![(token, receiver_identity, issuer_identity, 5, b"data".to_vec(), 1, 10)],
+	}.build_storage().unwrap();
+
+	sp_io::TestExternalities::from(storage).execute_with(|| {
+		assert_eq!(StructStorage::owner_of_identity(receiver_identity), Some(2));
+		assert_eq!(LitentryPallet::identities_of(&1), vec![issuer_identity]);
+		assert_eq!(AllIdentities::<Test>::len(), 2);
+		assert_eq!(LitentryPallet::tokens_of_identity(receiver_identity, 0, 10), vec![token]);
+		assert_eq!(LitentryPallet::tokens_issued_by(issuer_identity, 0, 10), vec![token]);
+		assert_eq!(StructStorage::tokens_expiring_at(10), vec![token]);
+		assert_eq!(StructStorage::token(token).cost, 5);
+		// The cost counts as paid.
+		assert_eq!(StructStorage::reserved_cost_of_token(token), None);
+
+		// Genesis storage is on the current layout, so the upgrade does not migrate it.
+		assert_eq!(LitentryPallet::on_chain_storage_version(), StorageVersion::new(6));
+		frame_support::storage::migration::put_storage_value(b"LitentryStorage", b"IdentitiesCount", &[], 1u64);
+		<LitentryPallet as frame_support::traits::OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(AllIdentities::<Test>::len(), 2);
+		assert_eq!(LitentryPallet::on_chain_storage_version(), StorageVersion::new(6));
+	});
+}
+
+#[test]
+fn attributes_are_proven_one_at_a_time() {
+	use litentry_utils::merkle;
+	use sp_runtime::traits::BlakeTwo256;
+
+	ExtBuilder::build().execute_with(|| {
+		let (identity, other_identity) = setup_identities();
+		let attributes: [(&[u8], &[u8], &[u8]); 3] = [
+			(b"email", b"alice@example.com", b"salt0"),
+			(b"handle", b"alice", b"salt1"),
+			(b"kyc_level", b"2", b"salt2"),
+		];
+		let leaves: Vec<H256> = attributes.iter()
+			.map(|(name, value, salt)| LitentryPallet::attribute_commitment(name, value, salt))
+			.collect();
+		let root = merkle::root::<BlakeTwo256>(&leaves);
+
+		// Nothing is proven before the identity commits to its attributes.
+		let proof = merkle::proof::<BlakeTwo256>(&leaves, 1).unwrap();
+		assert!(!LitentryPallet::verify_attribute(identity, leaves[1], &proof));
+
+		assert_noop!(
+			LitentryPallet::set_attributes_root(Origin::signed(2), identity, root),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_ok!(LitentryPallet::set_attributes_root(Origin::signed(1), identity, root));
+
+		for (index, leaf) in leaves.iter().enumerate() {
+			let proof = merkle::proof::<BlakeTwo256>(&leaves, index).unwrap();
+			assert!(LitentryPallet::verify_attribute(identity, *leaf, &proof));
+			assert!(!LitentryPallet::verify_attribute(other_identity, *leaf, &proof));
+		}
+		let forged = LitentryPallet::attribute_commitment(b"kyc_level", b"3", b"salt2");
+		let proof = merkle::proof::<BlakeTwo256>(&leaves, 2).unwrap();
+		assert!(!LitentryPallet::verify_attribute(identity, forged, &proof));
+	});
+}
+
+#[test]
+fn runtime_upgrade_adds_attribute_roots() {
+	use frame_support::{Blake2_128Concat, StorageHasher, traits::OnRuntimeUpgrade};
+	use frame_support::storage::migration::{get_storage_value, put_storage_value};
+
+	ExtBuilder::build().execute_with(|| {
+		let identity = H256::from_low_u64_be(16);
+		// `Releases::V2_0_0`, whose identities only hold their id.
+		put_storage_value(b"LitentryStorage", b"StorageVersion", &[], 1u8);
+		put_storage_value(
+			b"LitentryStorage",
+			b"Identities",
+			&identity.using_encoded(Blake2_128Concat::hash),
+			identity,
+		);
+
+		LitentryPallet::on_runtime_upgrade();
+
+		assert_eq!(
+			LitentryPallet::identity(identity),
+			Identity { id: identity, attributes_root: H256::default() }
+		);
+		// The legacy version item gives way to the module storage version.
+		assert_eq!(get_storage_value::<u8>(b"LitentryStorage", b"StorageVersion", &[]), None);
+		assert_eq!(LitentryPallet::on_chain_storage_version(), StorageVersion::new(6));
+	});
+}
+
+#[test]
+fn only_registered_issuers_issue_tokens() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		assert_ok!(LitentryPallet::register_datatype(Origin::signed(2), receiver_identity, 1, bounded(b"email".to_vec()), 8));
+
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(2), issuer_identity, receiver_identity, 0, bounded(b"data".to_vec()), 1, 100),
+			Error::<Test>::NotIssuer
+		);
+		assert_noop!(
+			LitentryPallet::register_issuer(Origin::signed(1), receiver_identity),
+			Error::<Test>::NotIdentityOwner
+		);
+		assert_noop!(
+			LitentryPallet::register_issuer(Origin::signed(1), issuer_identity),
+			Error::<Test>::IssuerExists
+		);
+		assert_eq!(Balances::reserved_balance(1), 10);
+
+		issue(issuer_identity, receiver_identity);
+		assert_eq!(StructStorage::issuer(issuer_identity).map(|issuer| issuer.issued), Some(1));
+
+		assert_ok!(LitentryPallet::unregister_issuer(Origin::signed(1), issuer_identity));
+		// Only the deposit of the issued token is left reserved.
+		assert_eq!(Balances::reserved_balance(1), 6);
+		assert_noop!(
+			LitentryPallet::issue_token(Origin::signed(1), receiver_identity, issuer_identity, 0, bounded(b"data".to_vec()), 1, 100),
+			Error::<Test>::NotIssuer
+		);
+	});
+}
+
+#[test]
+fn slash_issuer_burns_bond_and_unregisters_below_minimum() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, _) = setup_identities();
+
+		assert_noop!(
+			LitentryPallet::slash_issuer(Origin::signed(1), issuer_identity, 4),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(LitentryPallet::slash_issuer(Origin::ROOT, issuer_identity, 4));
+
+		// The bond fell below `IssuerBond`, the rest of it is returned.
+		assert_eq!(StructStorage::issuer(issuer_identity), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 96);
+		assert_eq!(Balances::total_issuance(), 296);
+
+		assert_noop!(
+			LitentryPallet::slash_issuer(Origin::ROOT, issuer_identity, 4),
+			Error::<Test>::NotIssuer
+		);
+	});
+}
+
+fn issuance(to: H256, cost: u64, datatype: u64) -> TokenIssuance<H256, u64, u64> {
+	TokenIssuance { to, cost, data: b"data".to_vec(), datatype, expired: 100 }
+}
+
+#[test]
+fn issue_tokens_batch_issues_all_or_nothing() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+
+		// A batch larger than MaxBatchSize cannot even be decoded into a call.
+		assert!(BoundedVec::<TokenIssuanceOf<Test>, MaxBatchSize>::try_from(vec![issuance(receiver_identity, 0, 1); 5]).is_err());
+		let call_index = Call::<Test>::issue_tokens_batch(issuer_identity, bounded(vec![])).encode()[0];
+		let encoded = (call_index, issuer_identity, vec![issuance(receiver_identity, 0, 1); 5]).encode();
+		assert!(Call::<Test>::decode(&mut &encoded[..]).is_err());
+		// The last token has an unknown datatype.
+		assert_noop!(
+			LitentryPallet::issue_tokens_batch(
+				Origin::signed(1),
+				issuer_identity,
+				bounded(vec![issuance(receiver_identity, 0, 1), issuance(receiver_identity, 0, 2)]),
+			),
+			Error::<Test>::DataTypeNotExist
+		);
+
+		assert_ok!(LitentryPallet::issue_tokens_batch(
+			Origin::signed(1),
+			issuer_identity,
+			bounded(vec![issuance(receiver_identity, 10, 1), issuance(receiver_identity, 20, 1), issuance(issuer_identity, 0, 1)]),
+		));
+		assert_eq!(IssuedTokens::<Test>::len(&issuer_identity), 3);
+		assert_eq!(LitentryPallet::tokens_of_identity(receiver_identity, 0, 10).len(), 2);
+		// The costs are left for the receiver to accept.
+		assert_eq!(Balances::reserved_balance(2), 0);
+		// Three deposits of 2 + 4 bytes and the issuer bond.
+		assert_eq!(Balances::reserved_balance(1), 28);
+		assert_eq!(StructStorage::issuer(issuer_identity).map(|issuer| issuer.issued), Some(3));
+	});
+}
+
+#[test]
+fn transfer_tokens_batch_transfers_all_or_nothing() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let first = issue(issuer_identity, receiver_identity);
+		let second = issue(issuer_identity, receiver_identity);
+		let not_held = issue(issuer_identity, issuer_identity);
+
+		// A batch larger than MaxBatchSize cannot even be decoded into a call.
+		assert!(BoundedVec::<(H256, H256), MaxBatchSize>::try_from(vec![(issuer_identity, first); 5]).is_err());
+		let call_index = Call::<Test>::transfer_tokens_batch(bounded(vec![])).encode()[0];
+		let encoded = (call_index, vec![(issuer_identity, first); 5]).encode();
+		assert!(Call::<Test>::decode(&mut &encoded[..]).is_err());
+		assert_noop!(
+			LitentryPallet::transfer_tokens_batch(
+				Origin::signed(2),
+				bounded(vec![(issuer_identity, first), (issuer_identity, first)]),
+			),
+			Error::<Test>::DuplicateToken
+		);
+		assert_noop!(
+			LitentryPallet::transfer_tokens_batch(
+				Origin::signed(2),
+				bounded(vec![(issuer_identity, first), (receiver_identity, not_held)]),
+			),
+			Error::<Test>::NotIdentityController
+		);
+		assert_noop!(
+			LitentryPallet::transfer_tokens_batch(
+				Origin::signed(2),
+				bounded(vec![(issuer_identity, first), (H256::from_low_u64_be(99), second)]),
+			),
+			Error::<Test>::IdentityNotExist
+		);
+
+		assert_ok!(LitentryPallet::transfer_tokens_batch(
+			Origin::signed(2),
+			bounded(vec![(issuer_identity, first), (issuer_identity, second)]),
+		));
+		assert_eq!(LitentryPallet::tokens_of_identity(receiver_identity, 0, 10), Vec::<H256>::new());
+		assert_eq!(StructStorage::owner_identity_of_token(second), Some(issuer_identity));
+	});
+}
+
+#[test]
+fn transfer_token_requires_existing_receiver() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+
+		assert_noop!(
+			LitentryPallet::transfer_token(Origin::signed(2), H256::from_low_u64_be(99), token),
+			Error::<Test>::IdentityNotExist
+		);
+		assert_eq!(StructStorage::owner_identity_of_token(token), Some(receiver_identity));
+	});
+}
+
+#[test]
+fn token_ids_are_predictable() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+
+		let predicted = LitentryPallet::next_token_id(issuer_identity, b"data");
+		assert_eq!(predicted, LitentryPallet::token_id(issuer_identity, 0, b"data"));
+		assert_eq!(issue(issuer_identity, receiver_identity), predicted);
+		// The same payload gets a new id.
+		let predicted = LitentryPallet::next_token_id(issuer_identity, b"data");
+		assert_eq!(predicted, LitentryPallet::token_id(issuer_identity, 1, b"data"));
+		assert_eq!(issue(issuer_identity, receiver_identity), predicted);
+
+		assert_ok!(LitentryPallet::issue_tokens_batch(
+			Origin::signed(1),
+			issuer_identity,
+			bounded(vec![issuance(receiver_identity, 0, 1); 2]),
+		));
+		assert_eq!(
+			LitentryPallet::tokens_issued_by(issuer_identity, 2, 2),
+			vec![
+				LitentryPallet::token_id(issuer_identity, 2, b"data"),
+				LitentryPallet::token_id(issuer_identity, 3, b"data"),
+			]
+		);
+
+		// Account nonces are left to the transaction pipeline.
+		assert_ok!(LitentryPallet::register_identity(Origin::signed(1)));
+		assert_ok!(LitentryPallet::register_identity(Origin::signed(1)));
+		assert_eq!(System::account_nonce(1), 0);
+		assert_eq!(LitentryPallet::identity_nonce(1), 2);
+	});
+}
+
+#[test]
+fn runtime_upgrade_derives_token_ids() {
+	use frame_support::traits::OnRuntimeUpgrade;
+	use frame_support::storage::migration::put_storage_value;
+
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let issued = issue_with(issuer_identity, receiver_identity, 30, 100);
+		assert_ok!(LitentryPallet::accept_token(Origin::signed(2), issued));
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(2), issued, bounded_url(b"https://litentry.com")));
+
+		// Give the token a random id, as `Releases::V3_0_0` did.
+		let old_id = H256::from_low_u64_be(42);
+		let mut token = StructStorage::token(issued);
+		<crate::AuthorizedTokens<Test>>::remove(issued);
+		LitentryPallet::rekey_token(issued, old_id, &mut token);
+		<crate::AuthorizedTokens<Test>>::insert(old_id, token);
+		<crate::IssuedTokenNonce<Test>>::remove(issuer_identity);
+		put_storage_value(b"LitentryStorage", b"StorageVersion", &[], 2u8);
+
+		LitentryPallet::on_runtime_upgrade();
+
+		let new_id = LitentryPallet::token_id(issuer_identity, 0, b"data");
+		assert_eq!(new_id, issued);
+		assert_eq!(StructStorage::token(new_id).id, new_id);
+		assert_eq!(StructStorage::owner_identity_of_token(old_id), None);
+		assert_eq!(StructStorage::owner_identity_of_token(new_id), Some(receiver_identity));
+		assert_eq!(LitentryPallet::tokens_of_identity(receiver_identity, 0, 10), vec![new_id]);
+		assert_eq!(LitentryPallet::tokens_issued_by(issuer_identity, 0, 10), vec![new_id]);
+		assert_eq!(AllTokens::<Test>::iter().collect::<Vec<_>>(), vec![new_id]);
+		assert_eq!(StructStorage::tokens_expiring_at(100), vec![new_id]);
+		assert_eq!(StructStorage::deposit_of_token(new_id), Some((1, 6)));
+		assert!(StructStorage::pending_authentication(new_id).is_some());
+		assert_eq!(LitentryPallet::next_token_id(issuer_identity, b"data"), LitentryPallet::token_id(issuer_identity, 1, b"data"));
+	});
+}
+
+#[test]
+fn token_quota_limits_requests_per_period() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue_expiring_at(issuer_identity, receiver_identity, 1000);
+		let url = bounded_url(b"https://litentry.com/auth");
+		assert_ok!(LitentryPallet::set_authenticators(Origin::ROOT, bounded(vec![7])));
+		let authenticate = || -> frame_support::dispatch::DispatchResult {
+			LitentryPallet::request_authentication(Origin::signed(2), token, url.clone())?;
+			LitentryPallet::submit_authentication_response(Origin::signed(7), token, 200)
+		};
+
+		assert_noop!(
+			LitentryPallet::set_token_quota(Origin::signed(2), token, Some((2, 10))),
+			Error::<Test>::NotIdentityController
+		);
+		assert_noop!(
+			LitentryPallet::set_token_quota(Origin::signed(1), token, Some((2, 0))),
+			Error::<Test>::InvalidQuotaPeriod
+		);
+		assert_ok!(LitentryPallet::set_token_quota(Origin::signed(1), token, Some((2, 10))));
+
+		assert_ok!(authenticate());
+		assert_ok!(authenticate());
+		assert_noop!(
+			LitentryPallet::request_authentication(Origin::signed(2), token, url.clone()),
+			Error::<Test>::QuotaExhausted
+		);
+
+		System::set_block_number(10);
+		LitentryPallet::on_initialize(10);
+		assert_eq!(StructStorage::token_quota(token).map(|quota| (quota.remaining, quota.next_reset)), Some((2, 20)));
+		assert_eq!(StructStorage::quota_resets_at(20), vec![token]);
+		assert_ok!(authenticate());
+
+		// Without quota the token is unlimited again and the pending reset is skipped.
+		assert_ok!(LitentryPallet::set_token_quota(Origin::signed(1), token, None));
+		System::set_block_number(20);
+		LitentryPallet::on_initialize(20);
+		assert_eq!(StructStorage::token_quota(token), None);
+		for _ in 0..3 {
+			assert_ok!(authenticate());
+		}
+	});
+}
+
+#[test]
+fn quota_resets_past_the_block_limit_are_carried_over() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let tokens: Vec<H256> = (0..3).map(|_| issue(issuer_identity, receiver_identity)).collect();
+		for token in &tokens {
+			assert_ok!(LitentryPallet::set_token_quota(Origin::signed(1), *token, Some((2, 10))));
+		}
+
+		System::set_block_number(10);
+		LitentryPallet::on_initialize(10);
+		assert_eq!(StructStorage::quota_resets_at(20), vec![tokens[0], tokens[1]]);
+		assert_eq!(StructStorage::quota_resets_at(11), vec![tokens[2]]);
+		assert_eq!(StructStorage::token_quota(tokens[2]).map(|quota| quota.next_reset), Some(10));
+
+		// The late reset starts the next period from the block it happens at.
+		System::set_block_number(11);
+		LitentryPallet::on_initialize(11);
+		assert_eq!(StructStorage::token_quota(tokens[2]).map(|quota| quota.next_reset), Some(21));
+		assert_eq!(StructStorage::quota_resets_at(21), vec![tokens[2]]);
+	});
+}
+
+#[test]
+fn only_controllers_of_the_holder_spend_its_quota() {
+	ExtBuilder::build().execute_with(|| {
+		let (issuer_identity, receiver_identity) = setup_identities();
+		let token = issue(issuer_identity, receiver_identity);
+		let url = bounded_url(b"https://litentry.com/auth");
+		assert_ok!(LitentryPallet::set_token_quota(Origin::signed(1), token, Some((1, 10))));
+
+		for sender in &[1, 3] {
+			assert_noop!(
+				LitentryPallet::request_authentication(Origin::signed(*sender), token, url.clone()),
+				Error::<Test>::NotIdentityController
+			);
+		}
+		assert_eq!(StructStorage::token_quota(token).map(|quota| quota.remaining), Some(1));
+		assert_eq!(StructStorage::pending_authentication(token), None);
+
+		// A delegate of the holder may spend it.
+		assert_ok!(LitentryPallet::add_delegate(Origin::signed(2), receiver_identity, 3));
+		assert_ok!(LitentryPallet::request_authentication(Origin::signed(3), token, url));
+		assert_eq!(StructStorage::token_quota(token).map(|quota| quota.remaining), Some(0));
+	});
+}
+
+#[test]
+fn register_issuer_requires_verification() {
+	ExtBuilder::build().execute_with(|| {
+		let identity = H256::from_low_u64_be(18);
+		assert_ok!(LitentryPallet::register_identity_with_id(Origin::signed(3), identity));
+
+		assert_noop!(
+			LitentryPallet::register_issuer(Origin::signed(3), identity),
+			Error::<Test>::IssuerNotVerified
+		);
+		// Once handed to a verified account, it can be registered.
+		assert_ok!(LitentryPallet::transfer_identity(Origin::signed(3), identity, 2));
+		assert_ok!(LitentryPallet::register_issuer(Origin::signed(2), identity));
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn upgrade_checks_catch_identities_without_owner() {
+	use frame_support::traits::OnRuntimeUpgrade;
+
+	ExtBuilder::build().execute_with(|| {
+		let (identity, _) = setup_identities();
+
+		assert_ok!(LitentryPallet::pre_upgrade());
+		LitentryPallet::on_runtime_upgrade();
+		assert_ok!(LitentryPallet::post_upgrade());
+
+		<crate::IdentityOwner<Test>>::remove(identity);
+		assert_eq!(LitentryPallet::post_upgrade(), Err("LitentryStorage: an identity has no owner"));
+
+		LitentryPallet::put_on_chain_storage_version(StorageVersion::new(7));
+		assert!(LitentryPallet::pre_upgrade().is_err());
 	});
 }
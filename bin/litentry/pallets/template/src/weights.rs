@@ -0,0 +1,275 @@
+//! Weights of the calls of the Litentry pallet.
+//!
+//! The benchmarks of `benchmarking.rs` measure these calls. Run them with a node built with the
+//! `runtime-benchmarks` feature, and copy the base weights, slopes and database accesses they
+//! print into the functions below:
+//!
+//! ```text
+//! litentry benchmark --chain dev --execution wasm --wasm-execution compiled \
+//!     --pallet litentry --extrinsic '*' --steps 50 --repeat 20
+//! ```
+
+#![allow(unused_parens)]
+#![allow(clippy::unnecessary_cast)]
+
+use frame_support::weights::Weight;
+use frame_support::traits::Get;
+use sp_std::marker::PhantomData;
+
+/// Weight functions of the calls of the Litentry pallet.
+pub trait WeightInfo {
+    fn register_identity() -> Weight;
+    fn register_identity_with_id() -> Weight;
+    fn issue_token(p: u32) -> Weight;
+    fn issue_tokens_batch(n: u32, p: u32) -> Weight;
+    fn register_datatype(n: u32) -> Weight;
+    fn transfer_token() -> Weight;
+    fn transfer_tokens_batch(n: u32) -> Weight;
+    fn accept_token() -> Weight;
+    fn request_authentication(u: u32) -> Weight;
+    fn set_token_quota() -> Weight;
+    fn submit_authentication_response() -> Weight;
+    fn set_authenticators(a: u32) -> Weight;
+    fn recall_token() -> Weight;
+    fn recall_all_identity_token(t: u32) -> Weight;
+    fn transfer_identity() -> Weight;
+    fn add_delegate() -> Weight;
+    fn remove_delegate() -> Weight;
+    fn destroy_identity(i: u32, o: u32, l: u32) -> Weight;
+    fn link_account() -> Weight;
+    fn unlink() -> Weight;
+    fn register_issuer() -> Weight;
+    fn unregister_issuer() -> Weight;
+    fn slash_issuer() -> Weight;
+    fn set_attributes_root() -> Weight;
+}
+
+/// Weights for the Litentry pallet, with the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Trait> WeightInfo for SubstrateWeight<T> {
+    fn register_identity() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(7 as Weight))
+            .saturating_add(T::DbWeight::get().writes(9 as Weight))
+    }
+    fn register_identity_with_id() -> Weight {
+        (43_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(6 as Weight))
+            .saturating_add(T::DbWeight::get().writes(8 as Weight))
+    }
+    fn issue_token(p: u32) -> Weight {
+        (96_000_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add(T::DbWeight::get().reads(16 as Weight))
+            .saturating_add(T::DbWeight::get().writes(20 as Weight))
+    }
+    fn issue_tokens_batch(n: u32, p: u32) -> Weight {
+        (4_000_000 as Weight)
+            .saturating_add((92_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().reads((14 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().writes((20 as Weight).saturating_mul(n as Weight)))
+    }
+    fn register_datatype(n: u32) -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add((3_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    fn transfer_token() -> Weight {
+        (52_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(9 as Weight))
+            .saturating_add(T::DbWeight::get().writes(9 as Weight))
+    }
+    fn transfer_tokens_batch(n: u32) -> Weight {
+        (3_000_000 as Weight)
+            .saturating_add((50_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads((9 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().writes((9 as Weight).saturating_mul(n as Weight)))
+    }
+    fn accept_token() -> Weight {
+        (48_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(6 as Weight))
+            .saturating_add(T::DbWeight::get().writes(4 as Weight))
+    }
+    fn request_authentication(u: u32) -> Weight {
+        (64_000_000 as Weight)
+            .saturating_add((6_000 as Weight).saturating_mul(u as Weight))
+            .saturating_add(T::DbWeight::get().reads(12 as Weight))
+            .saturating_add(T::DbWeight::get().writes(6 as Weight))
+    }
+    fn set_token_quota() -> Weight {
+        (35_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+    fn submit_authentication_response() -> Weight {
+        (40_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    fn set_authenticators(a: u32) -> Weight {
+        (8_000_000 as Weight)
+            .saturating_add((40_000 as Weight).saturating_mul(a as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    fn recall_token() -> Weight {
+        (75_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(19 as Weight))
+            .saturating_add(T::DbWeight::get().writes(24 as Weight))
+    }
+    fn recall_all_identity_token(t: u32) -> Weight {
+        (38_000_000 as Weight)
+            .saturating_add((71_000_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().reads((17 as Weight).saturating_mul(t as Weight)))
+            .saturating_add(T::DbWeight::get().writes((24 as Weight).saturating_mul(t as Weight)))
+    }
+    fn transfer_identity() -> Weight {
+        (42_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().writes(5 as Weight))
+    }
+    fn add_delegate() -> Weight {
+        (28_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    fn remove_delegate() -> Weight {
+        (27_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    fn destroy_identity(i: u32, o: u32, l: u32) -> Weight {
+        (64_000_000 as Weight)
+            .saturating_add((71_000_000 as Weight).saturating_mul(i as Weight))
+            .saturating_add((71_000_000 as Weight).saturating_mul(o as Weight))
+            .saturating_add((9_000_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(T::DbWeight::get().reads(9 as Weight))
+            .saturating_add(T::DbWeight::get().reads((17 as Weight).saturating_mul(i as Weight)))
+            .saturating_add(T::DbWeight::get().reads((17 as Weight).saturating_mul(o as Weight)))
+            .saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(l as Weight)))
+            .saturating_add(T::DbWeight::get().writes(10 as Weight))
+            .saturating_add(T::DbWeight::get().writes((24 as Weight).saturating_mul(i as Weight)))
+            .saturating_add(T::DbWeight::get().writes((24 as Weight).saturating_mul(o as Weight)))
+            .saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(l as Weight)))
+    }
+    fn link_account() -> Weight {
+        (95_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(5 as Weight))
+            .saturating_add(T::DbWeight::get().writes(5 as Weight))
+    }
+    fn unlink() -> Weight {
+        (38_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().writes(4 as Weight))
+    }
+    fn register_issuer() -> Weight {
+        (55_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+    fn unregister_issuer() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+    fn slash_issuer() -> Weight {
+        (60_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    fn set_attributes_root() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+}
+
+// For tests, with no database weights, as in the mock runtime.
+impl WeightInfo for () {
+    fn register_identity() -> Weight {
+        (45_000_000 as Weight)
+    }
+    fn register_identity_with_id() -> Weight {
+        (43_000_000 as Weight)
+    }
+    fn issue_token(p: u32) -> Weight {
+        (96_000_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+    }
+    fn issue_tokens_batch(n: u32, p: u32) -> Weight {
+        (4_000_000 as Weight)
+            .saturating_add((92_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+    }
+    fn register_datatype(n: u32) -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add((3_000 as Weight).saturating_mul(n as Weight))
+    }
+    fn transfer_token() -> Weight {
+        (52_000_000 as Weight)
+    }
+    fn transfer_tokens_batch(n: u32) -> Weight {
+        (3_000_000 as Weight)
+            .saturating_add((50_000_000 as Weight).saturating_mul(n as Weight))
+    }
+    fn accept_token() -> Weight {
+        (48_000_000 as Weight)
+    }
+    fn request_authentication(u: u32) -> Weight {
+        (64_000_000 as Weight)
+            .saturating_add((6_000 as Weight).saturating_mul(u as Weight))
+    }
+    fn set_token_quota() -> Weight {
+        (35_000_000 as Weight)
+    }
+    fn submit_authentication_response() -> Weight {
+        (40_000_000 as Weight)
+    }
+    fn set_authenticators(a: u32) -> Weight {
+        (8_000_000 as Weight)
+            .saturating_add((40_000 as Weight).saturating_mul(a as Weight))
+    }
+    fn recall_token() -> Weight {
+        (75_000_000 as Weight)
+    }
+    fn recall_all_identity_token(t: u32) -> Weight {
+        (38_000_000 as Weight)
+            .saturating_add((71_000_000 as Weight).saturating_mul(t as Weight))
+    }
+    fn transfer_identity() -> Weight {
+        (42_000_000 as Weight)
+    }
+    fn add_delegate() -> Weight {
+        (28_000_000 as Weight)
+    }
+    fn remove_delegate() -> Weight {
+        (27_000_000 as Weight)
+    }
+    fn destroy_identity(i: u32, o: u32, l: u32) -> Weight {
+        (64_000_000 as Weight)
+            .saturating_add((71_000_000 as Weight).saturating_mul(i as Weight))
+            .saturating_add((71_000_000 as Weight).saturating_mul(o as Weight))
+            .saturating_add((9_000_000 as Weight).saturating_mul(l as Weight))
+    }
+    fn link_account() -> Weight {
+        (95_000_000 as Weight)
+    }
+    fn unlink() -> Weight {
+        (38_000_000 as Weight)
+    }
+    fn register_issuer() -> Weight {
+        (55_000_000 as Weight)
+    }
+    fn unregister_issuer() -> Weight {
+        (45_000_000 as Weight)
+    }
+    fn slash_issuer() -> Weight {
+        (60_000_000 as Weight)
+    }
+    fn set_attributes_root() -> Weight {
+        (25_000_000 as Weight)
+    }
+}
@@ -0,0 +1,133 @@
+//! On-chain governance, replacing the sudo key when the `governance` feature is enabled.
+//!
+//! A council, whose members are managed by the `CouncilMembership` pallet, makes motions and
+//! external democracy proposals, approves treasury spends and takes the privileged Litentry and
+//! identity calls by a majority. Slashed deposits and bonds go to the treasury.
+
+use super::*;
+use sp_core::u32_trait::{_1, _2, _3, _4};
+use sp_runtime::{ModuleId, Percent, Permill};
+use frame_support::traits::{Contains, ContainsLengthBound};
+
+/// The origin of privileged Litentry and identity calls: more than half of the council.
+pub type PrivilegedOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+
+/// Slashed identity deposits and issuer bonds are paid to the treasury.
+pub type SlashedFunds = Treasury;
+
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+}
+
+pub type CouncilCollective = collective::Instance1;
+impl collective::Trait<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+}
+
+impl membership::Trait<membership::Instance1> for Runtime {
+	type Event = Event;
+	type AddOrigin = PrivilegedOrigin;
+	type RemoveOrigin = PrivilegedOrigin;
+	type SwapOrigin = PrivilegedOrigin;
+	type ResetOrigin = PrivilegedOrigin;
+	type PrimeOrigin = PrivilegedOrigin;
+	type MembershipInitialized = Council;
+	type MembershipChanged = Council;
+}
+
+parameter_types! {
+	pub const LaunchPeriod: BlockNumber = 7 * DAYS;
+	pub const VotingPeriod: BlockNumber = 7 * DAYS;
+	pub const FastTrackVotingPeriod: BlockNumber = 3 * HOURS;
+	pub const InstantAllowed: bool = false;
+	pub const MinimumDeposit: Balance = 100_000_000;
+	pub const EnactmentPeriod: BlockNumber = 8 * DAYS;
+	pub const CooloffPeriod: BlockNumber = 7 * DAYS;
+	// Deposits for on-chain data are 1_000 per byte.
+	pub const PreimageByteDeposit: Balance = 1_000;
+}
+
+impl democracy::Trait for Runtime {
+	type Proposal = Call;
+	type Event = Event;
+	type Currency = Balances;
+	type EnactmentPeriod = EnactmentPeriod;
+	type LaunchPeriod = LaunchPeriod;
+	type VotingPeriod = VotingPeriod;
+	type MinimumDeposit = MinimumDeposit;
+	/// A straight majority of the council can decide what their next motion is.
+	type ExternalOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	/// A super-majority can have the next scheduled referendum be a straight majority-carries vote.
+	type ExternalMajorityOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	/// A unanimous council can have the next scheduled referendum be a straight default-carries
+	/// (NTB) vote.
+	type ExternalDefaultOrigin = collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+	/// Three quarters of the council can have an ExternalMajority/ExternalDefault vote tabled
+	/// immediately and with a shorter voting period.
+	type FastTrackOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	type InstantOrigin = collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+	type InstantAllowed = InstantAllowed;
+	type FastTrackVotingPeriod = FastTrackVotingPeriod;
+	// To cancel a proposal which has been passed, 3/4 of the council must agree to it.
+	type CancellationOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	// Any single council member may veto a coming council proposal, however they can only do it
+	// once and it lasts only for the cooloff period.
+	type VetoOrigin = collective::EnsureMember<AccountId, CouncilCollective>;
+	type CooloffPeriod = CooloffPeriod;
+	type PreimageByteDeposit = PreimageByteDeposit;
+	type Slash = Treasury;
+	type Scheduler = Scheduler;
+}
+
+parameter_types! {
+	pub const ProposalBond: Permill = Permill::from_percent(5);
+	pub const ProposalBondMinimum: Balance = 1_000_000;
+	pub const SpendPeriod: BlockNumber = 1 * DAYS;
+	pub const Burn: Permill = Permill::from_percent(1);
+	pub const TipCountdown: BlockNumber = 1 * DAYS;
+	pub const TipFindersFee: Percent = Percent::from_percent(20);
+	pub const TipReportDepositBase: Balance = 1_000_000;
+	pub const TipReportDepositPerByte: Balance = 1_000;
+	pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+}
+
+impl treasury::Trait for Runtime {
+	type Currency = Balances;
+	type ApproveOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	type RejectOrigin = PrivilegedOrigin;
+	type Tippers = CouncilTippers;
+	type TipCountdown = TipCountdown;
+	type TipFindersFee = TipFindersFee;
+	type TipReportDepositBase = TipReportDepositBase;
+	type TipReportDepositPerByte = TipReportDepositPerByte;
+	type Event = Event;
+	type ProposalRejection = Treasury;
+	type ProposalBond = ProposalBond;
+	type ProposalBondMinimum = ProposalBondMinimum;
+	type SpendPeriod = SpendPeriod;
+	type Burn = Burn;
+	type ModuleId = TreasuryModuleId;
+}
+
+/// The largest council the tipping weights account for.
+const MAX_COUNCIL_MEMBERS: usize = 100;
+
+/// The council members, who may tip for treasury payouts.
+pub struct CouncilTippers;
+impl Contains<AccountId> for CouncilTippers {
+	fn sorted_members() -> Vec<AccountId> {
+		// The membership pallet keeps its members sorted.
+		CouncilMembership::members()
+	}
+}
+impl ContainsLengthBound for CouncilTippers {
+	fn min_len() -> usize {
+		0
+	}
+	fn max_len() -> usize {
+		MAX_COUNCIL_MEMBERS
+	}
+}
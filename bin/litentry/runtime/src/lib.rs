@@ -9,18 +9,22 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
+use codec::Encode;
 use sp_core::OpaqueMetadata;
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
-	transaction_validity::{TransactionValidity, TransactionSource},
+	SaturatedConversion,
+	curve::PiecewiseLinear,
+	transaction_validity::{TransactionValidity, TransactionSource, TransactionPriority},
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, IdentityLookup, Verify, ConvertInto, IdentifyAccount
+	self, BlakeTwo256, Block as BlockT, IdentityLookup, Verify, Convert, ConvertInto,
+	IdentifyAccount, OpaqueKeys,
 };
 use sp_api::impl_runtime_apis;
-use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use grandpa::AuthorityList as GrandpaAuthorityList;
 use grandpa::fg_primitives;
+use im_online::sr25519::AuthorityId as ImOnlineId;
 use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
@@ -32,14 +36,28 @@ pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
 pub use sp_runtime::{Permill, Perbill};
 pub use frame_support::{
-	StorageValue, construct_runtime, parameter_types,
+	StorageValue, construct_runtime, parameter_types, debug,
 	traits::Randomness,
 	weights::{Weight, RuntimeDbWeight},
 };
+pub use staking::StakerStatus;
 
 /// Importing a template pallet
 pub use template;
 
+#[cfg(feature = "governance")]
+mod governance;
+#[cfg(feature = "governance")]
+pub use governance::{PrivilegedOrigin, SlashedFunds};
+
+/// The origin of privileged Litentry and identity calls: the sudo key.
+#[cfg(not(feature = "governance"))]
+pub type PrivilegedOrigin = system::EnsureRoot<AccountId>;
+
+/// Slashed identity deposits and issuer bonds are burned.
+#[cfg(not(feature = "governance"))]
+pub type SlashedFunds = ();
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -84,19 +102,20 @@ pub mod opaque {
 
 	impl_opaque_keys! {
 		pub struct SessionKeys {
-			pub aura: Aura,
 			pub grandpa: Grandpa,
+			pub babe: Babe,
+			pub im_online: ImOnline,
 		}
 	}
 }
 
 /// This runtime version.
 pub const VERSION: RuntimeVersion = RuntimeVersion {
-	transaction_version: 1,
+	transaction_version: 2,
 	spec_name: create_runtime_str!("litentry"),
 	impl_name: create_runtime_str!("litentry"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 };
@@ -110,6 +129,13 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+/// 1 in 4 blocks (on average, not counting collisions) will be primary BABE blocks.
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
+/// With secondary slots every slot has a block, so an epoch is as many slots as blocks.
+pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = 10 * MINUTES;
+pub const EPOCH_DURATION_IN_SLOTS: u64 = EPOCH_DURATION_IN_BLOCKS as u64;
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -186,8 +212,16 @@ impl system::Trait for Runtime {
 	type AccountData = balances::AccountData<Balance>;
 }
 
-impl aura::Trait for Runtime {
-	type AuthorityId = AuraId;
+parameter_types! {
+	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
+	pub const ExpectedBlockTime: u64 = MILLISECS_PER_BLOCK;
+}
+
+impl babe::Trait for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	// Epochs change with sessions, which the staking pallet rotates.
+	type EpochChangeTrigger = babe::ExternalTrigger;
 }
 
 impl grandpa::Trait for Runtime {
@@ -201,7 +235,7 @@ parameter_types! {
 impl timestamp::Trait for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
-	type OnTimestampSet = Aura;
+	type OnTimestampSet = Babe;
 	type MinimumPeriod = MinimumPeriod;
 }
 
@@ -223,6 +257,118 @@ parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
 }
 
+parameter_types! {
+	pub const UncleGenerations: BlockNumber = 5;
+}
+
+impl authorship::Trait for Runtime {
+	type FindAuthor = session::FindAccountFromAuthorIndex<Self, Babe>;
+	type UncleGenerations = UncleGenerations;
+	type FilterUncle = ();
+	type EventHandler = (Staking, ImOnline);
+}
+
+parameter_types! {
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(17);
+}
+
+impl session::Trait for Runtime {
+	type Event = Event;
+	type ValidatorId = AccountId;
+	type ValidatorIdOf = staking::StashOf<Self>;
+	type ShouldEndSession = Babe;
+	type NextSessionRotation = Babe;
+	type SessionManager = Staking;
+	type SessionHandler = <opaque::SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
+	type Keys = opaque::SessionKeys;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+impl session::historical::Trait for Runtime {
+	type FullIdentification = staking::Exposure<AccountId, Balance>;
+	type FullIdentificationOf = staking::ExposureOf<Runtime>;
+}
+
+/// Converts balances to the `u64` vote weights of the staking election, scaled down by the
+/// total issuance so that they do not overflow.
+pub struct CurrencyToVoteHandler;
+
+impl CurrencyToVoteHandler {
+	fn factor() -> Balance { (Balances::total_issuance() / u64::max_value() as Balance).max(1) }
+}
+
+impl Convert<Balance, u64> for CurrencyToVoteHandler {
+	fn convert(x: Balance) -> u64 { (x / Self::factor()) as u64 }
+}
+
+impl Convert<u128, Balance> for CurrencyToVoteHandler {
+	fn convert(x: u128) -> Balance { x * Self::factor() }
+}
+
+staking_reward_curve::build! {
+	const REWARD_CURVE: PiecewiseLinear<'static> = curve!(
+		min_inflation: 0_025_000,
+		max_inflation: 0_100_000,
+		ideal_stake: 0_500_000,
+		falloff: 0_050_000,
+		max_piece_count: 40,
+		test_precision: 0_005_000,
+	);
+}
+
+parameter_types! {
+	pub const SessionsPerEra: sp_staking::SessionIndex = 6;
+	pub const BondingDuration: staking::EraIndex = 24 * 28;
+	pub const SlashDeferDuration: staking::EraIndex = 24 * 7; // 1/4 the bonding duration.
+	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
+	pub const ElectionLookahead: BlockNumber = EPOCH_DURATION_IN_BLOCKS / 4;
+	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const MaxIterations: u32 = 5;
+	pub const StakingUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+}
+
+impl staking::Trait for Runtime {
+	type Currency = Balances;
+	type UnixTime = Timestamp;
+	type CurrencyToVote = CurrencyToVoteHandler;
+	type RewardRemainder = SlashedFunds;
+	type Event = Event;
+	type Slash = SlashedFunds;
+	// Rewards are minted from the void.
+	type Reward = ();
+	type SessionsPerEra = SessionsPerEra;
+	type BondingDuration = BondingDuration;
+	type SlashDeferDuration = SlashDeferDuration;
+	type SlashCancelOrigin = PrivilegedOrigin;
+	type SessionInterface = Self;
+	type RewardCurve = RewardCurve;
+	type NextNewSession = Session;
+	type ElectionLookahead = ElectionLookahead;
+	type Call = Call;
+	type MaxIterations = MaxIterations;
+	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type UnsignedPriority = StakingUnsignedPriority;
+}
+
+parameter_types! {
+	pub const SessionDuration: BlockNumber = EPOCH_DURATION_IN_BLOCKS;
+	pub const ImOnlineUnsignedPriority: TransactionPriority = TransactionPriority::max_value();
+}
+
+impl im_online::Trait for Runtime {
+	type AuthorityId = ImOnlineId;
+	type Event = Event;
+	type SessionDuration = SessionDuration;
+	type ReportUnresponsiveness = Offences;
+	type UnsignedPriority = ImOnlineUnsignedPriority;
+}
+
+impl offences::Trait for Runtime {
+	type Event = Event;
+	type IdentificationTuple = session::historical::IdentificationTuple<Self>;
+	type OnOffenceHandler = Staking;
+}
+
 impl transaction_payment::Trait for Runtime {
 	type Currency = balances::Module<Runtime>;
 	type OnTransactionPayment = ();
@@ -231,17 +377,192 @@ impl transaction_payment::Trait for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+#[cfg(not(feature = "governance"))]
 impl sudo::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
 }
 
+// Deposits for on-chain data are 1_000 per byte.
+parameter_types! {
+	// One storage item; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const MultisigDepositBase: Balance = 56_000;
+	// Additional storage item size of 32 bytes.
+	pub const MultisigDepositFactor: Balance = 32_000;
+	pub const MaxSignatories: u16 = 100;
+}
+
+impl utility::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type MultisigDepositBase = MultisigDepositBase;
+	type MultisigDepositFactor = MultisigDepositFactor;
+	type MaxSignatories = MaxSignatories;
+}
+
+parameter_types! {
+	pub const MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * MaximumBlockWeight::get();
+}
+
+impl scheduler::Trait for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+}
+
+parameter_types! {
+	pub const BasicDeposit: Balance = 258_000;       // 258 bytes on-chain
+	pub const FieldDeposit: Balance = 66_000;        // 66 bytes on-chain
+	pub const SubAccountDeposit: Balance = 53_000;   // 53 bytes on-chain
+	pub const MaxSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+	pub const MaxRegistrars: u32 = 20;
+}
+
+impl identity::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	type Slashed = SlashedFunds;
+	type ForceOrigin = PrivilegedOrigin;
+	type RegistrarOrigin = PrivilegedOrigin;
+}
+
+impl<LocalCall> system::offchain::CreateSignedTransaction<LocalCall> for Runtime where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		public: <Signature as traits::Verify>::Signer,
+		account: AccountId,
+		nonce: Index,
+	) -> Option<(Call, <UncheckedExtrinsic as traits::Extrinsic>::SignaturePayload)> {
+		// take the biggest period possible.
+		let period = BlockHashCount::get()
+			.checked_next_power_of_two()
+			.map(|c| c / 2)
+			.unwrap_or(2) as u64;
+		let current_block = System::block_number()
+			.saturated_into::<u64>()
+			// The `System::block_number` is initialized with `n+1`,
+			// so the actual block number is `n`.
+			.saturating_sub(1);
+		let tip = 0;
+		let extra: SignedExtra = (
+			system::CheckVersion::<Runtime>::new(),
+			system::CheckGenesis::<Runtime>::new(),
+			system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			system::CheckNonce::<Runtime>::from(nonce),
+			system::CheckWeight::<Runtime>::new(),
+			transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+		);
+		let raw_payload = SignedPayload::new(call, extra).map_err(|e| {
+			debug::warn!("Unable to create signed payload: {:?}", e);
+		}).ok()?;
+		let signature = raw_payload.using_encoded(|payload| {
+			C::sign(payload, public)
+		})?;
+		let (call, extra, _) = raw_payload.deconstruct();
+		Some((call, (account, signature.into(), extra)))
+	}
+}
+
+impl system::offchain::SigningTypes for Runtime {
+	type Public = <Signature as traits::Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<C> system::offchain::SendTransactionTypes<C> for Runtime where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+parameter_types! {
+	/// Blocks an offchain worker waits before answering an authentication request again.
+	pub const AuthenticationGracePeriod: BlockNumber = 5;
+	/// Blocks an authentication request waits for an answer before it is dropped.
+	pub const AuthenticationTimeout: BlockNumber = 100;
+	pub const MaxDataTypeNameLength: u32 = 32;
+	pub const MaxPayloadLength: u32 = 1024;
+	pub const TokenDepositBase: Balance = 1_000;
+	pub const TokenDepositPerByte: Balance = 10;
+	pub const MaxUrlLength: u32 = 2048;
+	pub const IssuerBond: Balance = 1_000_000;
+	pub const MaxBatchSize: u32 = 500;
+	pub const MaxAuthenticators: u32 = 100;
+	pub const MaxExpiriesPerBlock: u32 = 200;
+	pub const MaxQuotaResetsPerBlock: u32 = 200;
+	pub const MaxTimeoutsPerBlock: u32 = 200;
+}
+
 /// Used for the module template in `./template.rs`
 impl template::Trait for Runtime {
 	type Event = Event;
-	type Randomness = RandomnessCollectiveFlip;
+	type AuthorityId = template::crypto::AuthenticatorId;
+	type GracePeriod = AuthenticationGracePeriod;
+	type AuthenticationTimeout = AuthenticationTimeout;
+	type MaxDataTypeNameLength = MaxDataTypeNameLength;
+	type MaxPayloadLength = MaxPayloadLength;
+	type TokenDepositBase = TokenDepositBase;
+	type TokenDepositPerByte = TokenDepositPerByte;
+	type MaxUrlLength = MaxUrlLength;
+	type IssuerBond = IssuerBond;
+	type Slashed = SlashedFunds;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxAuthenticators = MaxAuthenticators;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type MaxQuotaResetsPerBlock = MaxQuotaResetsPerBlock;
+	type MaxTimeoutsPerBlock = MaxTimeoutsPerBlock;
+	type IssuerVerifier = IdentityJudgement;
+	type AuthenticatorOrigin = PrivilegedOrigin;
+	type SlashOrigin = PrivilegedOrigin;
+	type WeightInfo = template::weights::SubstrateWeight<Runtime>;
+}
+
+/// Lets accounts whose on-chain identity a registrar judged `Reasonable` or `KnownGood`
+/// register Litentry issuers.
+pub struct IdentityJudgement;
+impl template::IssuerVerifier<AccountId> for IdentityJudgement {
+	fn is_verified(who: &AccountId) -> bool {
+		Identity::identity(who).map_or(false, |registration| {
+			registration.judgements.iter().any(|(_, judgement)| match judgement {
+				identity::Judgement::Reasonable | identity::Judgement::KnownGood => true,
+				_ => false,
+			})
+		})
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_verified(who: &AccountId) {
+		let unknown = identity::Data::None;
+		identity::IdentityOf::<Runtime>::insert(who, identity::Registration {
+			judgements: vec![(0, identity::Judgement::Reasonable)],
+			deposit: 0,
+			info: identity::IdentityInfo {
+				additional: vec![],
+				display: unknown.clone(),
+				legal: unknown.clone(),
+				web: unknown.clone(),
+				riot: unknown.clone(),
+				email: unknown.clone(),
+				pgp_fingerprint: None,
+				image: unknown.clone(),
+				twitter: unknown,
+			},
+		});
+	}
 }
 
+#[cfg(not(feature = "governance"))]
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -250,14 +571,54 @@ construct_runtime!(
 	{
 		System: system::{Module, Call, Config, Storage, Event<T>},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
+		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
 		Timestamp: timestamp::{Module, Call, Storage, Inherent},
-		Aura: aura::{Module, Config<T>, Inherent(Timestamp)},
-		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		Authorship: authorship::{Module, Call, Storage, Inherent},
 		Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: transaction_payment::{Module, Storage},
+		Staking: staking::{Module, Call, Config<T>, Storage, Event<T>, ValidateUnsigned},
+		Session: session::{Module, Call, Storage, Event, Config<T>},
+		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		ImOnline: im_online::{Module, Call, Storage, Event<T>, ValidateUnsigned, Config<T>},
+		Offences: offences::{Module, Call, Storage, Event},
 		Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
+		Utility: utility::{Module, Call, Storage, Event<T>},
+		Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+		Identity: identity::{Module, Call, Storage, Event<T>},
 		// Used for the module template in `./template.rs`
-		Litentry: template::{Module, Call, Storage, Event<T>}, //this define the name of the module
+		Litentry: template::{Module, Call, Storage, Event<T>, Config<T>}, //this define the name of the module
+	}
+);
+
+#[cfg(feature = "governance")]
+construct_runtime!(
+	pub enum Runtime where
+		Block = Block,
+		NodeBlock = opaque::Block,
+		UncheckedExtrinsic = UncheckedExtrinsic
+	{
+		System: system::{Module, Call, Config, Storage, Event<T>},
+		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
+		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+		Timestamp: timestamp::{Module, Call, Storage, Inherent},
+		Authorship: authorship::{Module, Call, Storage, Inherent},
+		Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
+		TransactionPayment: transaction_payment::{Module, Storage},
+		Staking: staking::{Module, Call, Config<T>, Storage, Event<T>, ValidateUnsigned},
+		Session: session::{Module, Call, Storage, Event, Config<T>},
+		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		ImOnline: im_online::{Module, Call, Storage, Event<T>, ValidateUnsigned, Config<T>},
+		Offences: offences::{Module, Call, Storage, Event},
+		Utility: utility::{Module, Call, Storage, Event<T>},
+		Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+		Identity: identity::{Module, Call, Storage, Event<T>},
+		// The council is initialized by `CouncilMembership`.
+		Council: collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>},
+		CouncilMembership: membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
+		Democracy: democracy::{Module, Call, Storage, Config, Event<T>},
+		Treasury: treasury::{Module, Call, Storage, Config, Event<T>},
+		// Used for the module template in `./template.rs`
+		Litentry: template::{Module, Call, Storage, Event<T>, Config<T>}, //this define the name of the module
 	}
 );
 
@@ -282,6 +643,8 @@ pub type SignedExtra = (
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+/// The payload being signed in transactions.
+pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
@@ -348,13 +711,25 @@ impl_runtime_apis! {
 		}
 	}
 
-	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
-		fn slot_duration() -> u64 {
-			Aura::slot_duration()
+	impl sp_consensus_babe::BabeApi<Block> for Runtime {
+		fn configuration() -> sp_consensus_babe::BabeGenesisConfiguration {
+			// The choice of `c` parameter (where `1 - c` represents the
+			// probability of a slot being empty), is done in accordance to the
+			// slot duration and expected target block time, for safely
+			// resisting network delays of maximum two seconds.
+			// <https://research.web3.foundation/en/latest/polkadot/BABE/Babe/#6-practical-results>
+			sp_consensus_babe::BabeGenesisConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EpochDuration::get(),
+				c: PRIMARY_PROBABILITY,
+				genesis_authorities: Babe::authorities(),
+				randomness: Babe::randomness(),
+				allowed_slots: sp_consensus_babe::AllowedSlots::PrimaryAndSecondaryPlainSlots,
+			}
 		}
 
-		fn authorities() -> Vec<AuraId> {
-			Aura::authorities()
+		fn current_epoch_start() -> sp_consensus_babe::SlotNumber {
+			Babe::current_epoch_start()
 		}
 	}
 
@@ -375,4 +750,81 @@ impl_runtime_apis! {
 			Grandpa::grandpa_authorities()
 		}
 	}
+
+	impl litentry_rpc_runtime_api::LitentryApi<Block, AccountId, Hash, Balance, BlockNumber> for Runtime {
+		fn identities_of(account: AccountId) -> Vec<Hash> {
+			Litentry::identities_of(&account)
+		}
+
+		fn tokens_of_identity(identity: Hash, start: u64, count: u64) -> Vec<Hash> {
+			Litentry::tokens_of_identity(identity, start, count)
+		}
+
+		fn tokens_issued_by(identity: Hash, start: u64, count: u64) -> Vec<Hash> {
+			Litentry::tokens_issued_by(identity, start, count)
+		}
+
+		fn token_details(
+			token: Hash,
+		) -> Option<litentry_rpc_runtime_api::TokenDetails<Hash, Balance, BlockNumber>> {
+			Litentry::token_details(token).map(|(token, owner_identity, issuer_identity)| {
+				litentry_rpc_runtime_api::TokenDetails {
+					id: token.id,
+					owner_identity,
+					issuer_identity,
+					cost: token.cost,
+					data: token.data,
+					datatype: token.datatype,
+					expired: token.expired,
+				}
+			})
+		}
+
+		fn verify_attribute(
+			identity: Hash,
+			name: Vec<u8>,
+			value: Vec<u8>,
+			salt: Vec<u8>,
+			proof: Vec<Hash>,
+		) -> bool {
+			let commitment = Litentry::attribute_commitment(&name, &value, &salt);
+			Litentry::verify_attribute(identity, commitment, &proof)
+		}
+
+		fn next_token_id(issuer: Hash, data: Vec<u8>) -> Hash {
+			Litentry::next_token_id(issuer, &data)
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			pallet: Vec<u8>,
+			benchmark: Vec<u8>,
+			lowest_range_values: Vec<u32>,
+			highest_range_values: Vec<u32>,
+			steps: Vec<u32>,
+			repeat: u32,
+		) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+			use frame_benchmarking::{Benchmarking, BenchmarkBatch, add_benchmark};
+
+			let mut batches = Vec::<BenchmarkBatch>::new();
+			let params = (&pallet, &benchmark, &lowest_range_values, &highest_range_values, &steps, repeat);
+
+			add_benchmark!(params, batches, b"identity", Identity);
+			add_benchmark!(params, batches, b"litentry", Litentry);
+			add_benchmark!(params, batches, b"utility", Utility);
+
+			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
+			Ok(batches)
+		}
+	}
+
+	#[cfg(feature = "try-runtime")]
+	impl frame_try_runtime::TryRuntime<Block> for Runtime {
+		fn on_runtime_upgrade() -> (Weight, Vec<Vec<u8>>) {
+			let (weight, failures) = Executive::try_runtime_upgrade();
+			(weight, failures.into_iter().map(|failure| failure.as_bytes().to_vec()).collect())
+		}
+	}
 }
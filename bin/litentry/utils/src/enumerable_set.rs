@@ -0,0 +1,355 @@
+//! An enumerable set of values, partitioned by a key, on top of three storage items.
+//!
+//! Each partition `key` of the set keeps:
+//! - `Count`: the number of values in the partition,
+//! - `Items`: the values by their position, densely packed in `0..Count`,
+//! - `Index`: the position of each value.
+//!
+//! Insertion appends at the tail and removal swaps the last value into the freed slot, so both
+//! touch a constant number of storage entries. Values are enumerated by position.
+//!
+//! A set that is not partitioned is an `EnumerableValueSet`, which keeps `Count` in a storage
+//! value and `Items` and `Index` in storage maps.
+
+use frame_support::codec::FullCodec;
+use frame_support::sp_std::{marker::PhantomData, prelude::*};
+use frame_support::storage::{StorageDoubleMap, StorageMap, StorageValue};
+
+/// An enumerable set of `V`, partitioned by `K`. See the module documentation.
+pub struct EnumerableSet<K, V, Count, Items, Index>(PhantomData<(K, V, Count, Items, Index)>);
+
+impl<K, V, Count, Items, Index> EnumerableSet<K, V, Count, Items, Index>
+where
+    K: FullCodec,
+    V: FullCodec,
+    Count: StorageMap<K, u64, Query = u64>,
+    Items: StorageDoubleMap<K, u64, V, Query = Option<V>>,
+    Index: StorageDoubleMap<K, V, u64, Query = Option<u64>>,
+{
+    /// The number of values in the partition `key`.
+    pub fn len(key: &K) -> u64 {
+        Count::get(key)
+    }
+
+    /// Whether the partition `key` is empty.
+    pub fn is_empty(key: &K) -> bool {
+        Self::len(key) == 0
+    }
+
+    /// Whether `value` is in the partition `key`.
+    pub fn contains(key: &K, value: &V) -> bool {
+        Index::contains_key(key, value)
+    }
+
+    /// The value at `position` of the partition `key`.
+    pub fn get(key: &K, position: u64) -> Option<V> {
+        Items::get(key, position)
+    }
+
+    /// The position of `value` in the partition `key`.
+    pub fn position(key: &K, value: &V) -> Option<u64> {
+        Index::get(key, value)
+    }
+
+    /// Add `value` to the partition `key`. Returns `false` if it was already there.
+    pub fn insert(key: &K, value: &V) -> Result<bool, &'static str> {
+        if Self::contains(key, value) {
+            return Ok(false);
+        }
+        let count = Count::get(key);
+        let new_count = count.checked_add(1).ok_or("Overflow adding a value to a set")?;
+
+        Items::insert(key, count, value);
+        Index::insert(key, value, count);
+        Count::insert(key, new_count);
+        Ok(true)
+    }
+
+    /// Remove `value` from the partition `key`. Returns `false` if it was not there.
+    ///
+    /// The last value of the partition takes the position of the removed one.
+    pub fn remove(key: &K, value: &V) -> bool {
+        let position = match Index::take(key, value) {
+            Some(position) => position,
+            None => return false,
+        };
+        // `position < count`, so this does not underflow.
+        let last = Count::get(key) - 1;
+        if position != last {
+            if let Some(last_value) = Items::get(key, last) {
+                Items::insert(key, position, &last_value);
+                Index::insert(key, &last_value, position);
+            }
+        }
+        Items::remove(key, last);
+        if last == 0 {
+            Count::remove(key);
+        } else {
+            Count::insert(key, last);
+        }
+        true
+    }
+
+    /// At most `count` values of the partition `key`, from position `start`.
+    pub fn range(key: &K, start: u64, count: u64) -> Vec<V> {
+        let end = start.saturating_add(count).min(Self::len(key));
+        (start..end).filter_map(|position| Self::get(key, position)).collect()
+    }
+
+    /// Iterate over the values of the partition `key`, by position.
+    ///
+    /// The partition must not be modified while iterating.
+    pub fn iter(key: K) -> impl Iterator<Item = V> {
+        let count = Self::len(&key);
+        (0..count).filter_map(move |position| Self::get(&key, position))
+    }
+
+    /// Remove every value of the partition `key`.
+    pub fn clear(key: &K) {
+        Items::remove_prefix(key);
+        Index::remove_prefix(key);
+        Count::remove(key);
+    }
+}
+
+/// An enumerable set of `V`, not partitioned. See the module documentation.
+pub struct EnumerableValueSet<V, Count, Items, Index>(PhantomData<(V, Count, Items, Index)>);
+
+impl<V, Count, Items, Index> EnumerableValueSet<V, Count, Items, Index>
+where
+    V: FullCodec,
+    Count: StorageValue<u64, Query = u64>,
+    Items: StorageMap<u64, V, Query = Option<V>>,
+    Index: StorageMap<V, u64, Query = Option<u64>>,
+{
+    /// The number of values in the set.
+    pub fn len() -> u64 {
+        Count::get()
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty() -> bool {
+        Self::len() == 0
+    }
+
+    /// Whether `value` is in the set.
+    pub fn contains(value: &V) -> bool {
+        Index::contains_key(value)
+    }
+
+    /// The value at `position`.
+    pub fn get(position: u64) -> Option<V> {
+        Items::get(position)
+    }
+
+    /// The position of `value`.
+    pub fn position(value: &V) -> Option<u64> {
+        Index::get(value)
+    }
+
+    /// Add `value` to the set. Returns `false` if it was already there.
+    pub fn insert(value: &V) -> Result<bool, &'static str> {
+        if Self::contains(value) {
+            return Ok(false);
+        }
+        let count = Count::get();
+        let new_count = count.checked_add(1).ok_or("Overflow adding a value to a set")?;
+
+        Items::insert(count, value);
+        Index::insert(value, count);
+        Count::put(new_count);
+        Ok(true)
+    }
+
+    /// Remove `value` from the set. Returns `false` if it was not there.
+    ///
+    /// The last value of the set takes the position of the removed one.
+    pub fn remove(value: &V) -> bool {
+        let position = match Index::take(value) {
+            Some(position) => position,
+            None => return false,
+        };
+        // `position < count`, so this does not underflow.
+        let last = Count::get() - 1;
+        if position != last {
+            if let Some(last_value) = Items::get(last) {
+                Items::insert(position, &last_value);
+                Index::insert(&last_value, position);
+            }
+        }
+        Items::remove(last);
+        if last == 0 {
+            Count::kill();
+        } else {
+            Count::put(last);
+        }
+        true
+    }
+
+    /// At most `count` values, from position `start`.
+    pub fn range(start: u64, count: u64) -> Vec<V> {
+        let end = start.saturating_add(count).min(Self::len());
+        (start..end).filter_map(Self::get).collect()
+    }
+
+    /// Iterate over the values, by position.
+    ///
+    /// The set must not be modified while iterating.
+    pub fn iter() -> impl Iterator<Item = V> {
+        (0..Self::len()).filter_map(Self::get)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_support::{decl_module, decl_storage};
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+    use sp_io::TestExternalities;
+    use std::collections::BTreeMap;
+
+    struct Runtime {}
+    pub trait Trait {
+        type Origin;
+        type BlockNumber;
+    }
+
+    impl Trait for Runtime {
+        type Origin = u32;
+        type BlockNumber = u32;
+    }
+
+    decl_module! {
+        pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+    }
+
+    decl_storage! {
+        trait Store for Module<T: Trait> as Test {
+            SetCount: map hasher(blake2_128_concat) u8 => u64;
+            SetItems: double_map hasher(blake2_128_concat) u8, hasher(blake2_128_concat) u64 => Option<u32>;
+            SetIndex: double_map hasher(blake2_128_concat) u8, hasher(blake2_128_concat) u32 => Option<u64>;
+
+            ValueSetCount: u64;
+            ValueSetItems: map hasher(blake2_128_concat) u64 => Option<u32>;
+            ValueSetIndex: map hasher(blake2_128_concat) u32 => Option<u64>;
+        }
+    }
+
+    type Set = EnumerableSet<u8, u32, SetCount, SetItems, SetIndex>;
+    type ValueSet = EnumerableValueSet<u32, ValueSetCount, ValueSetItems, ValueSetIndex>;
+
+    fn new_test_ext() -> TestExternalities {
+        GenesisConfig::default().build_storage().unwrap().into()
+    }
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Insert(u8, u32),
+        Remove(u8, u32),
+        Clear(u8),
+    }
+
+    impl Arbitrary for Op {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            // Few keys and values, so that operations hit existing entries.
+            let key = u8::arbitrary(g) % 3;
+            let value = u32::arbitrary(g) % 16;
+            match u8::arbitrary(g) % 8 {
+                0 => Op::Clear(key),
+                1..=4 => Op::Insert(key, value),
+                _ => Op::Remove(key, value),
+            }
+        }
+    }
+
+    /// Check the set against the model: same values, densely packed and consistently indexed.
+    fn matches_model(model: &BTreeMap<u8, Vec<u32>>) -> bool {
+        (0..3u8).all(|key| {
+            let mut expected = model.get(&key).cloned().unwrap_or_default();
+            let mut values: Vec<u32> = Set::iter(key).collect();
+            let indexed = values.iter().enumerate().all(|(position, value)| {
+                Set::position(&key, value) == Some(position as u64)
+            });
+            let dense = Set::get(&key, Set::len(&key)).is_none();
+            expected.sort();
+            values.sort();
+            indexed && dense && values.len() as u64 == Set::len(&key) && values == expected
+        })
+    }
+
+    #[test]
+    fn insert_and_remove_match_model() {
+        fn property(ops: Vec<Op>) -> bool {
+            new_test_ext().execute_with(|| {
+                let mut model = BTreeMap::<u8, Vec<u32>>::new();
+                for op in ops {
+                    match op {
+                        Op::Insert(key, value) => {
+                            let values = model.entry(key).or_default();
+                            let expected = !values.contains(&value);
+                            if expected {
+                                values.push(value);
+                            }
+                            if Set::insert(&key, &value) != Ok(expected) {
+                                return false;
+                            }
+                        }
+                        Op::Remove(key, value) => {
+                            let values = model.entry(key).or_default();
+                            let expected = values.contains(&value);
+                            values.retain(|v| *v != value);
+                            if Set::remove(&key, &value) != expected {
+                                return false;
+                            }
+                        }
+                        Op::Clear(key) => {
+                            model.remove(&key);
+                            Set::clear(&key);
+                        }
+                    }
+                    if !matches_model(&model) {
+                        return false;
+                    }
+                }
+                true
+            })
+        }
+        QuickCheck::new().quickcheck(property as fn(Vec<Op>) -> bool)
+    }
+
+    #[test]
+    fn remove_swaps_last_value_into_place() {
+        new_test_ext().execute_with(|| {
+            for value in 0..4 {
+                assert_eq!(Set::insert(&0, &value), Ok(true));
+            }
+            assert!(Set::remove(&0, &1));
+            assert_eq!(Set::iter(0).collect::<Vec<_>>(), vec![0, 3, 2]);
+            assert_eq!(Set::position(&0, &3), Some(1));
+            assert_eq!(Set::range(&0, 1, 5), vec![3, 2]);
+            assert!(!Set::remove(&0, &1));
+            assert!(Set::is_empty(&1));
+        });
+    }
+
+    #[test]
+    fn value_set_remove_swaps_last_value_into_place() {
+        new_test_ext().execute_with(|| {
+            for value in 0..4 {
+                assert_eq!(ValueSet::insert(&value), Ok(true));
+            }
+            assert_eq!(ValueSet::insert(&2), Ok(false));
+            assert!(ValueSet::remove(&1));
+            assert_eq!(ValueSet::iter().collect::<Vec<_>>(), vec![0, 3, 2]);
+            assert_eq!(ValueSet::position(&3), Some(1));
+            assert_eq!(ValueSet::range(1, 5), vec![3, 2]);
+            assert!(!ValueSet::remove(&1));
+
+            for value in &[0, 2, 3] {
+                assert!(ValueSet::remove(value));
+            }
+            assert!(ValueSet::is_empty());
+            assert!(!ValueSetCount::exists());
+        });
+    }
+}
@@ -1,4 +1,7 @@
-
 #![cfg_attr(not(feature = "std"), no_std)]
 
-// mod weights;
\ No newline at end of file
+pub mod enumerable_set;
+pub mod merkle;
+// mod weights;
+
+pub use enumerable_set::{EnumerableSet, EnumerableValueSet};
@@ -0,0 +1,103 @@
+//! A binary Merkle tree over hashed leaves, with inclusion proofs.
+//!
+//! Each parent is the hash of its two children in ascending order, so a proof is the list of
+//! sibling hashes from the leaf up to the root and carries no position. A node without sibling
+//! is promoted unchanged to the next level.
+
+use frame_support::sp_runtime::traits::Hash;
+use frame_support::sp_std::prelude::*;
+
+fn hash_pair<H: Hash>(a: &H::Output, b: &H::Output) -> H::Output {
+	let (left, right) = if a <= b { (a, b) } else { (b, a) };
+	let mut data = Vec::with_capacity(left.as_ref().len() + right.as_ref().len());
+	data.extend_from_slice(left.as_ref());
+	data.extend_from_slice(right.as_ref());
+	H::hash(&data)
+}
+
+fn next_level<H: Hash>(level: &[H::Output]) -> Vec<H::Output> {
+	level.chunks(2)
+		.map(|pair| match pair {
+			[left, right] => hash_pair::<H>(left, right),
+			[single] => single.clone(),
+			_ => unreachable!("chunks of two are never empty; qed"),
+		})
+		.collect()
+}
+
+/// The root of the tree of `leaves`, or the default hash if there are none.
+pub fn root<H: Hash>(leaves: &[H::Output]) -> H::Output {
+	if leaves.is_empty() {
+		return Default::default();
+	}
+	let mut level = leaves.to_vec();
+	while level.len() > 1 {
+		level = next_level::<H>(&level);
+	}
+	level.remove(0)
+}
+
+/// The proof that the leaf at `index` is in the tree of `leaves`.
+pub fn proof<H: Hash>(leaves: &[H::Output], mut index: usize) -> Option<Vec<H::Output>> {
+	if index >= leaves.len() {
+		return None;
+	}
+	let mut proof = Vec::new();
+	let mut level = leaves.to_vec();
+	while level.len() > 1 {
+		if let Some(sibling) = level.get(index ^ 1) {
+			proof.push(sibling.clone());
+		}
+		level = next_level::<H>(&level);
+		index /= 2;
+	}
+	Some(proof)
+}
+
+/// Whether `proof` shows that `leaf` is in the tree with the given `root`.
+pub fn verify<H: Hash>(root: &H::Output, leaf: H::Output, proof: &[H::Output]) -> bool {
+	let computed = proof.iter().fold(leaf, |node, sibling| hash_pair::<H>(&node, sibling));
+	&computed == root
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::sp_runtime::{testing::H256, traits::BlakeTwo256};
+
+	fn leaves(count: u64) -> Vec<H256> {
+		(0..count).map(|i| BlakeTwo256::hash(&i.to_le_bytes())).collect()
+	}
+
+	#[test]
+	fn every_leaf_has_a_valid_proof() {
+		for count in 1..20 {
+			let leaves = leaves(count);
+			let root = root::<BlakeTwo256>(&leaves);
+			for (index, leaf) in leaves.iter().enumerate() {
+				let proof = proof::<BlakeTwo256>(&leaves, index).unwrap();
+				assert!(verify::<BlakeTwo256>(&root, *leaf, &proof), "leaf {} of {}", index, count);
+			}
+			assert_eq!(proof::<BlakeTwo256>(&leaves, count as usize), None);
+		}
+	}
+
+	#[test]
+	fn proofs_do_not_verify_other_leaves_or_roots() {
+		let leaves = leaves(5);
+		let root = root::<BlakeTwo256>(&leaves);
+		let proof = proof::<BlakeTwo256>(&leaves, 2).unwrap();
+
+		assert!(!verify::<BlakeTwo256>(&root, leaves[3], &proof));
+		assert!(!verify::<BlakeTwo256>(&root, BlakeTwo256::hash(b"other"), &proof));
+		assert!(!verify::<BlakeTwo256>(&root::<BlakeTwo256>(&leaves[..4]), leaves[2], &proof));
+	}
+
+	#[test]
+	fn single_leaf_is_its_own_root() {
+		let leaves = leaves(1);
+		assert_eq!(root::<BlakeTwo256>(&leaves), leaves[0]);
+		assert_eq!(proof::<BlakeTwo256>(&leaves, 0), Some(vec![]));
+		assert_eq!(root::<BlakeTwo256>(&[]), H256::default());
+	}
+}
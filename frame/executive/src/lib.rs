@@ -231,10 +231,7 @@ where
 		digest: &Digest<System::Hash>,
 	) {
 		if Self::runtime_upgraded() {
-			// System is not part of `AllModules`, so we need to call this manually.
-			let mut weight = <frame_system::Module::<System> as OnRuntimeUpgrade>::on_runtime_upgrade();
-			weight = weight.saturating_add(COnRuntimeUpgrade::on_runtime_upgrade());
-			weight = weight.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade());
+			let weight = Self::execute_on_runtime_upgrade();
 			<frame_system::Module<System>>::register_extra_weight_unchecked(weight);
 		}
 		<frame_system::Module<System>>::initialize(
@@ -252,6 +249,43 @@ where
 		frame_system::Module::<System>::note_finished_initialize();
 	}
 
+	/// Execute all `OnRuntimeUpgrade` of this runtime, and return the aggregate weight.
+	pub fn execute_on_runtime_upgrade() -> frame_support::weights::Weight {
+		// System is not part of `AllModules`, so we need to call this manually.
+		let mut weight = <frame_system::Module::<System> as OnRuntimeUpgrade>::on_runtime_upgrade();
+		weight = weight.saturating_add(COnRuntimeUpgrade::on_runtime_upgrade());
+		weight.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade())
+	}
+
+	/// Execute all `OnRuntimeUpgrade` of this runtime, between their `pre_upgrade` and
+	/// `post_upgrade` checks.
+	///
+	/// Returns the aggregate weight of the upgrade and the failed checks. This is meant to test an
+	/// upgrade offline, against a snapshot of the chain state, and must not be called on chain.
+	#[cfg(feature = "try-runtime")]
+	pub fn try_runtime_upgrade() -> (frame_support::weights::Weight, Vec<&'static str>) {
+		let pre_upgrade = [
+			<frame_system::Module::<System> as OnRuntimeUpgrade>::pre_upgrade(),
+			COnRuntimeUpgrade::pre_upgrade(),
+			<AllModules as OnRuntimeUpgrade>::pre_upgrade(),
+		];
+
+		let weight = Self::execute_on_runtime_upgrade();
+
+		let post_upgrade = [
+			<frame_system::Module::<System> as OnRuntimeUpgrade>::post_upgrade(),
+			COnRuntimeUpgrade::post_upgrade(),
+			<AllModules as OnRuntimeUpgrade>::post_upgrade(),
+		];
+
+		let failures = pre_upgrade.iter()
+			.chain(post_upgrade.iter())
+			.filter_map(|check| check.err())
+			.collect();
+
+		(weight, failures)
+	}
+
 	/// Returns if the runtime was upgraded since the last time this function was called.
 	fn runtime_upgraded() -> bool {
 		let last = frame_system::LastRuntimeUpgrade::get();
@@ -640,6 +674,22 @@ mod tests {
 			sp_io::storage::set(CUSTOM_ON_RUNTIME_KEY, &true.encode());
 			0
 		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			match sp_io::storage::get(CUSTOM_ON_RUNTIME_KEY) {
+				Some(_) => Err("custom upgrade already applied"),
+				None => Ok(()),
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			match sp_io::storage::get(CUSTOM_ON_RUNTIME_KEY) {
+				Some(_) => Ok(()),
+				None => Err("custom upgrade not applied"),
+			}
+		}
 	}
 
 	type Executive = super::Executive<
@@ -1000,4 +1050,18 @@ mod tests {
 			assert_eq!(sp_io::storage::get(CUSTOM_ON_RUNTIME_KEY).unwrap(), true.encode());
 		});
 	}
+
+	#[cfg(feature = "try-runtime")]
+	#[test]
+	fn try_runtime_upgrade_runs_checks_around_upgrade() {
+		new_test_ext(1).execute_with(|| {
+			let (_, failures) = Executive::try_runtime_upgrade();
+			assert!(failures.is_empty());
+			assert_eq!(&sp_io::storage::get(TEST_KEY).unwrap()[..], *b"module");
+			assert_eq!(sp_io::storage::get(CUSTOM_ON_RUNTIME_KEY).unwrap(), true.encode());
+
+			let (_, failures) = Executive::try_runtime_upgrade();
+			assert_eq!(failures, vec!["custom upgrade already applied"]);
+		});
+	}
 }
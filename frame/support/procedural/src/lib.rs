@@ -22,6 +22,7 @@
 
 mod storage;
 mod construct_runtime;
+mod transactional;
 
 use proc_macro::TokenStream;
 
@@ -123,6 +124,26 @@ use proc_macro::TokenStream;
 ///   Twox128(module_prefix) ++ Twox128(storage_prefix) ++ Hasher1(encode(key1)) ++ Hasher2(encode(key2))
 ///   ```
 ///
+/// * N map: `Foo: nmap hasher($hash1) u32, hasher($hash2) u64, hasher($hash3) u8 => u32`:
+///   Implements the [`StorageNMap`](../frame_support/storage/trait.StorageNMap.html) trait using
+///   the [`StorageNMap generator`](../frame_support/storage/generator/trait.StorageNMap.html).
+///   And [`StoragePrefixedMap`](../frame_support/storage/trait.StoragePrefixedMap.html).
+///
+///   At least one key must be given, each with its own hasher. The keys are passed as a tuple,
+///   e.g. `Foo::get((1, 2, 3))`, and values can be iterated over and removed by any tuple of
+///   leading keys, e.g. `Foo::iter_prefix((1,))` or `Foo::remove_prefix((1, 2))`.
+///
+///   The generator is implemented with:
+///   * `module_prefix`: $module_prefix
+///   * `storage_prefix`: storage_name
+///   * `K`: `(Key<$hash1, u32>, Key<$hash2, u64>, Key<$hash3, u8>)`
+///
+///   Thus keys are stored at:
+///   ```nocompile
+///   Twox128(module_prefix) ++ Twox128(storage_prefix)
+///       ++ Hasher1(encode(key1)) ++ Hasher2(encode(key2)) ++ Hasher3(encode(key3))
+///   ```
+///
 /// Supported hashers (ordered from least to best security):
 ///
 /// * `identity` - Just the unrefined key material. Use only when it is known to be a secure hash
@@ -205,6 +226,21 @@ use proc_macro::TokenStream;
 /// * `Foo::<I>` if the value type is not generic
 /// * `Foo::<T, I>` if the value type is generic
 ///
+/// ## Storage version
+///
+/// The storage layout of a module can be versioned by declaring `storage_version(N)` after the
+/// module prefix, `N` being a `u16`:
+///
+/// ```nocompile
+/// trait Store for Module<T: Trait> as Example storage_version(2) {}
+/// ```
+///
+/// The module then implements `GetStorageVersion`: `current_storage_version` returns `N`, and
+/// `on_chain_storage_version` reads the version stored under the module prefix, `0` if none.
+/// The version `N` is put at genesis, so a module bumping `N` must migrate its storage in
+/// `on_runtime_upgrade` and put the new version once done. Without `storage_version` the current
+/// version is `0`.
+///
 /// ## Where clause
 ///
 /// This macro supports a where clause which will be replicated to all generated types.
@@ -290,3 +326,28 @@ pub fn decl_storage(input: TokenStream) -> TokenStream {
 pub fn construct_runtime(input: TokenStream) -> TokenStream {
 	construct_runtime::construct_runtime(input)
 }
+
+/// Execute the annotated function in a new storage transaction.
+///
+/// The return type of the annotated function must be `Result`. All changes to storage performed
+/// by the annotated function are discarded if it returns `Err`, or committed if `Ok`.
+///
+/// # Example
+///
+/// ```nocompile
+/// #[transactional]
+/// fn value_commits(v: u32) -> result::Result<u32, &'static str> {
+///     Value::set(v);
+///     Ok(v)
+/// }
+///
+/// #[transactional]
+/// fn value_rollbacks(v: u32) -> result::Result<u32, &'static str> {
+///     Value::set(v);
+///     Err("nah")
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn transactional(attr: TokenStream, input: TokenStream) -> TokenStream {
+	transactional::transactional(attr, input).unwrap_or_else(|e| e.to_compile_error().into())
+}
@@ -17,7 +17,7 @@
 //! Builder logic definition used to build genesis storage.
 
 use frame_support_procedural_tools::syn_ext as ext;
-use proc_macro2::TokenStream;
+use proc_macro2::{TokenStream, Span};
 use syn::spanned::Spanned;
 use quote::{quote, quote_spanned};
 use super::super::{DeclStorageDefExt, StorageLineTypeDef};
@@ -29,6 +29,7 @@ pub struct BuilderDef {
 	/// * build block for storage with build attribute.
 	/// * build block for storage with config attribute and no build attribute.
 	/// * build block for extra genesis build expression.
+	/// * build block for the storage version, if declared.
 	pub blocks: Vec<TokenStream>,
 	/// The build blocks requires generic traits.
 	pub is_generic: bool,
@@ -113,10 +114,36 @@ impl BuilderDef {
 							});
 						}}
 					},
+					StorageLineTypeDef::NMap(map) => {
+						let keys = &map.keys;
+						let key_names = (0..keys.len())
+							.map(|i| syn::Ident::new(&format!("k{}", i), Span::call_site()))
+							.collect::<Vec<_>>();
+						quote!{{
+							#data
+							let data: &#scrate::sp_std::vec::Vec<(#( #keys, )* #value_type)> = data;
+							data.iter().for_each(|(#( #key_names, )* v)| {
+								<#storage_struct as #scrate::#storage_trait>::insert::<
+									(#( &#keys, )*), &#value_type
+								>((#( #key_names, )*), v);
+							});
+						}}
+					},
 				});
 			}
 		}
 
+		if def.storage_version.is_some() {
+			let module_struct = &def.module_struct;
+			is_generic = true;
+
+			blocks.push(quote!{{
+				<#module_struct as #scrate::traits::GetStorageVersion>::put_on_chain_storage_version(
+					<#module_struct as #scrate::traits::GetStorageVersion>::current_storage_version()
+				);
+			}});
+		}
+
 		if let Some(builder) = def.extra_genesis_build.as_ref() {
 			is_generic |= ext::expr_contains_ident(&builder, &def.module_runtime_generic);
 
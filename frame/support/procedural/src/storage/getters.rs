@@ -64,6 +64,19 @@ pub fn impl_getters(scrate: &TokenStream, def: &DeclStorageDefExt) -> TokenStrea
 					}
 				}
 			},
+			StorageLineTypeDef::NMap(map) => {
+				let keys = map.to_keys_tuple();
+				let value = &map.value;
+				quote!{
+					#( #[ #attrs ] )*
+					pub fn #get_fn<KArg>(key: KArg) -> #value
+					where
+						KArg: #scrate::storage::key::EncodeLikeTuple<#keys>,
+					{
+						<#storage_struct as #scrate::#storage_trait>::get(key)
+					}
+				}
+			},
 		};
 		getters.extend(getter);
 	}
@@ -62,6 +62,20 @@ fn storage_line_metadata_type(scrate: &TokenStream, line: &StorageLineDefExt) ->
 				}
 			}
 		},
+		StorageLineTypeDef::NMap(map) => {
+			let keys = map.keys.iter()
+				.map(|key| clean_type_string(&quote!(#key).to_string()));
+			let hashers = map.hashers.iter()
+				.map(|hasher| hasher.into_metadata())
+				.map(|hasher| quote!( #scrate::metadata::#hasher ));
+			quote!{
+				#scrate::metadata::StorageEntryType::NMap {
+					keys: #scrate::metadata::DecodeDifferent::Encode(&[ #( #keys, )* ]),
+					hashers: #scrate::metadata::DecodeDifferent::Encode(&[ #( #hashers, )* ]),
+					value: #scrate::metadata::DecodeDifferent::Encode(#value_type),
+				}
+			}
+		},
 	}
 }
 
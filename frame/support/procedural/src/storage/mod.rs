@@ -23,6 +23,7 @@ mod getters;
 mod metadata;
 mod instance_trait;
 mod genesis_config;
+mod storage_version;
 
 use quote::quote;
 use frame_support_procedural_tools::{
@@ -55,6 +56,8 @@ pub struct DeclStorageDef {
 	storage_lines: Vec<StorageLineDef>,
 	/// Name of the crate, used for storage prefixes.
 	crate_name: syn::Ident,
+	/// The storage version declared with `storage_version(N)`, if any.
+	storage_version: Option<u16>,
 }
 
 impl syn::parse::Parse for DeclStorageDef {
@@ -90,6 +93,8 @@ pub struct DeclStorageDefExt {
 	storage_lines: Vec<StorageLineDefExt>,
 	/// Name of the crate, used for storage prefixes.
 	crate_name: syn::Ident,
+	/// The storage version declared with `storage_version(N)`, if any.
+	storage_version: Option<u16>,
 	/// Full struct expansion: `Module<T, I>`.
 	module_struct: proc_macro2::TokenStream,
 	/// Impl block for module: `<T: Trait, I: Instance>`.
@@ -104,9 +109,10 @@ pub struct DeclStorageDefExt {
 
 impl From<DeclStorageDef> for DeclStorageDefExt {
 	fn from(mut def: DeclStorageDef) -> Self {
+		let scrate = generate_crate_access(&hidden_crate_name(&def.hidden_crate), "frame-support");
 		let storage_lines = def.storage_lines.drain(..).collect::<Vec<_>>();
 		let storage_lines = storage_lines.into_iter()
-			.map(|line| StorageLineDefExt::from_def(line, &def))
+			.map(|line| StorageLineDefExt::from_def(line, &def, &scrate))
 			.collect();
 
 		let (
@@ -151,6 +157,7 @@ impl From<DeclStorageDef> for DeclStorageDefExt {
 			extra_genesis_build: def.extra_genesis_build,
 			extra_genesis_config_lines: def.extra_genesis_config_lines,
 			crate_name: def.crate_name,
+			storage_version: def.storage_version,
 			storage_lines,
 			module_struct,
 			module_impl,
@@ -226,7 +233,11 @@ pub struct StorageLineDefExt {
 }
 
 impl StorageLineDefExt {
-	fn from_def(storage_def: StorageLineDef, def: &DeclStorageDef) -> Self {
+	fn from_def(
+		storage_def: StorageLineDef,
+		def: &DeclStorageDef,
+		scrate: &proc_macro2::TokenStream,
+	) -> Self {
 		let is_generic = match &storage_def.storage_type {
 			StorageLineTypeDef::Simple(value) => {
 				ext::type_contains_ident(&value, &def.module_runtime_generic)
@@ -240,12 +251,17 @@ impl StorageLineDefExt {
 					|| ext::type_contains_ident(&map.key2, &def.module_runtime_generic)
 					|| ext::type_contains_ident(&map.value, &def.module_runtime_generic)
 			}
+			StorageLineTypeDef::NMap(map) => {
+				map.keys.iter().any(|key| ext::type_contains_ident(key, &def.module_runtime_generic))
+					|| ext::type_contains_ident(&map.value, &def.module_runtime_generic)
+			}
 		};
 
 		let query_type = match &storage_def.storage_type {
 			StorageLineTypeDef::Simple(value) => value.clone(),
 			StorageLineTypeDef::Map(map) => map.value.clone(),
 			StorageLineTypeDef::DoubleMap(map) => map.value.clone(),
+			StorageLineTypeDef::NMap(map) => map.value.clone(),
 		};
 		let is_option = ext::extract_type_option(&query_type).is_some();
 		let value_type = ext::extract_type_option(&query_type).unwrap_or(query_type.clone());
@@ -291,6 +307,10 @@ impl StorageLineDefExt {
 				let key2 = &map.key2;
 				quote!( StorageDoubleMap<#key1, #key2, #value_type> )
 			},
+			StorageLineTypeDef::NMap(map) => {
+				let key_generator = map.to_key_generator(scrate);
+				quote!( StorageNMap<#key_generator, #value_type> )
+			},
 		};
 
 		let storage_trait = quote!( storage::#storage_trait_truncated );
@@ -328,6 +348,7 @@ impl StorageLineDefExt {
 pub enum StorageLineTypeDef {
 	Map(MapDef),
 	DoubleMap(DoubleMapDef),
+	NMap(NMapDef),
 	Simple(syn::Type),
 }
 
@@ -347,6 +368,31 @@ pub struct DoubleMapDef {
 	pub value: syn::Type,
 }
 
+pub struct NMapDef {
+	pub hashers: Vec<HasherKind>,
+	pub keys: Vec<syn::Type>,
+	/// This is the query value not the inner value used in storage trait implementation.
+	pub value: syn::Type,
+}
+
+impl NMapDef {
+	/// The tuple of the keys, for example: `(u32, u64)`.
+	fn to_keys_tuple(&self) -> proc_macro2::TokenStream {
+		let keys = &self.keys;
+		quote!( ( #( #keys, )* ) )
+	}
+
+	/// The key generator of the map, for example:
+	/// `(Key<Blake2_128Concat, u32>, Key<Twox64Concat, u64>)`.
+	fn to_key_generator(&self, scrate: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+		let keys = self.hashers.iter().zip(&self.keys).map(|(hasher, key)| {
+			let hasher = hasher.to_storage_hasher_struct();
+			quote!( #scrate::storage::key::Key<#scrate::#hasher, #key> )
+		});
+		quote!( ( #( #keys, )* ) )
+	}
+}
+
 pub struct ExtraGenesisLineDef {
 	attrs: Vec<syn::Attribute>,
 	name: syn::Ident,
@@ -391,13 +437,17 @@ impl HasherKind {
 	}
 }
 
+/// Name of the module used to import hidden imports, `decl_storage` by default.
+fn hidden_crate_name(hidden_crate: &Option<syn::Ident>) -> String {
+	hidden_crate.as_ref().map(|i| i.to_string()).unwrap_or_else(|| "decl_storage".to_string())
+}
+
 /// Full implementation of decl_storage.
 pub fn decl_storage_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 	let def = syn::parse_macro_input!(input as DeclStorageDef);
 	let def_ext = DeclStorageDefExt::from(def);
 
-	let hidden_crate_name = def_ext.hidden_crate.as_ref().map(|i| i.to_string())
-		.unwrap_or_else(|| "decl_storage".to_string());
+	let hidden_crate_name = hidden_crate_name(&def_ext.hidden_crate);
 
 	let scrate = generate_crate_access(&hidden_crate_name, "frame-support");
 	let scrate_decl = generate_hidden_includes(&hidden_crate_name, "frame-support");
@@ -408,12 +458,14 @@ pub fn decl_storage_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 	let instance_trait = instance_trait::decl_and_impl(&scrate, &def_ext);
 	let genesis_config = genesis_config::genesis_config_and_build_storage(&scrate, &def_ext);
 	let storage_struct = storage_struct::decl_and_impl(&scrate, &def_ext);
+	let storage_version = storage_version::impl_get_storage_version(&scrate, &def_ext);
 
 	quote!(
 		use #scrate::{
 			StorageValue as _,
 			StorageMap as _,
 			StorageDoubleMap as _,
+			StorageNMap as _,
 			StoragePrefixedMap as _,
 		};
 
@@ -424,5 +476,6 @@ pub fn decl_storage_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 		#instance_trait
 		#genesis_config
 		#storage_struct
+		#storage_version
 	).into()
 }
@@ -21,6 +21,7 @@ use syn::{Ident, Token, spanned::Spanned};
 
 mod keyword {
 	syn::custom_keyword!(hiddencrate);
+	syn::custom_keyword!(storage_version);
 	syn::custom_keyword!(add_extra_genesis);
 	syn::custom_keyword!(extra_genesis_skip_phantom_data_field);
 	syn::custom_keyword!(config);
@@ -28,6 +29,7 @@ mod keyword {
 	syn::custom_keyword!(get);
 	syn::custom_keyword!(map);
 	syn::custom_keyword!(double_map);
+	syn::custom_keyword!(nmap);
 	syn::custom_keyword!(opaque_blake2_256);
 	syn::custom_keyword!(opaque_blake2_128);
 	syn::custom_keyword!(blake2_128_concat);
@@ -90,6 +92,7 @@ struct StorageDefinition {
 	pub mod_gt_token: Token![>],
 	pub as_token: Token![as],
 	pub crate_ident: Ident,
+	pub storage_version: Opt<SpecificStorageVersion>,
 	pub where_clause: Option<syn::WhereClause>,
 	pub content: ext::Braces<ext::Punctuated<DeclStorageLine, Token![;]>>,
 	pub extra_genesis: Opt<AddExtraGenesis>,
@@ -102,6 +105,13 @@ struct SpecificHiddenCrate {
 }
 impl_parse_for_opt!(SpecificHiddenCrate => keyword::hiddencrate);
 
+#[derive(Parse, ToTokens, Debug)]
+struct SpecificStorageVersion {
+	pub keyword: keyword::storage_version,
+	pub version: ext::Parens<syn::LitInt>,
+}
+impl_parse_for_opt!(SpecificStorageVersion => keyword::storage_version);
+
 #[derive(Parse, ToTokens, Debug)]
 struct AddExtraGenesis {
 	pub extragenesis_keyword: keyword::add_extra_genesis,
@@ -198,6 +208,7 @@ impl_parse_for_opt!(DeclStorageBuild => keyword::build);
 enum DeclStorageType {
 	Map(DeclStorageMap),
 	DoubleMap(DeclStorageDoubleMap),
+	NMap(DeclStorageNMap),
 	Simple(syn::Type),
 }
 
@@ -207,6 +218,8 @@ impl syn::parse::Parse for DeclStorageType {
 			Ok(Self::Map(input.parse()?))
 		} else if input.peek(keyword::double_map) {
 			Ok(Self::DoubleMap(input.parse()?))
+		} else if input.peek(keyword::nmap) {
+			Ok(Self::NMap(input.parse()?))
 		} else {
 			Ok(Self::Simple(input.parse()?))
 		}
@@ -234,6 +247,20 @@ struct DeclStorageDoubleMap {
 	pub value: syn::Type,
 }
 
+#[derive(Parse, ToTokens, Debug)]
+struct DeclStorageNMap {
+	pub map_keyword: keyword::nmap,
+	pub keys: ext::PunctuatedTrailing<DeclStorageNMapKey, Token![,]>,
+	pub ass_keyword: Token![=>],
+	pub value: syn::Type,
+}
+
+#[derive(Parse, ToTokens, Debug)]
+struct DeclStorageNMapKey {
+	pub hasher: Opt<SetHasher>,
+	pub key: syn::Type,
+}
+
 #[derive(ToTokens, Debug)]
 enum Hasher {
 	Blake2_256(keyword::opaque_blake2_256),
@@ -378,6 +405,11 @@ pub fn parse(input: syn::parse::ParseStream) -> syn::Result<super::DeclStorageDe
 		def.mod_default_instance,
 	)?;
 
+	let storage_version = match def.storage_version.inner {
+		Some(version) => Some(version.version.content.base10_parse::<u16>()?),
+		None => None,
+	};
+
 	let mut extra_genesis_config_lines = vec![];
 	let mut extra_genesis_build = None;
 
@@ -417,6 +449,7 @@ pub fn parse(input: syn::parse::ParseStream) -> syn::Result<super::DeclStorageDe
 		module_runtime_trait: def.mod_param_bound,
 		where_clause: def.where_clause,
 		crate_name: def.crate_ident,
+		storage_version,
 		module_instance,
 		extra_genesis_build,
 		extra_genesis_config_lines,
@@ -485,6 +518,27 @@ fn parse_storage_line_defs(
 					value: map.value,
 				}
 			),
+			DeclStorageType::NMap(map) => {
+				if map.keys.inner.is_empty() {
+					return Err(syn::Error::new(
+						map.map_keyword.span(),
+						"`nmap` needs at least one key, e.g. `nmap hasher(blake2_128_concat) u32 => u32`.",
+					));
+				}
+				let mut hashers = Vec::new();
+				let mut keys = Vec::new();
+				for key in map.keys.inner {
+					hashers.push(key.hasher.inner.ok_or_else(no_hasher_error)?.into());
+					keys.push(key.key);
+				}
+				super::StorageLineTypeDef::NMap(
+					super::NMapDef {
+						hashers,
+						keys,
+						value: map.value,
+					}
+				)
+			},
 			DeclStorageType::Simple(expr) => super::StorageLineTypeDef::Simple(expr),
 		};
 
@@ -0,0 +1,52 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implementation of `GetStorageVersion` on module structure.
+
+use proc_macro2::{TokenStream, Span};
+use quote::quote;
+use syn::Ident;
+use super::{DeclStorageDefExt, instance_trait::INHERENT_INSTANCE_NAME};
+
+pub fn impl_get_storage_version(scrate: &TokenStream, def: &DeclStorageDefExt) -> TokenStream {
+	let module_struct = &def.module_struct;
+	let module_impl = &def.module_impl;
+	let where_clause = &def.where_clause;
+	let version = def.storage_version.unwrap_or_default();
+
+	// Contains accessor to instance, used to get prefixes
+	let instance_or_inherent = if let Some(instance) = def.module_instance.as_ref() {
+		instance.instance_generic.clone()
+	} else {
+		Ident::new(INHERENT_INSTANCE_NAME, Span::call_site())
+	};
+
+	quote!(
+		impl#module_impl #scrate::traits::GetStorageVersion for #module_struct #where_clause {
+			fn current_storage_version() -> #scrate::traits::StorageVersion {
+				#scrate::traits::StorageVersion::new(#version)
+			}
+
+			fn on_chain_storage_version() -> #scrate::traits::StorageVersion {
+				#scrate::traits::StorageVersion::get(#instance_or_inherent::PREFIX.as_bytes())
+			}
+
+			fn put_on_chain_storage_version(version: #scrate::traits::StorageVersion) {
+				version.put(#instance_or_inherent::PREFIX.as_bytes())
+			}
+		}
+	)
+}
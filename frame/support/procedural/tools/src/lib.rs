@@ -45,6 +45,20 @@ pub fn generate_crate_access(unique_id: &str, def_crate: &str) -> TokenStream {
 	}
 }
 
+/// Generates the access to the `def_crate` crate by its name, for use in 2018 edition paths.
+///
+/// Unlike [`generate_crate_access`], no hidden includes are required, so the access can be used
+/// in any position, e.g. within a function body.
+pub fn generate_crate_access_2018(def_crate: &str) -> Result<Ident, Error> {
+	if std::env::var("CARGO_PKG_NAME").unwrap() == def_crate {
+		Ok(Ident::new(&def_crate.replace("-", "_"), Span::call_site()))
+	} else {
+		crate_name(def_crate)
+			.map(|name| Ident::new(&name, Span::call_site()))
+			.map_err(|e| Error::new(Span::call_site(), &e))
+	}
+}
+
 /// Generates the hidden includes that are required to make the macro independent from its scope.
 pub fn generate_hidden_includes(unique_id: &str, def_crate: &str) -> TokenStream {
 	if std::env::var("CARGO_PKG_NAME").unwrap() == def_crate {
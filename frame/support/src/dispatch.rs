@@ -189,6 +189,31 @@ impl<T> Parameter for T where T: Codec + EncodeLike + Clone + Eq + fmt::Debug {}
 /// # fn main() {}
 /// ```
 ///
+/// ### Transactional Function Example
+///
+/// Attributes following `#[weight]` are kept on the generated function. A function annotated with
+/// [`#[transactional]`](./attr.transactional.html) discards all its changes to storage if it
+/// returns an error, so it may write before all its checks are done.
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate frame_support;
+/// # use frame_support::{dispatch, transactional};
+/// # use frame_system::{self as system, Trait, ensure_signed};
+/// decl_module! {
+/// 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+/// 		#[weight = 0]
+/// 		#[transactional]
+/// 		fn my_transactional_function(origin) -> dispatch::DispatchResult {
+/// 			ensure_signed(origin)?;
+/// 			// Changes made here are discarded if an error is returned below.
+/// 			Ok(())
+/// 		}
+/// 	}
+/// }
+/// # fn main() {}
+/// ```
+///
 /// ## Multiple Module Instances Example
 ///
 /// A Substrate module can be built such that multiple instances of the same module can be used within a single
@@ -256,6 +281,13 @@ impl<T> Parameter for T where T: Codec + EncodeLike + Clone + Eq + fmt::Debug {}
 /// [`OnRuntimeUpgrade`](../sp_runtime/traits/trait.OnRuntimeUpgrade.html) trait.
 /// Function signature must be `fn on_runtime_upgrade() -> frame_support::weights::Weight`.
 ///
+/// * `pre_upgrade` and `post_upgrade`: Check the state before and after the runtime upgrade.
+/// They are only compiled with the `try-runtime` feature of the pallet, and only run by offline
+/// tooling testing an upgrade, never on chain. Using them will implement the checks of the
+/// [`OnRuntimeUpgrade`](./traits/trait.OnRuntimeUpgrade.html) trait.
+/// Function signatures must be `fn pre_upgrade() -> Result<(), &'static str>` and
+/// `fn post_upgrade() -> Result<(), &'static str>`.
+///
 /// * `on_initialize`: Executes at the beginning of a block. Using this function will
 /// implement the [`OnInitialize`](./trait.OnInitialize.html) trait.
 /// Function signature can be either:
@@ -461,7 +493,7 @@ macro_rules! decl_module {
 		{ $( $other_where_bounds:tt )* }
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
-		{}
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $on_finalize:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
@@ -485,7 +517,7 @@ macro_rules! decl_module {
 		{ $( $other_where_bounds:tt )* }
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
-		{}
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $on_finalize:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
@@ -501,7 +533,7 @@ macro_rules! decl_module {
 			by the function directly."
 		);
 	};
-	// Add on_runtime_upgrade
+	// Add on_runtime_upgrade, ahead of any upgrade check.
 	(@normalize
 		$(#[$attr:meta])*
 		pub struct $mod_type:ident<
@@ -511,7 +543,7 @@ macro_rules! decl_module {
 		{ $( $other_where_bounds:tt )* }
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
-		{}
+		{ $( $upgrade_checks:tt )* }
 		{ $( $on_finalize:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
@@ -530,6 +562,85 @@ macro_rules! decl_module {
 			{ $( $on_initialize )* }
 			{
 				fn on_runtime_upgrade( $( $param_name : $param ),* ) -> $return { $( $impl )* }
+				$( $upgrade_checks )*
+			}
+			{ $( $on_finalize )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			{ $( $error_type )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
+	// Add pre_upgrade, after on_runtime_upgrade if any.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<
+			$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?
+		>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
+		{ $( $on_finalize:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		{ $( $error_type:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		fn pre_upgrade() -> $return:ty { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{
+				$( $on_runtime_upgrade )*
+				fn pre_upgrade() -> $return { $( $impl )* }
+			}
+			{ $( $on_finalize )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			{ $( $error_type )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
+	// Add post_upgrade, after on_runtime_upgrade if any.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<
+			$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?
+		>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
+		{ $( $on_finalize:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		{ $( $error_type:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		fn post_upgrade() -> $return:ty { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{
+				$( $on_runtime_upgrade )*
+				fn post_upgrade() -> $return { $( $impl )* }
 			}
 			{ $( $on_finalize )* }
 			{ $( $offchain )* }
@@ -807,6 +918,7 @@ macro_rules! decl_module {
 		[ $( $dispatchables:tt )* ]
 		$(#[doc = $doc_attr:tt])*
 		#[weight = $weight:expr]
+		$(#[$fn_attr:meta])*
 		$fn_vis:vis fn $fn_name:ident(
 			$origin:ident $( , $(#[$codec_attr:ident])* $param_name:ident : $param:ty )* $(,)?
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -830,6 +942,7 @@ macro_rules! decl_module {
 				$( $dispatchables )*
 				$(#[doc = $doc_attr])*
 				#[weight = $weight]
+				$(#[$fn_attr])*
 				$fn_vis fn $fn_name(
 					$origin $( , $(#[$codec_attr])* $param_name : $param )*
 				) $( -> $result )* { $( $impl )* }
@@ -1057,29 +1170,60 @@ macro_rules! decl_module {
 	(@impl_on_runtime_upgrade
 		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
 		{ $( $other_where_bounds:tt )* }
-		fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* }
+		$( fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* } )?
+		$( fn pre_upgrade() -> $pre_return:ty { $( $pre_impl:tt )* } )?
+		$( fn post_upgrade() -> $post_return:ty { $( $post_impl:tt )* } )?
 	) => {
 		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
 			$crate::traits::OnRuntimeUpgrade
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
-			fn on_runtime_upgrade() -> $return {
-				$crate::sp_tracing::enter_span!("on_runtime_upgrade");
-				{ $( $impl )* }
-			}
+			$(
+				fn on_runtime_upgrade() -> $return {
+					$crate::sp_tracing::enter_span!("on_runtime_upgrade");
+					{ $( $impl )* }
+				}
+			)?
+
+			$(
+				#[cfg(feature = "try-runtime")]
+				fn pre_upgrade() -> $pre_return { $( $pre_impl )* }
+			)?
+
+			$(
+				#[cfg(feature = "try-runtime")]
+				fn post_upgrade() -> $post_return { $( $post_impl )* }
+			)?
 		}
 	};
 
+	// `post_upgrade` was given before `pre_upgrade`.
 	(@impl_on_runtime_upgrade
 		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
 		{ $( $other_where_bounds:tt )* }
+		$( fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* } )?
+		fn post_upgrade() -> $post_return:ty { $( $post_impl:tt )* }
+		fn pre_upgrade() -> $pre_return:ty { $( $pre_impl:tt )* }
 	) => {
-		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
-			$crate::traits::OnRuntimeUpgrade
-			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
-		{}
+		$crate::decl_module! {
+			@impl_on_runtime_upgrade
+			$module<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>;
+			{ $( $other_where_bounds )* }
+			$( fn on_runtime_upgrade() -> $return { $( $impl )* } )?
+			fn pre_upgrade() -> $pre_return { $( $pre_impl )* }
+			fn post_upgrade() -> $post_return { $( $post_impl )* }
+		}
 	};
 
+	(@impl_on_runtime_upgrade
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		$( $duplicated:tt )*
+	) => {
+		compile_error!(
+			"`on_runtime_upgrade`, `pre_upgrade` and `post_upgrade` can only be passed once as input."
+		);
+	};
 
 	(@impl_on_finalize
 		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
@@ -1166,12 +1310,12 @@ macro_rules! decl_module {
 		$origin_ty:ty;
 		$error_type:ty;
 		$ignore:ident;
-		$(#[doc = $doc_attr:tt])*
+		$(#[$fn_attr:meta])*
 		$vis:vis fn $name:ident (
 			$origin:ident $(, $param:ident : $param_ty:ty )*
 		) { $( $impl:tt )* }
 	) => {
-		$(#[doc = $doc_attr])*
+		$(#[$fn_attr])*
 		#[allow(unreachable_code)]
 		$vis fn $name(
 			$origin: $origin_ty $(, $param: $param_ty )*
@@ -1188,12 +1332,12 @@ macro_rules! decl_module {
 		$origin_ty:ty;
 		$error_type:ty;
 		$ignore:ident;
-		$(#[doc = $doc_attr:tt])*
+		$(#[$fn_attr:meta])*
 		$vis:vis fn $name:ident (
 			$origin:ident $(, $param:ident : $param_ty:ty )*
 		) -> $result:ty { $( $impl:tt )* }
 	) => {
-		$(#[doc = $doc_attr])*
+		$(#[$fn_attr])*
 		$vis fn $name($origin: $origin_ty $(, $param: $param_ty )* ) -> $result {
 			$crate::sp_tracing::enter_span!(stringify!($name));
 			$( $impl )*
@@ -1325,6 +1469,7 @@ macro_rules! decl_module {
 			$(
 				$(#[doc = $doc_attr:tt])*
 				#[weight = $weight:expr]
+				$(#[$fn_attr:meta])*
 				$fn_vis:vis fn $fn_name:ident(
 					$from:ident $( , $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 				) $( -> $result:ty )* { $( $impl:tt )* }
@@ -1401,6 +1546,7 @@ macro_rules! decl_module {
 					$error_type;
 					$from;
 					$(#[doc = $doc_attr])*
+					$(#[$fn_attr])*
 					$fn_vis fn $fn_name (
 						$from $(, $param_name : $param )*
 					) $( -> $result )* { $( $impl )* }
@@ -2092,7 +2238,9 @@ mod tests {
 
 			fn on_initialize(n: T::BlockNumber,) -> Weight { if n.into() == 42 { panic!("on_initialize") } 7 }
 			fn on_finalize(n: T::BlockNumber,) { if n.into() == 42 { panic!("on_finalize") } }
+			fn pre_upgrade() -> Result<(), &'static str> { Ok(()) }
 			fn on_runtime_upgrade() -> Weight { 10 }
+			fn post_upgrade() -> Result<(), &'static str> { Err("post_upgrade") }
 			fn offchain_worker() {}
 		}
 	}
@@ -2241,6 +2389,13 @@ mod tests {
 		assert_eq!(<Module<TraitImpl> as OnRuntimeUpgrade>::on_runtime_upgrade(), 10);
 	}
 
+	#[cfg(feature = "try-runtime")]
+	#[test]
+	fn upgrade_checks_should_work() {
+		assert_eq!(<Module<TraitImpl> as OnRuntimeUpgrade>::pre_upgrade(), Ok(()));
+		assert_eq!(<Module<TraitImpl> as OnRuntimeUpgrade>::post_upgrade(), Err("post_upgrade"));
+	}
+
 	#[test]
 	fn weight_should_attach_to_call_enum() {
 		// operational.
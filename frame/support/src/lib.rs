@@ -73,7 +73,8 @@ pub use self::hash::{
 };
 pub use self::storage::{
 	StorageValue, StorageMap, StorageDoubleMap, StoragePrefixedMap, IterableStorageMap,
-	IterableStorageDoubleMap, migration
+	IterableStorageDoubleMap, StorageNMap, IterableStorageNMap, migration, BoundedVec,
+	BoundedBTreeMap,
 };
 pub use self::dispatch::{Parameter, Callable, IsSubType};
 pub use sp_runtime::{self, ConsensusEngineId, print, traits::Printable};
@@ -152,7 +153,7 @@ macro_rules! ord_parameter_types {
 }
 
 #[doc(inline)]
-pub use frame_support_procedural::{decl_storage, construct_runtime};
+pub use frame_support_procedural::{decl_storage, construct_runtime, transactional};
 
 /// Return Err of the expression: `return Err($expression);`.
 ///
@@ -289,6 +290,9 @@ mod tests {
 				=> Option<T::BlockNumber>;
 			pub AppendableDM:
 				double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::BlockNumber => Vec<u32>;
+			pub DataNM config(test_n_map_config) build(|_| vec![(15u32, 16u32, 17u8, 42u64)]):
+				nmap hasher(twox_64_concat) u32, hasher(blake2_128_concat) u32, hasher(identity) u8
+				=> u64;
 		}
 	}
 
@@ -490,6 +494,48 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn n_map_basic_insert_remove_remove_prefix_should_work() {
+		new_test_ext().execute_with(|| {
+			type NMap = DataNM;
+			// initialized during genesis
+			assert_eq!(NMap::get((15u32, 16u32, 17u8)), 42u64);
+
+			// get / insert / take
+			let key = (17u32, 18u32, 19u8);
+			assert_eq!(NMap::get(key), 0u64);
+			NMap::insert(key, 4u64);
+			assert_eq!(NMap::get(key), 4u64);
+			assert_eq!(NMap::take((&key.0, &key.1, &key.2)), 4u64);
+			assert_eq!(NMap::get(key), 0u64);
+
+			// mutate
+			NMap::mutate(key, |val| {
+				*val = 15;
+			});
+			assert_eq!(NMap::get(key), 15u64);
+
+			// remove
+			NMap::remove(key);
+			assert_eq!(NMap::get(key), 0u64);
+
+			// remove prefix of two keys, then of one key
+			let (key1, key2, key3) = key;
+			NMap::insert((key1, key2, key3), 4u64);
+			NMap::insert((key1, key2, key3 + 1), 4u64);
+			NMap::insert((key1, key2 + 1, key3), 4u64);
+			NMap::insert((key1 + 1, key2, key3), 4u64);
+			NMap::remove_prefix((key1, key2));
+			assert_eq!(NMap::get((key1, key2, key3)), 0u64);
+			assert_eq!(NMap::get((key1, key2, key3 + 1)), 0u64);
+			assert_eq!(NMap::get((key1, key2 + 1, key3)), 4u64);
+			assert_eq!(NMap::get((key1 + 1, key2, key3)), 4u64);
+			NMap::remove_prefix((key1,));
+			assert_eq!(NMap::get((key1, key2 + 1, key3)), 0u64);
+			assert_eq!(NMap::get((key1 + 1, key2, key3)), 4u64);
+		});
+	}
+
 	#[test]
 	fn double_map_append_should_work() {
 		new_test_ext().execute_with(|| {
@@ -624,6 +670,23 @@ mod tests {
 					),
 					documentation: DecodeDifferent::Encode(&[]),
 				},
+				StorageEntryMetadata {
+					name: DecodeDifferent::Encode("DataNM"),
+					modifier: StorageEntryModifier::Default,
+					ty: StorageEntryType::NMap {
+						keys: DecodeDifferent::Encode(&["u32", "u32", "u8"]),
+						hashers: DecodeDifferent::Encode(&[
+							StorageHasher::Twox64Concat,
+							StorageHasher::Blake2_128Concat,
+							StorageHasher::Identity,
+						]),
+						value: DecodeDifferent::Encode("u64"),
+					},
+					default: DecodeDifferent::Encode(
+						DefaultByteGetter(&__GetByteStructDataNM(PhantomData::<Test>))
+					),
+					documentation: DecodeDifferent::Encode(&[]),
+				},
 			]
 		),
 	};
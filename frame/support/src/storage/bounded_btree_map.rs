@@ -0,0 +1,230 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A map with a maximum number of entries, checked on every insertion and when decoding.
+
+use sp_std::{collections::btree_map::BTreeMap, convert::TryFrom, fmt, marker::PhantomData, ops::Deref};
+use codec::{Compact, Decode, DecodeLength, Encode, EncodeLike, Input, Output};
+use crate::traits::Get;
+
+/// A `BTreeMap` holding at most `S::get()` entries.
+///
+/// It encodes as a `BTreeMap<K, V>`, but decoding fails beyond the bound, see
+/// [`BoundedVec`](../bounded_vec/struct.BoundedVec.html) for its use in storage and calls.
+///
+/// The entries are readable through `Deref<Target = BTreeMap<K, V>>`; insertions that could break
+/// the bound are fallible.
+pub struct BoundedBTreeMap<K, V, S>(BTreeMap<K, V>, PhantomData<S>);
+
+impl<K: Ord, V, S: Get<u32>> BoundedBTreeMap<K, V, S> {
+	/// The maximum number of entries.
+	pub fn bound() -> usize {
+		S::get() as usize
+	}
+
+	/// Consume self, and return the inner `BTreeMap`.
+	pub fn into_inner(self) -> BTreeMap<K, V> {
+		self.0
+	}
+
+	/// Insert `value` under `key`, if the bound allows it. Replacing the value of a key already in
+	/// the map is always allowed.
+	///
+	/// Returns the value replaced, if any.
+	pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, ()> {
+		if self.0.len() < Self::bound() || self.0.contains_key(&key) {
+			Ok(self.0.insert(key, value))
+		} else {
+			Err(())
+		}
+	}
+
+	/// Remove the entry of `key`, and return its value if any.
+	pub fn remove(&mut self, key: &K) -> Option<V> {
+		self.0.remove(key)
+	}
+
+	/// A mutable reference to the value of `key`, if any.
+	pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+		self.0.get_mut(key)
+	}
+}
+
+impl<K: Ord, V, S> Default for BoundedBTreeMap<K, V, S> {
+	fn default() -> Self {
+		Self(BTreeMap::new(), PhantomData)
+	}
+}
+
+impl<K: Clone, V: Clone, S> Clone for BoundedBTreeMap<K, V, S> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone(), PhantomData)
+	}
+}
+
+impl<K: PartialEq, V: PartialEq, S> PartialEq for BoundedBTreeMap<K, V, S> {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 == other.0
+	}
+}
+
+impl<K: PartialEq, V: PartialEq, S> PartialEq<BTreeMap<K, V>> for BoundedBTreeMap<K, V, S> {
+	fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+		&self.0 == other
+	}
+}
+
+impl<K: Eq, V: Eq, S> Eq for BoundedBTreeMap<K, V, S> {}
+
+impl<K: fmt::Debug, V: fmt::Debug, S: Get<u32>> fmt::Debug for BoundedBTreeMap<K, V, S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("BoundedBTreeMap").field(&self.0).field(&S::get()).finish()
+	}
+}
+
+impl<K: Ord, V, S: Get<u32>> TryFrom<BTreeMap<K, V>> for BoundedBTreeMap<K, V, S> {
+	type Error = ();
+
+	fn try_from(map: BTreeMap<K, V>) -> Result<Self, Self::Error> {
+		if map.len() <= Self::bound() {
+			Ok(Self(map, PhantomData))
+		} else {
+			Err(())
+		}
+	}
+}
+
+impl<K, V, S> From<BoundedBTreeMap<K, V, S>> for BTreeMap<K, V> {
+	fn from(bounded: BoundedBTreeMap<K, V, S>) -> Self {
+		bounded.0
+	}
+}
+
+impl<K, V, S> Deref for BoundedBTreeMap<K, V, S> {
+	type Target = BTreeMap<K, V>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<K, V, S> IntoIterator for BoundedBTreeMap<K, V, S> {
+	type Item = (K, V);
+	type IntoIter = sp_std::collections::btree_map::IntoIter<K, V>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a, K, V, S> IntoIterator for &'a BoundedBTreeMap<K, V, S> {
+	type Item = (&'a K, &'a V);
+	type IntoIter = sp_std::collections::btree_map::Iter<'a, K, V>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
+
+impl<K: Encode, V: Encode, S> Encode for BoundedBTreeMap<K, V, S> {
+	fn size_hint(&self) -> usize {
+		self.0.size_hint()
+	}
+
+	fn encode_to<W: Output>(&self, dest: &mut W) {
+		self.0.encode_to(dest)
+	}
+}
+
+impl<K: Encode, V: Encode, S> EncodeLike for BoundedBTreeMap<K, V, S> {}
+impl<K: Encode, V: Encode, S> EncodeLike<BTreeMap<K, V>> for BoundedBTreeMap<K, V, S> {}
+
+impl<K: Decode + Ord, V: Decode, S: Get<u32>> Decode for BoundedBTreeMap<K, V, S> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+		// Check the length prefix first, so no entry is decoded beyond the bound.
+		let len = <Compact<u32>>::decode(input)?.0;
+		if len > S::get() {
+			return Err("BoundedBTreeMap exceeds its bound".into());
+		}
+		let map = (0..len)
+			.map(|_| <(K, V)>::decode(input))
+			.collect::<Result<BTreeMap<_, _>, _>>()?;
+		Ok(Self(map, PhantomData))
+	}
+}
+
+impl<K, V, S> DecodeLength for BoundedBTreeMap<K, V, S> {
+	fn len(self_encoded: &[u8]) -> Result<usize, codec::Error> {
+		<BTreeMap<K, V> as DecodeLength>::len(self_encoded)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<K: serde::Serialize, V: serde::Serialize, S> serde::Serialize for BoundedBTreeMap<K, V, S> {
+	fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+		self.0.serialize(serializer)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de, K, V, S> serde::Deserialize<'de> for BoundedBTreeMap<K, V, S> where
+	K: serde::Deserialize<'de> + Ord,
+	V: serde::Deserialize<'de>,
+	S: Get<u32>,
+{
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let map = BTreeMap::<K, V>::deserialize(deserializer)?;
+		Self::try_from(map)
+			.map_err(|_| serde::de::Error::custom("BoundedBTreeMap exceeds its bound"))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::parameter_types;
+
+	parameter_types! {
+		pub const Three: u32 = 3;
+	}
+
+	fn map(keys: &[u32]) -> BTreeMap<u32, u32> {
+		keys.iter().map(|k| (*k, *k * 10)).collect()
+	}
+
+	#[test]
+	fn try_insert_respects_bound() {
+		let mut bounded = BoundedBTreeMap::<u32, u32, Three>::try_from(map(&[1, 2])).unwrap();
+		assert_eq!(bounded.try_insert(3, 30), Ok(None));
+		assert_eq!(bounded.try_insert(4, 40), Err(()));
+		// Replacing a value does not grow the map.
+		assert_eq!(bounded.try_insert(3, 31), Ok(Some(30)));
+		assert_eq!(bounded.remove(&1), Some(10));
+		assert_eq!(bounded.try_insert(4, 40), Ok(None));
+		assert_eq!(bounded.into_inner().into_iter().collect::<Vec<_>>(), vec![(2, 20), (3, 31), (4, 40)]);
+	}
+
+	#[test]
+	fn decode_fails_beyond_bound() {
+		let bounded = BoundedBTreeMap::<u32, u32, Three>::try_from(map(&[1, 2, 3])).unwrap();
+		assert_eq!(bounded.encode(), map(&[1, 2, 3]).encode());
+		assert_eq!(BoundedBTreeMap::<u32, u32, Three>::decode(&mut &bounded.encode()[..]).unwrap(), bounded);
+
+		let encoded = map(&[1, 2, 3, 4]).encode();
+		assert!(BoundedBTreeMap::<u32, u32, Three>::decode(&mut &encoded[..]).is_err());
+		assert!(BoundedBTreeMap::<u32, u32, Three>::try_from(map(&[1, 2, 3, 4])).is_err());
+	}
+}
@@ -0,0 +1,269 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A vector with a maximum length, checked on every mutation and when decoding.
+
+use sp_std::{prelude::*, convert::TryFrom, fmt, marker::PhantomData, ops::Deref};
+use codec::{Compact, Decode, DecodeLength, Encode, EncodeLike, Input, Output};
+use crate::traits::Get;
+
+/// A vector holding at most `S::get()` items.
+///
+/// It encodes as a `Vec<T>`, so it can replace one in storage without any migration, but decoding
+/// fails beyond the bound. Used as the type of a storage item or of a call argument, the length of
+/// the value is thus checked by construction. The metadata of the item or argument names the `Get`
+/// type of the bound, which the module should expose as a constant.
+///
+/// The items are readable through `Deref<Target = Vec<T>>`; mutations that could break the bound
+/// are fallible.
+pub struct BoundedVec<T, S>(Vec<T>, PhantomData<S>);
+
+impl<T, S: Get<u32>> BoundedVec<T, S> {
+	/// The maximum number of items.
+	pub fn bound() -> usize {
+		S::get() as usize
+	}
+
+	/// Consume self, and return the inner `Vec`.
+	pub fn into_inner(self) -> Vec<T> {
+		self.0
+	}
+
+	/// Push `element` at the end, if the bound allows it.
+	pub fn try_push(&mut self, element: T) -> Result<(), ()> {
+		if self.len() < Self::bound() {
+			self.0.push(element);
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+
+	/// Insert `element` at `index`, if the bound allows it.
+	///
+	/// Panics if `index > len`, as `Vec::insert` does.
+	pub fn try_insert(&mut self, index: usize, element: T) -> Result<(), ()> {
+		if self.len() < Self::bound() {
+			self.0.insert(index, element);
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+
+	/// Remove and return the item at `index`, shifting the following items.
+	///
+	/// Panics if `index` is out of bounds, as `Vec::remove` does.
+	pub fn remove(&mut self, index: usize) -> T {
+		self.0.remove(index)
+	}
+
+	/// Remove and return the item at `index`, replacing it by the last item.
+	///
+	/// Panics if `index` is out of bounds, as `Vec::swap_remove` does.
+	pub fn swap_remove(&mut self, index: usize) -> T {
+		self.0.swap_remove(index)
+	}
+
+	/// Retain only the items for which `f` returns `true`.
+	pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+		self.0.retain(f)
+	}
+
+	/// A mutable reference to the item at `index`, if any.
+	pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+		self.0.get_mut(index)
+	}
+}
+
+impl<T, S> Default for BoundedVec<T, S> {
+	fn default() -> Self {
+		Self(Vec::new(), PhantomData)
+	}
+}
+
+impl<T: Clone, S> Clone for BoundedVec<T, S> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone(), PhantomData)
+	}
+}
+
+impl<T: PartialEq, S> PartialEq for BoundedVec<T, S> {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 == other.0
+	}
+}
+
+impl<T: PartialEq, S> PartialEq<Vec<T>> for BoundedVec<T, S> {
+	fn eq(&self, other: &Vec<T>) -> bool {
+		&self.0 == other
+	}
+}
+
+impl<T: Eq, S> Eq for BoundedVec<T, S> {}
+
+impl<T: fmt::Debug, S: Get<u32>> fmt::Debug for BoundedVec<T, S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("BoundedVec").field(&self.0).field(&S::get()).finish()
+	}
+}
+
+impl<T, S: Get<u32>> TryFrom<Vec<T>> for BoundedVec<T, S> {
+	type Error = ();
+
+	fn try_from(items: Vec<T>) -> Result<Self, Self::Error> {
+		if items.len() <= Self::bound() {
+			Ok(Self(items, PhantomData))
+		} else {
+			Err(())
+		}
+	}
+}
+
+impl<T, S> From<BoundedVec<T, S>> for Vec<T> {
+	fn from(bounded: BoundedVec<T, S>) -> Self {
+		bounded.0
+	}
+}
+
+impl<T, S> Deref for BoundedVec<T, S> {
+	type Target = Vec<T>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<T, S> AsRef<[T]> for BoundedVec<T, S> {
+	fn as_ref(&self) -> &[T] {
+		&self.0
+	}
+}
+
+impl<T, S> IntoIterator for BoundedVec<T, S> {
+	type Item = T;
+	type IntoIter = sp_std::vec::IntoIter<T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a, T, S> IntoIterator for &'a BoundedVec<T, S> {
+	type Item = &'a T;
+	type IntoIter = sp_std::slice::Iter<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
+
+impl<T: Encode, S> Encode for BoundedVec<T, S> {
+	fn size_hint(&self) -> usize {
+		self.0.size_hint()
+	}
+
+	fn encode_to<W: Output>(&self, dest: &mut W) {
+		self.0.encode_to(dest)
+	}
+}
+
+impl<T: Encode, S> EncodeLike for BoundedVec<T, S> {}
+impl<T: Encode, S> EncodeLike<Vec<T>> for BoundedVec<T, S> {}
+
+impl<T: Decode, S: Get<u32>> Decode for BoundedVec<T, S> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+		// Check the length prefix first, so no item is decoded beyond the bound.
+		let len = <Compact<u32>>::decode(input)?.0;
+		if len > S::get() {
+			return Err("BoundedVec exceeds its bound".into());
+		}
+		let items = (0..len).map(|_| T::decode(input)).collect::<Result<Vec<_>, _>>()?;
+		Ok(Self(items, PhantomData))
+	}
+}
+
+impl<T, S> DecodeLength for BoundedVec<T, S> {
+	fn len(self_encoded: &[u8]) -> Result<usize, codec::Error> {
+		<Vec<T> as DecodeLength>::len(self_encoded)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: serde::Serialize, S> serde::Serialize for BoundedVec<T, S> {
+	fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+		self.0.serialize(serializer)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de, T: serde::Deserialize<'de>, S: Get<u32>> serde::Deserialize<'de> for BoundedVec<T, S> {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let items = Vec::<T>::deserialize(deserializer)?;
+		Self::try_from(items)
+			.map_err(|_| serde::de::Error::custom("BoundedVec exceeds its bound"))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use sp_io::TestExternalities;
+	use crate::{parameter_types, storage::unhashed};
+
+	parameter_types! {
+		pub const Seven: u32 = 7;
+	}
+
+	#[test]
+	fn try_push_respects_bound() {
+		let mut bounded = BoundedVec::<u32, Seven>::try_from(vec![1, 2, 3, 4, 5, 6]).unwrap();
+		assert_eq!(bounded.try_push(7), Ok(()));
+		assert_eq!(bounded.try_push(8), Err(()));
+		assert_eq!(bounded.try_insert(0, 0), Err(()));
+		assert_eq!(bounded.remove(0), 1);
+		assert_eq!(bounded.try_insert(0, 0), Ok(()));
+		assert_eq!(bounded, vec![0, 2, 3, 4, 5, 6, 7]);
+	}
+
+	#[test]
+	fn try_from_respects_bound() {
+		assert!(BoundedVec::<u32, Seven>::try_from(vec![0; 7]).is_ok());
+		assert!(BoundedVec::<u32, Seven>::try_from(vec![0; 8]).is_err());
+	}
+
+	#[test]
+	fn decode_fails_beyond_bound() {
+		let bounded = BoundedVec::<u32, Seven>::try_from(vec![1, 2, 3]).unwrap();
+		assert_eq!(bounded.encode(), vec![1u32, 2, 3].encode());
+		assert_eq!(BoundedVec::<u32, Seven>::decode(&mut &bounded.encode()[..]).unwrap(), bounded);
+
+		let encoded = vec![0u32; 8].encode();
+		assert!(BoundedVec::<u32, Seven>::decode(&mut &encoded[..]).is_err());
+	}
+
+	#[test]
+	fn reads_vec_from_storage() {
+		TestExternalities::default().execute_with(|| {
+			unhashed::put(b"foo", &vec![1u32, 2, 3]);
+			let bounded = unhashed::get::<BoundedVec<u32, Seven>>(b"foo").unwrap();
+			assert_eq!(bounded, vec![1, 2, 3]);
+
+			unhashed::put(b"foo", &vec![0u32; 8]);
+			assert_eq!(unhashed::get::<BoundedVec<u32, Seven>>(b"foo"), None);
+		});
+	}
+}
@@ -346,19 +346,34 @@ impl<K1, K2, V, G> storage::StorageDoubleMap<K1, K2, V> for G where
 
 /// Iterate over a prefix and decode raw_key and raw_value into `T`.
 pub struct MapIterator<T> {
-	prefix: Vec<u8>,
-	previous_key: Vec<u8>,
+	pub(super) prefix: Vec<u8>,
+	pub(super) previous_key: Vec<u8>,
 	/// If true then value are removed while iterating
-	drain: bool,
+	pub(super) drain: bool,
+	/// If true then no element is left to iterate over.
+	pub(super) finished: bool,
 	/// Function that take `(raw_key_without_prefix, raw_value)` and decode `T`.
 	/// `raw_key_without_prefix` is the raw storage key without the prefix iterated on.
-	closure: fn(&[u8], &[u8]) -> Result<T, codec::Error>,
+	pub(super) closure: fn(&[u8], &[u8]) -> Result<T, codec::Error>,
+}
+
+impl<T> MapIterator<T> {
+	/// The raw key of the last element iterated over, or the prefix iterated on if there is none.
+	///
+	/// This is the cursor to give to `iter_from` or `iter_prefix_from` to resume the iteration
+	/// after this element.
+	pub fn last_raw_key(&self) -> &[u8] {
+		&self.previous_key
+	}
 }
 
 impl<T> Iterator for MapIterator<T> {
 	type Item = T;
 
 	fn next(&mut self) -> Option<Self::Item> {
+		if self.finished {
+			return None
+		}
 		loop {
 			let maybe_next = sp_io::storage::next_key(&self.previous_key)
 				.filter(|n| n.starts_with(&self.prefix));
@@ -410,6 +425,7 @@ impl<
 			prefix: prefix.clone(),
 			previous_key: prefix,
 			drain: false,
+			finished: false,
 			closure: |raw_key_without_prefix, mut raw_value| {
 				let mut key_material = G::Hasher2::reverse(raw_key_without_prefix);
 				Ok((K2::decode(&mut key_material)?, V::decode(&mut raw_value)?))
@@ -417,6 +433,17 @@ impl<
 		}
 	}
 
+	fn iter_prefix_from(
+		k1: impl EncodeLike<K1>,
+		starting_raw_key: Vec<u8>,
+	) -> Self::PrefixIterator {
+		let mut iterator = Self::iter_prefix(k1);
+		// A key outside of the prefix would resume at an arbitrary point.
+		iterator.finished = !starting_raw_key.starts_with(&iterator.prefix);
+		iterator.previous_key = starting_raw_key;
+		iterator
+	}
+
 	fn drain_prefix(k1: impl EncodeLike<K1>) -> Self::PrefixIterator {
 		let mut iterator = Self::iter_prefix(k1);
 		iterator.drain = true;
@@ -429,6 +456,7 @@ impl<
 			prefix: prefix.clone(),
 			previous_key: prefix,
 			drain: false,
+			finished: false,
 			closure: |raw_key_without_prefix, mut raw_value| {
 				let mut k1_k2_material = G::Hasher1::reverse(raw_key_without_prefix);
 				let k1 = K1::decode(&mut k1_k2_material)?;
@@ -439,12 +467,24 @@ impl<
 		}
 	}
 
+	fn iter_from(starting_raw_key: Vec<u8>) -> Self::Iterator {
+		let mut iterator = Self::iter();
+		// A key outside of the prefix would resume at an arbitrary point.
+		iterator.finished = !starting_raw_key.starts_with(&iterator.prefix);
+		iterator.previous_key = starting_raw_key;
+		iterator
+	}
+
 	fn drain() -> Self::Iterator {
 		let mut iterator = Self::iter();
 		iterator.drain = true;
 		iterator
 	}
 
+	fn drain_limited(limit: u32) -> Vec<(K1, K2, V)> {
+		Self::drain().take(limit as usize).collect()
+	}
+
 	fn translate<O: Decode, F: Fn(O) -> Option<V>>(f: F) {
 		let prefix = G::prefix_hash();
 		let mut previous_key = prefix.clone();
@@ -574,4 +614,47 @@ mod test_iterators {
 			assert_eq!(unhashed::get(&key_after_prefix(prefix.clone())), Some(1u64));
 		})
 	}
+
+	#[test]
+	fn double_map_iteration_resumes_from_cursor() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			for i in 0..4 {
+				DoubleMap::insert(i as u16, i as u32, i as u64);
+			}
+
+			let mut iterator = DoubleMap::iter();
+			assert_eq!(iterator.next(), Some((3, 3, 3)));
+			assert_eq!(iterator.next(), Some((0, 0, 0)));
+			assert_eq!(iterator.last_raw_key(), &DoubleMap::hashed_key_for(0u16, 0u32)[..]);
+			assert_eq!(
+				DoubleMap::iter_from(iterator.last_raw_key().to_vec()).collect::<Vec<_>>(),
+				vec![(2, 2, 2), (1, 1, 1)],
+			);
+
+			// Prefix iterator
+			let k1 = 3 << 8;
+			for i in 0..4 {
+				DoubleMap::insert(k1, i as u32, i as u64);
+			}
+
+			let mut iterator = DoubleMap::iter_prefix(k1);
+			assert_eq!(iterator.next(), Some((0, 0)));
+			assert_eq!(
+				DoubleMap::iter_prefix_from(k1, iterator.last_raw_key().to_vec()).collect::<Vec<_>>(),
+				vec![(2, 2), (1, 1), (3, 3)],
+			);
+
+			// Cursors outside of the map, or of the first key, resume to nothing.
+			let outside = key_before_prefix(DoubleMap::prefix_hash());
+			assert_eq!(DoubleMap::iter_from(outside.clone()).count(), 0);
+			assert_eq!(DoubleMap::iter_prefix_from(k1, outside).count(), 0);
+			let other_k1 = DoubleMap::hashed_key_for(0u16, 0u32);
+			assert_eq!(DoubleMap::iter_prefix_from(k1, other_k1).count(), 0);
+
+			// Limited drain
+			assert_eq!(DoubleMap::drain_limited(5).len(), 5);
+			assert_eq!(DoubleMap::drain_limited(5).len(), 3);
+			assert_eq!(DoubleMap::iter().count(), 0);
+		})
+	}
 }
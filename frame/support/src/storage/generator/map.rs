@@ -92,9 +92,20 @@ pub struct StorageMapIterator<K, V, Hasher> {
 	prefix: Vec<u8>,
 	previous_key: Vec<u8>,
 	drain: bool,
+	/// If true then no element is left to iterate over.
+	finished: bool,
 	_phantom: ::sp_std::marker::PhantomData<(K, V, Hasher)>,
 }
 
+impl<K, V, Hasher> StorageMapIterator<K, V, Hasher> {
+	/// The raw key of the last element iterated over, or the prefix of the map if there is none.
+	///
+	/// This is the cursor to give to `iter_from` to resume the iteration after this element.
+	pub fn last_raw_key(&self) -> &[u8] {
+		&self.previous_key
+	}
+}
+
 impl<
 	K: Decode + Sized,
 	V: Decode + Sized,
@@ -103,6 +114,9 @@ impl<
 	type Item = (K, V);
 
 	fn next(&mut self) -> Option<(K, V)> {
+		if self.finished {
+			return None
+		}
 		loop {
 			let maybe_next = sp_io::storage::next_key(&self.previous_key)
 				.filter(|n| n.starts_with(&self.prefix));
@@ -145,10 +159,20 @@ impl<
 			prefix: prefix.clone(),
 			previous_key: prefix,
 			drain: false,
+			finished: false,
 			_phantom: Default::default(),
 		}
 	}
 
+	/// Enumerate all elements in the map after the given raw key.
+	fn iter_from(starting_raw_key: Vec<u8>) -> Self::Iterator {
+		let mut iterator = Self::iter();
+		// A key of another storage item would resume at an arbitrary point.
+		iterator.finished = !starting_raw_key.starts_with(&iterator.prefix);
+		iterator.previous_key = starting_raw_key;
+		iterator
+	}
+
 	/// Enumerate all elements in the map.
 	fn drain() -> Self::Iterator {
 		let prefix = G::prefix_hash();
@@ -156,10 +180,15 @@ impl<
 			prefix: prefix.clone(),
 			previous_key: prefix,
 			drain: true,
+			finished: false,
 			_phantom: Default::default(),
 		}
 	}
 
+	fn drain_limited(limit: u32) -> Vec<(K, V)> {
+		Self::drain().take(limit as usize).collect()
+	}
+
 	fn translate<O: Decode, F: Fn(K, O) -> Option<V>>(f: F) {
 		let prefix = G::prefix_hash();
 		let mut previous_key = prefix.clone();
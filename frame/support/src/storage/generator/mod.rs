@@ -25,10 +25,12 @@
 
 mod map;
 mod double_map;
+mod nmap;
 mod value;
 
 pub use map::StorageMap;
-pub use double_map::StorageDoubleMap;
+pub use double_map::{StorageDoubleMap, MapIterator};
+pub use nmap::StorageNMap;
 pub use value::StorageValue;
 
 #[cfg(test)]
@@ -105,6 +107,56 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn map_iteration_resumes_from_cursor() {
+		let t = GenesisConfig::default().build_storage().unwrap();
+		TestExternalities::new(t).execute_with(|| {
+			for i in 0u32..10u32 {
+				NumberMap::insert(i, i as u64);
+			}
+
+			let mut iterator = NumberMap::iter();
+			let first = iterator.by_ref().take(4).collect::<Vec<_>>();
+			assert_eq!(first, (0..4).map(|x| (x as u32, x as u64)).collect::<Vec<_>>());
+			assert_eq!(iterator.last_raw_key(), &NumberMap::hashed_key_for(3u32)[..]);
+
+			let cursor = iterator.last_raw_key().to_vec();
+			assert_eq!(
+				NumberMap::iter_from(cursor).collect::<Vec<_>>(),
+				(4..10).map(|x| (x as u32, x as u64)).collect::<Vec<_>>(),
+			);
+
+			// A cursor of the last element resumes to nothing.
+			let mut iterator = NumberMap::iter();
+			assert_eq!(iterator.by_ref().count(), 10);
+			assert_eq!(NumberMap::iter_from(iterator.last_raw_key().to_vec()).count(), 0);
+
+			// Neither does a key of another storage item.
+			assert_eq!(NumberMap::iter_from(Value::storage_value_final_key().to_vec()).count(), 0);
+		})
+	}
+
+	#[test]
+	fn map_drain_limited_works() {
+		let t = GenesisConfig::default().build_storage().unwrap();
+		TestExternalities::new(t).execute_with(|| {
+			for i in 0u32..10u32 {
+				NumberMap::insert(i, i as u64);
+			}
+
+			assert_eq!(
+				NumberMap::drain_limited(4),
+				(0..4).map(|x| (x as u32, x as u64)).collect::<Vec<_>>(),
+			);
+			assert_eq!(NumberMap::iter().count(), 6);
+
+			assert_eq!(NumberMap::drain_limited(4).len(), 4);
+			assert_eq!(NumberMap::drain_limited(4).len(), 2);
+			assert_eq!(NumberMap::iter().count(), 0);
+			assert_eq!(NumberMap::drain_limited(4), vec![]);
+		})
+	}
+
 	#[test]
 	fn try_mutate_works() {
 		let t = GenesisConfig::default().build_storage().unwrap();
@@ -0,0 +1,459 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use sp_std::prelude::*;
+use codec::{FullCodec, Decode, Encode, EncodeLike, EncodeAppend};
+use crate::{storage::{self, unhashed}, traits::Len, Never};
+use crate::hash::{StorageHasher, Twox128};
+use crate::storage::key::{
+	EncodeLikeTuple, KeyGenerator, ReversibleKeyGenerator, HasKeyPrefix, HasReversibleKeyPrefix,
+};
+use super::MapIterator;
+
+/// Generator for `StorageNMap` used by `decl_storage`.
+///
+/// # Mapping of keys to a storage path
+///
+/// The storage key (i.e. the key under which the `Value` will be stored) is created from the
+/// hashes of each of the keys, each key being hashed with its own hasher.
+///
+/// Thus value for (key1, key2, ..., keyN) is stored at:
+/// ```nocompile
+/// Twox128(module_prefix) ++ Twox128(storage_prefix)
+///     ++ Hasher1(encode(key1)) ++ Hasher2(encode(key2)) ++ ... ++ HasherN(encode(keyN))
+/// ```
+///
+/// # Warning
+///
+/// If the keys are not trusted (e.g. can be set by a user), a cryptographic `hasher` such as
+/// `blake2_256` must be used for them. Otherwise, other values in storage with the same prefix
+/// can be compromised.
+pub trait StorageNMap<K: KeyGenerator, V: FullCodec> {
+	/// The type that get/take returns.
+	type Query;
+
+	/// Module prefix. Used for generating final key.
+	fn module_prefix() -> &'static [u8];
+
+	/// Storage prefix. Used for generating final key.
+	fn storage_prefix() -> &'static [u8];
+
+	/// The full prefix; just the hash of `module_prefix` concatenated to the hash of
+	/// `storage_prefix`.
+	fn prefix_hash() -> Vec<u8> {
+		let module_prefix_hashed = Twox128::hash(Self::module_prefix());
+		let storage_prefix_hashed = Twox128::hash(Self::storage_prefix());
+
+		let mut result = Vec::with_capacity(
+			module_prefix_hashed.len() + storage_prefix_hashed.len()
+		);
+
+		result.extend_from_slice(&module_prefix_hashed[..]);
+		result.extend_from_slice(&storage_prefix_hashed[..]);
+
+		result
+	}
+
+	/// Convert an optional value retrieved from storage to the type queried.
+	fn from_optional_value_to_query(v: Option<V>) -> Self::Query;
+
+	/// Convert a query to an optional value into storage.
+	fn from_query_to_optional_value(v: Self::Query) -> Option<V>;
+
+	/// Generate the key used in top storage for the values whose leading keys are `partial_key`.
+	fn storage_n_map_partial_key<KP>(partial_key: KP) -> Vec<u8> where
+		K: HasKeyPrefix<KP>,
+	{
+		let mut final_key = Self::prefix_hash();
+		final_key.extend_from_slice(&K::partial_key(partial_key));
+		final_key
+	}
+
+	/// Generate the full key used in top storage.
+	fn storage_n_map_final_key<KArg>(key: KArg) -> Vec<u8> where
+		KArg: EncodeLikeTuple<K::Key>,
+	{
+		let mut final_key = Self::prefix_hash();
+		final_key.extend_from_slice(&K::final_key(key));
+		final_key
+	}
+}
+
+impl<K, V, G> storage::StorageNMap<K, V> for G where
+	K: KeyGenerator,
+	V: FullCodec,
+	G: StorageNMap<K, V>,
+{
+	type Query = G::Query;
+
+	fn hashed_key_for<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> Vec<u8> {
+		Self::storage_n_map_final_key(key)
+	}
+
+	fn contains_key<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> bool {
+		unhashed::exists(&Self::storage_n_map_final_key(key))
+	}
+
+	fn get<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> Self::Query {
+		G::from_optional_value_to_query(unhashed::get(&Self::storage_n_map_final_key(key)))
+	}
+
+	fn take<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> Self::Query {
+		let final_key = Self::storage_n_map_final_key(key);
+
+		let value = unhashed::take(&final_key);
+		G::from_optional_value_to_query(value)
+	}
+
+	fn swap<XKArg, YKArg>(x_key: XKArg, y_key: YKArg) where
+		XKArg: EncodeLikeTuple<K::Key>,
+		YKArg: EncodeLikeTuple<K::Key>,
+	{
+		let final_x_key = Self::storage_n_map_final_key(x_key);
+		let final_y_key = Self::storage_n_map_final_key(y_key);
+
+		let v1 = unhashed::get_raw(&final_x_key);
+		if let Some(val) = unhashed::get_raw(&final_y_key) {
+			unhashed::put_raw(&final_x_key, &val);
+		} else {
+			unhashed::kill(&final_x_key)
+		}
+		if let Some(val) = v1 {
+			unhashed::put_raw(&final_y_key, &val);
+		} else {
+			unhashed::kill(&final_y_key)
+		}
+	}
+
+	fn insert<KArg, VArg>(key: KArg, val: VArg) where
+		KArg: EncodeLikeTuple<K::Key>,
+		VArg: EncodeLike<V>,
+	{
+		unhashed::put(&Self::storage_n_map_final_key(key), &val)
+	}
+
+	fn remove<KArg: EncodeLikeTuple<K::Key>>(key: KArg) {
+		unhashed::kill(&Self::storage_n_map_final_key(key))
+	}
+
+	fn remove_prefix<KP>(partial_key: KP) where K: HasKeyPrefix<KP> {
+		unhashed::kill_prefix(&Self::storage_n_map_partial_key(partial_key))
+	}
+
+	fn iter_prefix_values<KP>(partial_key: KP) -> storage::PrefixIterator<V> where
+		K: HasKeyPrefix<KP>,
+	{
+		let prefix = Self::storage_n_map_partial_key(partial_key);
+		storage::PrefixIterator::<V> {
+			prefix: prefix.clone(),
+			previous_key: prefix,
+			phantom_data: Default::default(),
+		}
+	}
+
+	fn mutate<KArg, R, F>(key: KArg, f: F) -> R where
+		KArg: EncodeLikeTuple<K::Key>,
+		F: FnOnce(&mut Self::Query) -> R,
+	{
+		Self::try_mutate(key, |v| Ok::<R, Never>(f(v))).expect("`Never` can not be constructed; qed")
+	}
+
+	fn try_mutate<KArg, R, E, F>(key: KArg, f: F) -> Result<R, E> where
+		KArg: EncodeLikeTuple<K::Key>,
+		F: FnOnce(&mut Self::Query) -> Result<R, E>,
+	{
+		let final_key = Self::storage_n_map_final_key(key);
+		let mut val = G::from_optional_value_to_query(unhashed::get(final_key.as_ref()));
+
+		let ret = f(&mut val);
+		if ret.is_ok() {
+			match G::from_query_to_optional_value(val) {
+				Some(ref val) => unhashed::put(final_key.as_ref(), val),
+				None => unhashed::kill(final_key.as_ref()),
+			}
+		}
+		ret
+	}
+
+	fn append<Items, Item, EncodeLikeItem, KArg>(
+		key: KArg,
+		items: Items,
+	) -> Result<(), &'static str> where
+		KArg: EncodeLikeTuple<K::Key>,
+		Item: Encode,
+		EncodeLikeItem: EncodeLike<Item>,
+		V: EncodeAppend<Item=Item>,
+		Items: IntoIterator<Item=EncodeLikeItem>,
+		Items::IntoIter: ExactSizeIterator
+	{
+		let final_key = Self::storage_n_map_final_key(key);
+
+		let encoded_value = unhashed::get_raw(&final_key)
+			.unwrap_or_else(|| {
+				match G::from_query_to_optional_value(G::from_optional_value_to_query(None)) {
+					Some(value) => value.encode(),
+					None => Vec::new(),
+				}
+			});
+
+		let new_val = V::append_or_new(
+			encoded_value,
+			items,
+		).map_err(|_| "Could not append given item")?;
+		unhashed::put_raw(&final_key, &new_val);
+
+		Ok(())
+	}
+
+	fn decode_len<KArg>(key: KArg) -> Result<usize, &'static str> where
+		KArg: EncodeLikeTuple<K::Key>,
+		V: codec::DecodeLength + Len,
+	{
+		let final_key = Self::storage_n_map_final_key(key);
+		if let Some(v) = unhashed::get_raw(&final_key) {
+			<V as codec::DecodeLength>::len(&v).map_err(|e| e.what())
+		} else {
+			let len = G::from_query_to_optional_value(G::from_optional_value_to_query(None))
+				.map(|v| v.len())
+				.unwrap_or(0);
+
+			Ok(len)
+		}
+	}
+}
+
+impl<
+	K: ReversibleKeyGenerator,
+	V: FullCodec,
+	G: StorageNMap<K, V>,
+> storage::IterableStorageNMap<K, V> for G {
+	type Iterator = MapIterator<(K::Key, V)>;
+
+	fn iter_prefix<KP>(
+		partial_key: KP,
+	) -> MapIterator<(<K as HasKeyPrefix<KP>>::Suffix, V)> where
+		K: HasReversibleKeyPrefix<KP>,
+	{
+		let prefix = G::storage_n_map_partial_key(partial_key);
+		MapIterator {
+			prefix: prefix.clone(),
+			previous_key: prefix,
+			drain: false,
+			finished: false,
+			closure: |raw_key_without_prefix, mut raw_value| {
+				let suffix = K::decode_partial_key(raw_key_without_prefix)?;
+				Ok((suffix, V::decode(&mut raw_value)?))
+			},
+		}
+	}
+
+	fn drain_prefix<KP>(
+		partial_key: KP,
+	) -> MapIterator<(<K as HasKeyPrefix<KP>>::Suffix, V)> where
+		K: HasReversibleKeyPrefix<KP>,
+	{
+		let mut iterator = Self::iter_prefix(partial_key);
+		iterator.drain = true;
+		iterator
+	}
+
+	fn iter() -> Self::Iterator {
+		let prefix = G::prefix_hash();
+		Self::Iterator {
+			prefix: prefix.clone(),
+			previous_key: prefix,
+			drain: false,
+			finished: false,
+			closure: |raw_key_without_prefix, mut raw_value| {
+				let key = K::decode_final_key(raw_key_without_prefix)?;
+				Ok((key, V::decode(&mut raw_value)?))
+			},
+		}
+	}
+
+	fn iter_from(starting_raw_key: Vec<u8>) -> Self::Iterator {
+		let mut iterator = Self::iter();
+		// A key of another storage item would resume at an arbitrary point.
+		iterator.finished = !starting_raw_key.starts_with(&iterator.prefix);
+		iterator.previous_key = starting_raw_key;
+		iterator
+	}
+
+	fn drain() -> Self::Iterator {
+		let mut iterator = Self::iter();
+		iterator.drain = true;
+		iterator
+	}
+
+	fn drain_limited(limit: u32) -> Vec<(K::Key, V)> {
+		Self::drain().take(limit as usize).collect()
+	}
+
+	fn translate<O: Decode, F: Fn(K::Key, O) -> Option<V>>(f: F) {
+		let prefix = G::prefix_hash();
+		let mut previous_key = prefix.clone();
+		loop {
+			match sp_io::storage::next_key(&previous_key).filter(|n| n.starts_with(&prefix)) {
+				Some(next) => {
+					previous_key = next;
+					let maybe_value = unhashed::get::<O>(&previous_key);
+					match maybe_value {
+						Some(value) => match K::decode_final_key(&previous_key[prefix.len()..]) {
+							Ok(key) => match f(key, value) {
+								Some(new) => unhashed::put::<V>(&previous_key, &new),
+								None => unhashed::kill(&previous_key),
+							},
+							Err(_) => continue,
+						},
+						None => continue,
+					}
+				}
+				None => return,
+			}
+		}
+	}
+}
+
+/// Test iterators for StorageNMap
+#[cfg(test)]
+#[allow(dead_code)]
+mod test_iterators {
+	use crate::storage::{generator::StorageNMap, IterableStorageNMap, StorageNMap as _, unhashed};
+
+	pub trait Trait {
+		type Origin;
+		type BlockNumber;
+	}
+
+	crate::decl_module! {
+		pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+	}
+
+	crate::decl_storage! {
+		trait Store for Module<T: Trait> as Test {
+			NMap: nmap hasher(blake2_128_concat) u16, hasher(twox_64_concat) u32,
+				hasher(identity) u8 => u64;
+		}
+	}
+
+	fn key_before_prefix(mut prefix: Vec<u8>) -> Vec<u8> {
+		let last = prefix.iter_mut().last().unwrap();
+		assert!(*last != 0, "mock function not implemented for this prefix");
+		*last -= 1;
+		prefix
+	}
+
+	fn key_after_prefix(mut prefix: Vec<u8>) -> Vec<u8> {
+		let last = prefix.iter_mut().last().unwrap();
+		assert!(*last != 255, "mock function not implemented for this prefix");
+		*last += 1;
+		prefix
+	}
+
+	#[test]
+	fn n_map_reversible_iteration() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			// All map iterator
+			let prefix = NMap::prefix_hash();
+
+			unhashed::put(&key_before_prefix(prefix.clone()), &1u64);
+			unhashed::put(&key_after_prefix(prefix.clone()), &1u64);
+
+			for i in 0..4 {
+				NMap::insert((i as u16, i as u32, i as u8), i as u64);
+			}
+
+			let mut all = NMap::iter().collect::<Vec<_>>();
+			all.sort();
+			assert_eq!(all, vec![((0, 0, 0), 0), ((1, 1, 1), 1), ((2, 2, 2), 2), ((3, 3, 3), 3)]);
+
+			assert_eq!(NMap::drain().count(), 4);
+			assert_eq!(NMap::iter().collect::<Vec<_>>(), vec![]);
+			assert_eq!(unhashed::get(&key_before_prefix(prefix.clone())), Some(1u64));
+			assert_eq!(unhashed::get(&key_after_prefix(prefix.clone())), Some(1u64));
+
+			// Prefix iterators on one and two keys
+			let k1 = 3u16 << 8;
+			let prefix = NMap::storage_n_map_partial_key((k1,));
+
+			unhashed::put(&key_before_prefix(prefix.clone()), &1u64);
+			unhashed::put(&key_after_prefix(prefix.clone()), &1u64);
+
+			for i in 0..4 {
+				NMap::insert((k1, i as u32 % 2, i as u8), i as u64);
+			}
+
+			let mut entries = NMap::iter_prefix((k1,)).collect::<Vec<_>>();
+			entries.sort();
+			assert_eq!(entries, vec![((0, 0), 0), ((0, 2), 2), ((1, 1), 1), ((1, 3), 3)]);
+
+			let mut entries = NMap::iter_prefix((k1, 1u32)).collect::<Vec<_>>();
+			entries.sort();
+			assert_eq!(entries, vec![((1,), 1), ((3,), 3)]);
+
+			let mut values = NMap::iter_prefix_values((k1, 0u32)).collect::<Vec<_>>();
+			values.sort();
+			assert_eq!(values, vec![0, 2]);
+
+			assert_eq!(NMap::drain_prefix((k1, 0u32)).count(), 2);
+			assert_eq!(NMap::iter_prefix((k1,)).count(), 2);
+
+			NMap::remove_prefix((k1,));
+			assert_eq!(NMap::iter_prefix((k1,)).collect::<Vec<_>>(), vec![]);
+			assert_eq!(unhashed::get(&key_before_prefix(prefix.clone())), Some(1u64));
+			assert_eq!(unhashed::get(&key_after_prefix(prefix.clone())), Some(1u64));
+		})
+	}
+
+	#[test]
+	fn n_map_iteration_resumes_from_cursor() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			for i in 0..4 {
+				NMap::insert((i as u16, i as u32, i as u8), i as u64);
+			}
+
+			let mut iterator = NMap::iter();
+			let first = iterator.next().unwrap();
+			let mut rest = NMap::iter_from(iterator.last_raw_key().to_vec()).collect::<Vec<_>>();
+			rest.push(first);
+			rest.sort();
+			assert_eq!(rest, vec![((0, 0, 0), 0), ((1, 1, 1), 1), ((2, 2, 2), 2), ((3, 3, 3), 3)]);
+
+			// A cursor outside of the map resumes to nothing.
+			let outside = key_before_prefix(NMap::prefix_hash());
+			assert_eq!(NMap::iter_from(outside).count(), 0);
+
+			assert_eq!(NMap::drain_limited(3).len(), 3);
+			assert_eq!(NMap::drain_limited(3).len(), 1);
+			assert_eq!(NMap::iter().count(), 0);
+		})
+	}
+
+	#[test]
+	fn n_map_translate() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			for i in 0..4 {
+				NMap::insert((i as u16, i as u32, i as u8), i as u64);
+			}
+
+			NMap::translate(|(k1, _, _), value: u64| if k1 % 2 == 0 { Some(value * 10) } else { None });
+
+			let mut all = NMap::iter().collect::<Vec<_>>();
+			all.sort();
+			assert_eq!(all, vec![((0, 0, 0), 0), ((2, 2, 2), 20)]);
+		})
+	}
+}
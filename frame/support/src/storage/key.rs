@@ -0,0 +1,262 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keys of a storage n-map.
+//!
+//! The keys of an n-map are described by a tuple of [`Key`]s, each giving the hasher and the type
+//! of one key, e.g. `(Key<Blake2_128Concat, AccountId>, Key<Twox64Concat, u64>)`. Such a tuple
+//! implements [`KeyGenerator`], and [`HasKeyPrefix`] for each tuple of its leading key types.
+
+use sp_std::{prelude::*, marker::PhantomData};
+use codec::{FullCodec, Encode, EncodeLike, Decode};
+use crate::hash::{StorageHasher, ReversibleStorageHasher};
+
+/// A key of a storage n-map: the hasher of its encoding and its type.
+pub struct Key<Hasher, KeyType>(PhantomData<(Hasher, KeyType)>);
+
+impl<H: StorageHasher, K> Key<H, K> {
+	/// Append the hash of `encoded_key` to `final_key`.
+	fn hash_into(encoded_key: &[u8], final_key: &mut Vec<u8>) {
+		final_key.extend_from_slice(H::hash(encoded_key).as_ref());
+	}
+}
+
+impl<H: ReversibleStorageHasher, K: Decode> Key<H, K> {
+	/// Decode the key from the start of `key_material`, advancing it past the key.
+	fn decode_from(key_material: &mut &[u8]) -> Result<K, codec::Error> {
+		let mut remaining = H::reverse(key_material);
+		let key = K::decode(&mut remaining)?;
+		*key_material = remaining;
+		Ok(key)
+	}
+}
+
+/// A tuple of values each encoding like the element of `T` at the same position.
+///
+/// Arguments of n-map functions are given as such tuples, e.g. `(&account, 3)` for a key of type
+/// `(AccountId, u64)`.
+pub trait EncodeLikeTuple<T> {
+	/// The encoding of each element of the tuple, in order.
+	fn encode_elements(&self) -> Vec<Vec<u8>>;
+}
+
+/// The keys of a storage n-map.
+pub trait KeyGenerator {
+	/// The full key: the tuple of the types of the keys.
+	type Key: FullCodec;
+
+	/// The number of keys.
+	const LEN: usize;
+
+	/// Concatenate the hashes of the keys of `key`.
+	fn final_key<KArg: EncodeLikeTuple<Self::Key>>(key: KArg) -> Vec<u8>;
+}
+
+/// The keys of a storage n-map whose hashers are all reversible.
+pub trait ReversibleKeyGenerator: KeyGenerator {
+	/// Decode the full key from the concatenation of the hashes of its keys.
+	fn decode_final_key(key_material: &[u8]) -> Result<Self::Key, codec::Error>;
+}
+
+/// The keys of a storage n-map starting with the keys of types `P`, a tuple.
+pub trait HasKeyPrefix<P>: KeyGenerator {
+	/// The tuple of the types of the keys following the prefix.
+	type Suffix: FullCodec;
+
+	/// Concatenate the hashes of the keys of `prefix`.
+	fn partial_key(prefix: P) -> Vec<u8>;
+}
+
+/// The keys of a storage n-map starting with the keys of types `P`, whose following keys have
+/// reversible hashers.
+pub trait HasReversibleKeyPrefix<P>: HasKeyPrefix<P> {
+	/// Decode the keys following the prefix from the concatenation of their hashes.
+	fn decode_partial_key(key_material: &[u8]) -> Result<Self::Suffix, codec::Error>;
+}
+
+macro_rules! impl_encode_like_tuples {
+	() => {};
+	( ($K:ident, $A:ident) $( ($RK:ident, $RA:ident) )* ) => {
+		impl<$K: Encode, $A: EncodeLike<$K>, $( $RK: Encode, $RA: EncodeLike<$RK>, )*>
+			EncodeLikeTuple<($K, $( $RK, )*)> for ($A, $( $RA, )*)
+		{
+			#[allow(non_snake_case)]
+			fn encode_elements(&self) -> Vec<Vec<u8>> {
+				let ($A, $( $RA, )*) = self;
+				let mut encoded = Vec::new();
+				encoded.push($A.encode());
+				$( encoded.push($RA.encode()); )*
+				encoded
+			}
+		}
+
+		impl_encode_like_tuples!( $( ($RK, $RA) )* );
+	};
+}
+
+macro_rules! impl_key_prefixes {
+	( [ $( ($PH:ident, $PK:ident) )+ ] [] ) => {};
+	(
+		[ $( ($PH:ident, $PK:ident) )+ ]
+		[ ($SH:ident, $SK:ident) $( ($RH:ident, $RK:ident) )* ]
+	) => {
+		impl<$( $PH, $PK, )+ $SH, $SK, $( $RH, $RK, )*> HasKeyPrefix<($( $PK, )+)>
+			for ($( Key<$PH, $PK>, )+ Key<$SH, $SK>, $( Key<$RH, $RK>, )*)
+		where
+			$( $PH: StorageHasher, $PK: FullCodec, )+
+			$SH: StorageHasher, $SK: FullCodec,
+			$( $RH: StorageHasher, $RK: FullCodec, )*
+		{
+			type Suffix = ($SK, $( $RK, )*);
+
+			fn partial_key(prefix: ($( $PK, )+)) -> Vec<u8> {
+				let encoded = EncodeLikeTuple::<($( $PK, )+)>::encode_elements(&prefix);
+				let mut elements = encoded.iter();
+				let mut partial_key = Vec::new();
+				$(
+					Key::<$PH, $PK>::hash_into(
+						elements.next().expect("one element per key of the prefix; qed"),
+						&mut partial_key,
+					);
+				)+
+				partial_key
+			}
+		}
+
+		impl<$( $PH, $PK, )+ $SH, $SK, $( $RH, $RK, )*> HasReversibleKeyPrefix<($( $PK, )+)>
+			for ($( Key<$PH, $PK>, )+ Key<$SH, $SK>, $( Key<$RH, $RK>, )*)
+		where
+			$( $PH: StorageHasher, $PK: FullCodec, )+
+			$SH: ReversibleStorageHasher, $SK: FullCodec,
+			$( $RH: ReversibleStorageHasher, $RK: FullCodec, )*
+		{
+			fn decode_partial_key(mut key_material: &[u8]) -> Result<Self::Suffix, codec::Error> {
+				Ok((
+					Key::<$SH, $SK>::decode_from(&mut key_material)?,
+					$( Key::<$RH, $RK>::decode_from(&mut key_material)?, )*
+				))
+			}
+		}
+
+		impl_key_prefixes!(
+			[ $( ($PH, $PK) )+ ($SH, $SK) ]
+			[ $( ($RH, $RK) )* ]
+		);
+	};
+}
+
+macro_rules! impl_key_generators {
+	(@one $K:ident) => { 1 };
+	() => {};
+	( ($H:ident, $K:ident) $( ($RH:ident, $RK:ident) )* ) => {
+		impl<$H: StorageHasher, $K: FullCodec, $( $RH: StorageHasher, $RK: FullCodec, )*>
+			KeyGenerator for (Key<$H, $K>, $( Key<$RH, $RK>, )*)
+		{
+			type Key = ($K, $( $RK, )*);
+
+			const LEN: usize = 1 $( + impl_key_generators!(@one $RK) )*;
+
+			fn final_key<KArg: EncodeLikeTuple<Self::Key>>(key: KArg) -> Vec<u8> {
+				let encoded = key.encode_elements();
+				let mut elements = encoded.iter();
+				let mut final_key = Vec::new();
+				Key::<$H, $K>::hash_into(
+					elements.next().expect("one element per key; qed"),
+					&mut final_key,
+				);
+				$(
+					Key::<$RH, $RK>::hash_into(
+						elements.next().expect("one element per key; qed"),
+						&mut final_key,
+					);
+				)*
+				final_key
+			}
+		}
+
+		impl<
+			$H: ReversibleStorageHasher,
+			$K: FullCodec,
+			$( $RH: ReversibleStorageHasher, $RK: FullCodec, )*
+		> ReversibleKeyGenerator for (Key<$H, $K>, $( Key<$RH, $RK>, )*) {
+			fn decode_final_key(mut key_material: &[u8]) -> Result<Self::Key, codec::Error> {
+				Ok((
+					Key::<$H, $K>::decode_from(&mut key_material)?,
+					$( Key::<$RH, $RK>::decode_from(&mut key_material)?, )*
+				))
+			}
+		}
+
+		impl_key_prefixes!( [ ($H, $K) ] [ $( ($RH, $RK) )* ] );
+
+		impl_key_generators!( $( ($RH, $RK) )* );
+	};
+}
+
+impl_encode_like_tuples!(
+	(K1, A1) (K2, A2) (K3, A3) (K4, A4) (K5, A5) (K6, A6)
+	(K7, A7) (K8, A8) (K9, A9) (K10, A10) (K11, A11) (K12, A12)
+);
+
+impl_key_generators!(
+	(H1, K1) (H2, K2) (H3, K3) (H4, K4) (H5, K5) (H6, K6)
+	(H7, K7) (H8, K8) (H9, K9) (H10, K10) (H11, K11) (H12, K12)
+);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::hash::{Blake2_128Concat, Identity, Twox64Concat};
+
+	type Keys = (Key<Blake2_128Concat, u16>, Key<Twox64Concat, u32>, Key<Identity, u8>);
+
+	#[test]
+	fn final_key_concatenates_hashes_of_keys() {
+		let mut expected = Blake2_128Concat::hash(&1u16.encode());
+		expected.extend(Twox64Concat::hash(&2u32.encode()));
+		expected.extend(Identity::hash(&3u8.encode()));
+
+		assert_eq!(Keys::final_key((1u16, 2u32, 3u8)), expected);
+		assert_eq!(Keys::final_key((&1u16, &2u32, &3u8)), expected);
+		assert_eq!(<Keys as KeyGenerator>::LEN, 3);
+	}
+
+	#[test]
+	fn partial_keys_are_prefixes_of_final_key() {
+		let final_key = Keys::final_key((1u16, 2u32, 3u8));
+
+		let prefix = <Keys as HasKeyPrefix<(u16,)>>::partial_key((1,));
+		assert!(final_key.starts_with(&prefix));
+		assert_eq!(
+			<Keys as HasReversibleKeyPrefix<(u16,)>>::decode_partial_key(&final_key[prefix.len()..]).ok(),
+			Some((2, 3)),
+		);
+
+		let prefix = <Keys as HasKeyPrefix<(u16, u32)>>::partial_key((1, 2));
+		assert!(final_key.starts_with(&prefix));
+		assert_eq!(
+			<Keys as HasReversibleKeyPrefix<(u16, u32)>>::decode_partial_key(&final_key[prefix.len()..]).ok(),
+			Some((3,)),
+		);
+	}
+
+	#[test]
+	fn final_key_decodes() {
+		let final_key = Keys::final_key((1u16, 2u32, 3u8));
+		assert_eq!(Keys::decode_final_key(&final_key).ok(), Some((1, 2, 3)));
+		assert!(Keys::decode_final_key(&final_key[..final_key.len() - 1]).is_err());
+	}
+}
@@ -19,6 +19,7 @@
 use sp_std::{prelude::*, marker::PhantomData};
 use codec::{FullCodec, FullEncode, Encode, EncodeAppend, EncodeLike, Decode};
 use crate::{traits::Len, hash::{Twox128, StorageHasher}};
+use key::{EncodeLikeTuple, KeyGenerator, ReversibleKeyGenerator, HasKeyPrefix, HasReversibleKeyPrefix};
 
 pub mod unhashed;
 pub mod hashed;
@@ -26,6 +27,46 @@ pub mod child;
 #[doc(hidden)]
 pub mod generator;
 pub mod migration;
+pub mod bounded_vec;
+pub mod bounded_btree_map;
+pub mod key;
+
+pub use bounded_vec::BoundedVec;
+pub use bounded_btree_map::BoundedBTreeMap;
+
+/// Describes whether a storage transaction should be committed or rolled back.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransactionOutcome<R> {
+	/// Keep the changes made in the transaction.
+	Commit(R),
+	/// Discard the changes made in the transaction.
+	Rollback(R),
+}
+
+/// Execute the supplied function in a new storage transaction.
+///
+/// All changes to storage performed by the supplied function are discarded if it returns
+/// `TransactionOutcome::Rollback`, and kept if it returns `TransactionOutcome::Commit`.
+///
+/// Transactions can be nested to any depth. Changes kept by an inner transaction are still
+/// discarded if an outer transaction is rolled back.
+pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+	use sp_io::storage::{commit_transaction, rollback_transaction, start_transaction};
+	use TransactionOutcome::*;
+
+	start_transaction();
+
+	match f() {
+		Commit(res) => {
+			commit_transaction();
+			res
+		},
+		Rollback(res) => {
+			rollback_transaction();
+			res
+		},
+	}
+}
 
 /// A trait for working with macro-generated storage values under the substrate storage API.
 ///
@@ -228,10 +269,23 @@ pub trait IterableStorageMap<K: FullEncode, V: FullCodec>: StorageMap<K, V> {
 	/// this, you'll get undefined results.
 	fn iter() -> Self::Iterator;
 
+	/// Enumerate all elements in the map after the element stored at `starting_raw_key`, in the
+	/// same order as `iter`.
+	///
+	/// `starting_raw_key` is a cursor returned by `last_raw_key` on an iterator of this map, which
+	/// allows to resume an iteration in a later block. Nothing is iterated over if it is not a key
+	/// of this map.
+	fn iter_from(starting_raw_key: Vec<u8>) -> Self::Iterator;
+
 	/// Remove all elements from the map and iterate through them in no particular order. If you
 	/// add elements to the map while doing this, you'll get undefined results.
 	fn drain() -> Self::Iterator;
 
+	/// Remove at most `limit` elements from the map and return them, in the same order as `drain`.
+	///
+	/// The map is empty when fewer than `limit` elements are returned.
+	fn drain_limited(limit: u32) -> Vec<(K, V)>;
+
 	/// Translate the values of all elements by a function `f`, in the map in no particular order.
 	/// By returning `None` from `f` for an element, you'll remove it from the map.
 	fn translate<O: Decode, F: Fn(K, O) -> Option<V>>(f: F);
@@ -254,6 +308,14 @@ pub trait IterableStorageDoubleMap<
 	/// results.
 	fn iter_prefix(k1: impl EncodeLike<K1>) -> Self::PrefixIterator;
 
+	/// Enumerate all elements in the map with first key `k1` after the element stored at
+	/// `starting_raw_key`, in the same order as `iter_prefix`.
+	///
+	/// `starting_raw_key` is a cursor returned by `last_raw_key` on an iterator of this map over
+	/// the same first key, which allows to resume an iteration in a later block. Nothing is
+	/// iterated over if it is not a key of this map with first key `k1`.
+	fn iter_prefix_from(k1: impl EncodeLike<K1>, starting_raw_key: Vec<u8>) -> Self::PrefixIterator;
+
 	/// Remove all elements from the map with first key `k1` and iterate through them in no
 	/// particular order. If you add elements with first key `k1` to the map while doing this,
 	/// you'll get undefined results.
@@ -263,10 +325,23 @@ pub trait IterableStorageDoubleMap<
 	/// the map while doing this, you'll get undefined results.
 	fn iter() -> Self::Iterator;
 
+	/// Enumerate all elements in the map after the element stored at `starting_raw_key`, in the
+	/// same order as `iter`.
+	///
+	/// `starting_raw_key` is a cursor returned by `last_raw_key` on an iterator of this map, which
+	/// allows to resume an iteration in a later block. Nothing is iterated over if it is not a key
+	/// of this map.
+	fn iter_from(starting_raw_key: Vec<u8>) -> Self::Iterator;
+
 	/// Remove all elements from the map and iterate through them in no particular order. If you
 	/// add elements to the map while doing this, you'll get undefined results.
 	fn drain() -> Self::Iterator;
 
+	/// Remove at most `limit` elements from the map and return them, in the same order as `drain`.
+	///
+	/// The map is empty when fewer than `limit` elements are returned.
+	fn drain_limited(limit: u32) -> Vec<(K1, K2, V)>;
+
 	/// Translate the values of all elements by a function `f`, in the map in no particular order.
 	/// By returning `None` from `f` for an element, you'll remove it from the map.
 	fn translate<O: Decode, F: Fn(O) -> Option<V>>(f: F);
@@ -409,6 +484,135 @@ pub trait StorageDoubleMap<K1: FullEncode, K2: FullEncode, V: FullCodec> {
 	>(key1: KeyArg1, key2: KeyArg2) -> Option<V>;
 }
 
+/// An implementation of a map with an arbitrary number of keys.
+///
+/// The keys `K` are a tuple of [`key::Key`]s, and functions of the map take the keys of an entry
+/// as a tuple, e.g. `(&account, index)`. Values can be iterated over and removed by any leading
+/// subset of the keys.
+///
+/// Details on implementation can be found at
+/// [`generator::StorageNMap`]
+pub trait StorageNMap<K: KeyGenerator, V: FullCodec> {
+	/// The type that get/take returns.
+	type Query;
+
+	/// Get the storage key used to fetch a value corresponding to a specific key.
+	fn hashed_key_for<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> Vec<u8>;
+
+	/// Does the value (explicitly) exist in storage?
+	fn contains_key<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> bool;
+
+	/// Load the value associated with the given key from the map.
+	fn get<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> Self::Query;
+
+	/// Take a value from storage, removing it afterwards.
+	fn take<KArg: EncodeLikeTuple<K::Key>>(key: KArg) -> Self::Query;
+
+	/// Swap the values of two keys.
+	fn swap<XKArg, YKArg>(x_key: XKArg, y_key: YKArg)
+	where
+		XKArg: EncodeLikeTuple<K::Key>,
+		YKArg: EncodeLikeTuple<K::Key>;
+
+	/// Store a value to be associated with the given key from the map.
+	fn insert<KArg, VArg>(key: KArg, val: VArg)
+	where
+		KArg: EncodeLikeTuple<K::Key>,
+		VArg: EncodeLike<V>;
+
+	/// Remove the value under the given key.
+	fn remove<KArg: EncodeLikeTuple<K::Key>>(key: KArg);
+
+	/// Remove all values whose leading keys are `partial_key`.
+	fn remove_prefix<KP>(partial_key: KP) where K: HasKeyPrefix<KP>;
+
+	/// Iterate over values whose leading keys are `partial_key`.
+	fn iter_prefix_values<KP>(partial_key: KP) -> PrefixIterator<V> where K: HasKeyPrefix<KP>;
+
+	/// Mutate the value under the given key.
+	fn mutate<KArg, R, F>(key: KArg, f: F) -> R
+	where
+		KArg: EncodeLikeTuple<K::Key>,
+		F: FnOnce(&mut Self::Query) -> R;
+
+	/// Mutate the value under the given key when the closure returns `Ok`.
+	fn try_mutate<KArg, R, E, F>(key: KArg, f: F) -> Result<R, E>
+	where
+		KArg: EncodeLikeTuple<K::Key>,
+		F: FnOnce(&mut Self::Query) -> Result<R, E>;
+
+	/// Append the given item to the value in the storage.
+	///
+	/// `V` is required to implement `codec::EncodeAppend`.
+	fn append<Items, Item, EncodeLikeItem, KArg>(key: KArg, items: Items) -> Result<(), &'static str>
+	where
+		KArg: EncodeLikeTuple<K::Key>,
+		Item: Encode,
+		EncodeLikeItem: EncodeLike<Item>,
+		V: EncodeAppend<Item=Item>,
+		Items: IntoIterator<Item=EncodeLikeItem>,
+		Items::IntoIter: ExactSizeIterator;
+
+	/// Read the length of the value in a fast way, without decoding the entire value.
+	///
+	/// `V` is required to implement `Codec::DecodeLength`.
+	///
+	/// Note that `0` is returned as the default value if no encoded value exists at the given key.
+	/// Therefore, this function cannot be used as a sign of _existence_. use the `::contains_key()`
+	/// function for this purpose.
+	fn decode_len<KArg>(key: KArg) -> Result<usize, &'static str>
+		where
+			KArg: EncodeLikeTuple<K::Key>,
+			V: codec::DecodeLength + Len;
+}
+
+/// A strongly-typed n-map in storage whose keys and values can be iterated over.
+pub trait IterableStorageNMap<K: ReversibleKeyGenerator, V: FullCodec>: StorageNMap<K, V> {
+	/// The type that iterates over all `(key, value)`.
+	type Iterator: Iterator<Item = (K::Key, V)>;
+
+	/// Enumerate all elements in the map whose leading keys are `partial_key`, with the remaining
+	/// keys, in no particular order. If you add or remove values with these leading keys to the
+	/// map while doing this, you'll get undefined results.
+	fn iter_prefix<KP>(
+		partial_key: KP,
+	) -> generator::MapIterator<(<K as HasKeyPrefix<KP>>::Suffix, V)>
+		where K: HasReversibleKeyPrefix<KP>;
+
+	/// Remove all elements from the map whose leading keys are `partial_key` and iterate through
+	/// them in no particular order. If you add elements with these leading keys to the map while
+	/// doing this, you'll get undefined results.
+	fn drain_prefix<KP>(
+		partial_key: KP,
+	) -> generator::MapIterator<(<K as HasKeyPrefix<KP>>::Suffix, V)>
+		where K: HasReversibleKeyPrefix<KP>;
+
+	/// Enumerate all elements in the map in no particular order. If you add or remove values to
+	/// the map while doing this, you'll get undefined results.
+	fn iter() -> Self::Iterator;
+
+	/// Enumerate all elements in the map after the element stored at `starting_raw_key`, in the
+	/// same order as `iter`.
+	///
+	/// `starting_raw_key` is a cursor returned by `last_raw_key` on an iterator of this map, which
+	/// allows to resume an iteration in a later block. Nothing is iterated over if it is not a key
+	/// of this map.
+	fn iter_from(starting_raw_key: Vec<u8>) -> Self::Iterator;
+
+	/// Remove all elements from the map and iterate through them in no particular order. If you
+	/// add elements to the map while doing this, you'll get undefined results.
+	fn drain() -> Self::Iterator;
+
+	/// Remove at most `limit` elements from the map and return them, in the same order as `drain`.
+	///
+	/// The map is empty when fewer than `limit` elements are returned.
+	fn drain_limited(limit: u32) -> Vec<(K::Key, V)>;
+
+	/// Translate the values of all elements by a function `f`, in the map in no particular order.
+	/// By returning `None` from `f` for an element, you'll remove it from the map.
+	fn translate<O: Decode, F: Fn(K::Key, O) -> Option<V>>(f: F);
+}
+
 /// Iterator for prefixed map.
 pub struct PrefixIterator<Value> {
 	prefix: Vec<u8>,
@@ -529,7 +733,49 @@ pub trait StoragePrefixedMap<Value: FullCodec> {
 mod test {
 	use sp_core::hashing::twox_128;
 	use sp_io::TestExternalities;
-	use crate::storage::{unhashed, StoragePrefixedMap};
+	use crate::storage::{unhashed, StoragePrefixedMap, with_transaction, TransactionOutcome};
+
+	#[test]
+	fn with_transaction_works() {
+		TestExternalities::default().execute_with(|| {
+			unhashed::put(b"kept", &1u32);
+
+			let res = with_transaction(|| {
+				unhashed::put(b"kept", &2u32);
+				unhashed::put(b"outer", &1u32);
+
+				let inner = with_transaction(|| {
+					unhashed::put(b"kept", &3u32);
+					unhashed::put(b"inner", &1u32);
+					TransactionOutcome::Rollback(1u32)
+				});
+				assert_eq!(inner, 1);
+				assert_eq!(unhashed::get(b"kept"), Some(2u32));
+				assert_eq!(unhashed::get::<u32>(b"inner"), None);
+
+				with_transaction(|| {
+					unhashed::put(b"committed", &1u32);
+					TransactionOutcome::Commit(())
+				});
+
+				TransactionOutcome::Commit(2u32)
+			});
+
+			assert_eq!(res, 2);
+			assert_eq!(unhashed::get(b"kept"), Some(2u32));
+			assert_eq!(unhashed::get(b"outer"), Some(1u32));
+			assert_eq!(unhashed::get(b"committed"), Some(1u32));
+
+			with_transaction(|| {
+				unhashed::put(b"kept", &4u32);
+				unhashed::kill(b"outer");
+				TransactionOutcome::Rollback(())
+			});
+
+			assert_eq!(unhashed::get(b"kept"), Some(2u32));
+			assert_eq!(unhashed::get(b"outer"), Some(1u32));
+		});
+	}
 
 	#[test]
 	fn prefixed_map_works() {
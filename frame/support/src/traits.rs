@@ -1129,11 +1129,25 @@ impl<BlockNumber: Clone> OnInitialize<BlockNumber> for Tuple {
 
 /// The runtime upgrade trait. Implementing this lets you express what should happen
 /// when the runtime upgrades, and changes may need to occur to your module.
+///
+/// Upgrades compose as tuples, which run the upgrades and checks of their members in order.
 pub trait OnRuntimeUpgrade {
 	/// Perform a module upgrade.
 	///
 	/// Return the non-negotiable weight consumed for runtime upgrade.
 	fn on_runtime_upgrade() -> crate::weights::Weight { 0 }
+
+	/// Check the state before the upgrade, e.g. that it is at the version the upgrade expects.
+	///
+	/// Only run by offline tooling testing the upgrade, never on chain.
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<(), &'static str> { Ok(()) }
+
+	/// Check the invariants of the state after the upgrade.
+	///
+	/// Only run by offline tooling testing the upgrade, never on chain.
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade() -> Result<(), &'static str> { Ok(()) }
 }
 
 #[impl_for_tuples(30)]
@@ -1143,6 +1157,85 @@ impl OnRuntimeUpgrade for Tuple {
 		for_tuples!( #( weight = weight.saturating_add(Tuple::on_runtime_upgrade()); )* );
 		weight
 	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<(), &'static str> {
+		let mut result = Ok(());
+		for_tuples!( #( result = result.and(Tuple::pre_upgrade()); )* );
+		result
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade() -> Result<(), &'static str> {
+		let mut result = Ok(());
+		for_tuples!( #( result = result.and(Tuple::post_upgrade()); )* );
+		result
+	}
+}
+
+/// The storage key postfix under which the storage version of a module is kept, after the
+/// hashed module prefix.
+pub const STORAGE_VERSION_STORAGE_KEY_POSTFIX: &[u8] = b":__STORAGE_VERSION__:";
+
+/// The version of the storage layout of a module.
+///
+/// A module declares the version its code expects with `storage_version(N)` in `decl_storage!`,
+/// which stores it at genesis. A module upgrading its storage puts the new version on chain once
+/// it is done. Modules that never stored a version are at version 0.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, RuntimeDebug)]
+pub struct StorageVersion(u16);
+
+impl StorageVersion {
+	/// Create a storage version.
+	pub const fn new(version: u16) -> Self {
+		Self(version)
+	}
+
+	/// The storage key of the version of the module with storage prefix `module_prefix`.
+	pub fn storage_key(module_prefix: &[u8]) -> [u8; 32] {
+		use crate::hash::{StorageHasher, Twox128};
+
+		let mut key = [0u8; 32];
+		key[..16].copy_from_slice(&Twox128::hash(module_prefix));
+		key[16..].copy_from_slice(&Twox128::hash(STORAGE_VERSION_STORAGE_KEY_POSTFIX));
+		key
+	}
+
+	/// Store this version as the version of the module with storage prefix `module_prefix`.
+	pub fn put(&self, module_prefix: &[u8]) {
+		crate::storage::unhashed::put(&Self::storage_key(module_prefix), self);
+	}
+
+	/// The version stored for the module with storage prefix `module_prefix`, 0 if none is.
+	pub fn get(module_prefix: &[u8]) -> Self {
+		crate::storage::unhashed::get_or_default(&Self::storage_key(module_prefix))
+	}
+}
+
+impl PartialEq<u16> for StorageVersion {
+	fn eq(&self, other: &u16) -> bool {
+		self.0 == *other
+	}
+}
+
+impl PartialOrd<u16> for StorageVersion {
+	fn partial_cmp(&self, other: &u16) -> Option<sp_std::cmp::Ordering> {
+		Some(self.0.cmp(other))
+	}
+}
+
+/// Access to the storage version of a module, implemented by `decl_storage!`.
+pub trait GetStorageVersion {
+	/// The storage version the code of the module expects.
+	fn current_storage_version() -> StorageVersion;
+
+	/// The storage version on chain.
+	///
+	/// It is behind the current storage version until the module upgraded its storage.
+	fn on_chain_storage_version() -> StorageVersion;
+
+	/// Put `version` on chain, once the storage is upgraded to it.
+	fn put_on_chain_storage_version(version: StorageVersion);
 }
 
 /// Off-chain computation trait.
@@ -0,0 +1,115 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use frame_support::{storage::unhashed, traits::{GetStorageVersion, StorageVersion}};
+use sp_io::{TestExternalities, hashing::twox_128};
+
+mod no_instance {
+	use codec::{Encode, Decode, EncodeLike};
+
+	pub trait Trait {
+		type Origin;
+		type BlockNumber: Encode + Decode + EncodeLike + Default + Clone;
+	}
+
+	frame_support::decl_module! {
+		pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+	}
+
+	frame_support::decl_storage!{
+		trait Store for Module<T: Trait> as VersionNone storage_version(3) {
+			pub Value: u32;
+		}
+	}
+}
+
+mod instance {
+	pub trait Trait<I = DefaultInstance>: super::no_instance::Trait {}
+
+	frame_support::decl_module! {
+		pub struct Module<T: Trait<I>, I: Instantiable = DefaultInstance>
+			for enum Call where origin: T::Origin {}
+	}
+
+	frame_support::decl_storage!{
+		trait Store for Module<T: Trait<I>, I: Instantiable = DefaultInstance>
+			as VersionSome storage_version(5)
+		{
+			pub Value: u32;
+		}
+	}
+}
+
+mod unversioned {
+	use super::no_instance::Trait;
+
+	frame_support::decl_module! {
+		pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+	}
+
+	frame_support::decl_storage!{
+		trait Store for Module<T: Trait> as Unversioned {
+			pub Value: u32;
+		}
+	}
+}
+
+struct Runtime;
+
+impl no_instance::Trait for Runtime {
+	type Origin = u32;
+	type BlockNumber = u32;
+}
+
+impl instance::Trait<instance::Instance2> for Runtime {}
+
+type NoInstance = no_instance::Module<Runtime>;
+type Instance2 = instance::Module<Runtime, instance::Instance2>;
+type Unversioned = unversioned::Module<Runtime>;
+
+#[test]
+fn current_storage_version_is_declared_version() {
+	assert_eq!(NoInstance::current_storage_version(), StorageVersion::new(3));
+	assert_eq!(Instance2::current_storage_version(), StorageVersion::new(5));
+	assert_eq!(Unversioned::current_storage_version(), StorageVersion::new(0));
+}
+
+#[test]
+fn on_chain_storage_version_is_stored_under_module_prefix() {
+	TestExternalities::default().execute_with(|| {
+		assert_eq!(NoInstance::on_chain_storage_version(), StorageVersion::new(0));
+
+		NoInstance::put_on_chain_storage_version(StorageVersion::new(2));
+		assert_eq!(NoInstance::on_chain_storage_version(), StorageVersion::new(2));
+		let k = [twox_128(b"VersionNone"), twox_128(b":__STORAGE_VERSION__:")].concat();
+		assert_eq!(unhashed::get::<StorageVersion>(&k), Some(StorageVersion::new(2)));
+
+		Instance2::put_on_chain_storage_version(StorageVersion::new(4));
+		assert_eq!(Instance2::on_chain_storage_version(), StorageVersion::new(4));
+		let k = [twox_128(b"Instance2VersionSome"), twox_128(b":__STORAGE_VERSION__:")].concat();
+		assert_eq!(unhashed::get::<StorageVersion>(&k), Some(StorageVersion::new(4)));
+
+		assert_eq!(Unversioned::on_chain_storage_version(), StorageVersion::new(0));
+	});
+}
+
+#[test]
+fn genesis_puts_current_storage_version() {
+	let storage = no_instance::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	TestExternalities::new(storage).execute_with(|| {
+		assert_eq!(NoInstance::on_chain_storage_version(), StorageVersion::new(3));
+	});
+}
@@ -0,0 +1,36 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API to test a runtime upgrade offline.
+//!
+//! This API should be implemented by the runtime with its `try-runtime` feature enabled,
+//! and is called by the `try-runtime` command against a snapshot of the chain state.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use frame_support::weights::Weight;
+
+sp_api::decl_runtime_apis! {
+	/// The API to test the runtime upgrade of a runtime.
+	pub trait TryRuntime {
+		/// Execute the runtime upgrade, with the `pre_upgrade` and `post_upgrade` checks of all
+		/// modules.
+		///
+		/// Returns the weight consumed by the upgrade, and the messages of the failed checks.
+		fn on_runtime_upgrade() -> (Weight, Vec<Vec<u8>>);
+	}
+}
@@ -194,6 +194,24 @@ pub trait Externalities: ExtensionStore {
 	/// The returned hash is defined by the `Block` and is SCALE encoded.
 	fn storage_changes_root(&mut self, parent: &[u8]) -> Result<Option<Vec<u8>>, ()>;
 
+	/// Start a new nested storage transaction.
+	///
+	/// This allows to either commit or roll back all changes made after this call to the
+	/// top and child storages.
+	fn storage_start_transaction(&mut self);
+
+	/// Roll back the changes made since the last call to `storage_start_transaction`, and close
+	/// that transaction.
+	///
+	/// Returns an error if there is no open transaction.
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()>;
+
+	/// Keep the changes made since the last call to `storage_start_transaction`, and close that
+	/// transaction.
+	///
+	/// Returns an error if there is no open transaction.
+	fn storage_commit_transaction(&mut self) -> Result<(), ()>;
+
 	/// !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
 	/// Benchmarking related functionality and shouldn't be used anywhere else!
 	/// !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
@@ -39,12 +39,14 @@ use sp_externalities::Extensions;
 pub struct BasicExternalities {
 	inner: Storage,
 	extensions: Extensions,
+	/// The storage as it was when each open transaction started, innermost last.
+	transactions: Vec<Storage>,
 }
 
 impl BasicExternalities {
 	/// Create a new instance of `BasicExternalities`
 	pub fn new(inner: Storage) -> Self {
-		BasicExternalities { inner, extensions: Default::default() }
+		BasicExternalities { inner, extensions: Default::default(), transactions: Vec::new() }
 	}
 
 	/// New basic externalities with empty storage.
@@ -60,6 +62,7 @@ impl BasicExternalities {
 		Self {
 			inner: Storage::default(),
 			extensions,
+			transactions: Vec::new(),
 		}
 	}
 
@@ -86,6 +89,7 @@ impl BasicExternalities {
 				children_default: std::mem::take(&mut storage.children_default),
 			},
 			extensions: Default::default(),
+			transactions: Vec::new(),
 		};
 
 		let r = ext.execute_with(f);
@@ -135,6 +139,7 @@ impl From<BTreeMap<StorageKey, StorageValue>> for BasicExternalities {
 				children_default: Default::default(),
 			},
 			extensions: Default::default(),
+			transactions: Vec::new(),
 		}
 	}
 }
@@ -306,6 +311,19 @@ impl Externalities for BasicExternalities {
 		Ok(None)
 	}
 
+	fn storage_start_transaction(&mut self) {
+		self.transactions.push(self.inner.clone());
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.inner = self.transactions.pop().ok_or(())?;
+		Ok(())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.transactions.pop().map(drop).ok_or(())
+	}
+
 	fn wipe(&mut self) {}
 
 	fn commit(&mut self) {}
@@ -387,6 +405,24 @@ mod tests {
 		assert_eq!(ext.child_storage(child_info, b"doe"), None);
 	}
 
+	#[test]
+	fn nested_transactions_work() {
+		let mut ext = BasicExternalities::default();
+		ext.set_storage(b"doe".to_vec(), b"reindeer".to_vec());
+
+		ext.storage_start_transaction();
+		ext.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+		ext.storage_start_transaction();
+		ext.clear_storage(b"doe");
+		assert_eq!(ext.storage_rollback_transaction(), Ok(()));
+		assert_eq!(ext.storage(b"doe"), Some(b"reindeer".to_vec()));
+		assert_eq!(ext.storage_commit_transaction(), Ok(()));
+		assert_eq!(ext.storage(b"dog"), Some(b"puppy".to_vec()));
+
+		assert_eq!(ext.storage_commit_transaction(), Err(()));
+		assert_eq!(ext.storage_rollback_transaction(), Err(()));
+	}
+
 	#[test]
 	fn basic_externalities_is_empty() {
 		// Make sure no values are set by default in `BasicExternalities`.
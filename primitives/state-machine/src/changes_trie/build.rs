@@ -468,6 +468,7 @@ mod test {
 			},
 			collect_extrinsics: true,
 			stats: Default::default(),
+			transactions: Default::default(),
 		};
 		let config = Configuration { digest_interval: 4, digest_levels: 2 };
 
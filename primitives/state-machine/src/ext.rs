@@ -547,6 +547,19 @@ where
 		root.map(|r| r.map(|o| o.encode()))
 	}
 
+	fn storage_start_transaction(&mut self) {
+		self.overlay.start_transaction();
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.mark_dirty();
+		self.overlay.rollback_transaction().map_err(|_| ())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.overlay.commit_transaction().map_err(|_| ())
+	}
+
 	fn wipe(&mut self) {
 		self.overlay.discard_prospective();
 		self.overlay.drain_storage_changes(&self.backend, None, Default::default(), self.storage_transaction_cache)
@@ -705,6 +718,7 @@ mod tests {
 			committed: Default::default(),
 			collect_extrinsics: true,
 			stats: Default::default(),
+			transactions: Default::default(),
 		}
 	}
 
@@ -65,7 +65,7 @@ pub use changes_trie::{
 };
 pub use overlayed_changes::{
 	OverlayedChanges, StorageChanges, StorageTransactionCache, StorageKey, StorageValue,
-	StorageCollection, ChildStorageCollection,
+	StorageCollection, ChildStorageCollection, NoOpenTransaction,
 };
 pub use proving_backend::{
 	create_proof_check_backend, ProofRecorder, ProvingBackend, ProvingBackendRecorder,
@@ -350,6 +350,7 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 
 		if was_native {
 			self.overlay.prospective = orig_prospective.clone();
+			self.overlay.transactions.clear();
 			let (wasm_result, _) = self.execute_aux(
 				false,
 				native_call,
@@ -386,6 +387,7 @@ impl<'a, B, H, N, Exec> StateMachine<'a, B, H, N, Exec> where
 			result
 		} else {
 			self.overlay.prospective = orig_prospective.clone();
+			self.overlay.transactions.clear();
 			let (wasm_result, _) = self.execute_aux(
 				false,
 				native_call,
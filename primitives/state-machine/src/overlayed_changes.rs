@@ -27,7 +27,7 @@ use crate::{
 
 #[cfg(test)]
 use std::iter::FromIterator;
-use std::collections::{HashMap, BTreeMap, BTreeSet};
+use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet};
 use codec::{Decode, Encode};
 use sp_core::storage::{well_known_keys::EXTRINSIC_INDEX, ChildInfo};
 use sp_core::offchain::storage::OffchainOverlayedChanges;
@@ -51,12 +51,22 @@ pub type ChildStorageCollection = Vec<(StorageKey, StorageCollection)>;
 ///
 /// A transaction shares all prospective changes within an inner overlay
 /// that can be cleared.
+///
+/// The prospective changes can further be split into nested storage transactions, which are
+/// started with [`start_transaction`] and ended with [`commit_transaction`] or
+/// [`rollback_transaction`].
+///
+/// [`start_transaction`]: #method.start_transaction
+/// [`commit_transaction`]: #method.commit_transaction
+/// [`rollback_transaction`]: #method.rollback_transaction
 #[derive(Debug, Default, Clone)]
 pub struct OverlayedChanges {
 	/// Changes that are not yet committed.
 	pub(crate) prospective: OverlayedChangeSet,
 	/// Committed changes.
 	pub(crate) committed: OverlayedChangeSet,
+	/// The open storage transactions, innermost last.
+	pub(crate) transactions: Vec<TransactionJournal>,
 	/// True if extrinsics stats must be collected.
 	pub(crate) collect_extrinsics: bool,
 	/// Collect statistic on this execution.
@@ -84,6 +94,23 @@ pub struct OverlayedChangeSet {
 	pub children_default: HashMap<StorageKey, (BTreeMap<StorageKey, OverlayedValue>, ChildInfo)>,
 }
 
+/// The prospective values a storage transaction overwrote, to restore them on rollback.
+///
+/// Only the first change of each key within the transaction is recorded.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TransactionJournal {
+	/// Top level values before the transaction, `None` if they were not in the overlay.
+	top: BTreeMap<StorageKey, Option<OverlayedValue>>,
+	/// Child values before the transaction, by child storage key.
+	children: HashMap<StorageKey, BTreeMap<StorageKey, Option<OverlayedValue>>>,
+	/// Child change sets that did not exist before the transaction.
+	new_children: HashSet<StorageKey>,
+}
+
+/// Error returned when committing or rolling back while no storage transaction is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoOpenTransaction;
+
 /// A storage changes structure that can be generated by the data collected in [`OverlayedChanges`].
 ///
 /// This contains all the changes to the storage and transactions to apply theses changes to the
@@ -234,6 +261,7 @@ impl OverlayedChanges {
 		init: impl Fn() -> StorageValue,
 	) -> &mut StorageValue {
 		let extrinsic_index = self.extrinsic_index();
+		self.journal_top(key);
 		let committed = &self.committed.top;
 
 		let mut entry = self.prospective.top.entry(key.to_vec())
@@ -286,6 +314,7 @@ impl OverlayedChanges {
 		let size_write = val.as_ref().map(|x| x.len() as u64).unwrap_or(0);
 		self.stats.tally_write_overlay(size_write);
 		let extrinsic_index = self.extrinsic_index();
+		self.journal_top(&key);
 		let entry = self.prospective.top.entry(key).or_default();
 		entry.value = val;
 
@@ -307,6 +336,7 @@ impl OverlayedChanges {
 		let size_write = val.as_ref().map(|x| x.len() as u64).unwrap_or(0);
 		self.stats.tally_write_overlay(size_write);
 		let extrinsic_index = self.extrinsic_index();
+		self.journal_child(child_info.storage_key(), &key);
 		let storage_key = child_info.storage_key().to_vec();
 		let map_entry = self.prospective.children_default.entry(storage_key)
 			.or_insert_with(|| (Default::default(), child_info.to_owned()));
@@ -334,6 +364,7 @@ impl OverlayedChanges {
 	) {
 		let extrinsic_index = self.extrinsic_index();
 		let storage_key = child_info.storage_key();
+		self.journal_child_keys(storage_key, |_| true);
 		let map_entry = self.prospective.children_default.entry(storage_key.to_vec())
 			.or_insert_with(|| (Default::default(), child_info.to_owned()));
 		let updatable = map_entry.1.try_update(child_info);
@@ -373,6 +404,16 @@ impl OverlayedChanges {
 	/// [`discard_prospective`]: #method.discard_prospective
 	pub(crate) fn clear_prefix(&mut self, prefix: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
+		if !self.transactions.is_empty() {
+			let keys: Vec<StorageKey> = self.prospective.top.keys()
+				.chain(self.committed.top.keys())
+				.filter(|key| key.starts_with(prefix))
+				.cloned()
+				.collect();
+			for key in keys {
+				self.journal_top(&key);
+			}
+		}
 
 		// Iterate over all prospective and mark all keys that share
 		// the given prefix as removed (None).
@@ -409,6 +450,7 @@ impl OverlayedChanges {
 	) {
 		let extrinsic_index = self.extrinsic_index();
 		let storage_key = child_info.storage_key();
+		self.journal_child_keys(storage_key, |key| key.starts_with(prefix));
 		let map_entry = self.prospective.children_default.entry(storage_key.to_vec())
 			.or_insert_with(|| (Default::default(), child_info.to_owned()));
 		let updatable = map_entry.1.try_update(child_info);
@@ -442,13 +484,138 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Record the prospective value of `key` in the innermost transaction, if this is its first
+	/// change in the transaction.
+	fn journal_top(&mut self, key: &[u8]) {
+		if let Some(journal) = self.transactions.last_mut() {
+			if !journal.top.contains_key(key) {
+				journal.top.insert(key.to_vec(), self.prospective.top.get(key).cloned());
+			}
+		}
+	}
+
+	/// Record the prospective value of `key` in the child storage `storage_key` in the innermost
+	/// transaction, if this is its first change in the transaction.
+	fn journal_child(&mut self, storage_key: &[u8], key: &[u8]) {
+		if let Some(journal) = self.transactions.last_mut() {
+			if journal.new_children.contains(storage_key) {
+				return;
+			}
+			match self.prospective.children_default.get(storage_key) {
+				Some((map, _)) => {
+					journal.children.entry(storage_key.to_vec())
+						.or_default()
+						.entry(key.to_vec())
+						.or_insert_with(|| map.get(key).cloned());
+				},
+				None => {
+					journal.new_children.insert(storage_key.to_vec());
+				},
+			}
+		}
+	}
+
+	/// Record the prospective values of the keys of the child storage `storage_key` matching
+	/// `filter`, in the prospective or committed changes, in the innermost transaction.
+	fn journal_child_keys(&mut self, storage_key: &[u8], filter: impl Fn(&[u8]) -> bool) {
+		let journal = match self.transactions.last_mut() {
+			Some(journal) => journal,
+			None => return,
+		};
+		if !self.prospective.children_default.contains_key(storage_key) {
+			// A child change set that is new to the transaction is removed on rollback.
+			journal.new_children.insert(storage_key.to_vec());
+			return;
+		}
+		let keys: Vec<StorageKey> = self.prospective.children_default.get(storage_key)
+			.into_iter()
+			.chain(self.committed.children_default.get(storage_key))
+			.flat_map(|(map, _)| map.keys())
+			.filter(|key| filter(key))
+			.cloned()
+			.collect();
+		for key in keys {
+			self.journal_child(storage_key, &key);
+		}
+	}
+
+	/// The number of open storage transactions.
+	pub fn transaction_depth(&self) -> usize {
+		self.transactions.len()
+	}
+
+	/// Start a new storage transaction, nested in the open ones.
+	///
+	/// The prospective changes made until the transaction is ended can be rolled back with
+	/// [`rollback_transaction`](#method.rollback_transaction), or kept with
+	/// [`commit_transaction`](#method.commit_transaction).
+	pub fn start_transaction(&mut self) {
+		self.transactions.push(Default::default());
+	}
+
+	/// Discard the prospective changes made since the innermost open transaction started, and
+	/// close it.
+	pub fn rollback_transaction(&mut self) -> Result<(), NoOpenTransaction> {
+		let journal = self.transactions.pop().ok_or(NoOpenTransaction)?;
+
+		for (key, value) in journal.top {
+			match value {
+				Some(value) => { self.prospective.top.insert(key, value); },
+				None => { self.prospective.top.remove(&key); },
+			}
+		}
+		for storage_key in &journal.new_children {
+			self.prospective.children_default.remove(storage_key);
+		}
+		for (storage_key, values) in journal.children {
+			if let Some((map, _)) = self.prospective.children_default.get_mut(&storage_key) {
+				for (key, value) in values {
+					match value {
+						Some(value) => { map.insert(key, value); },
+						None => { map.remove(&key); },
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Keep the prospective changes made since the innermost open transaction started, and close
+	/// it. They are rolled back with the enclosing transaction, if any.
+	pub fn commit_transaction(&mut self) -> Result<(), NoOpenTransaction> {
+		let journal = self.transactions.pop().ok_or(NoOpenTransaction)?;
+
+		if let Some(parent) = self.transactions.last_mut() {
+			for (key, value) in journal.top {
+				parent.top.entry(key).or_insert(value);
+			}
+			for (storage_key, values) in journal.children {
+				if parent.new_children.contains(&storage_key) {
+					continue;
+				}
+				let parent_values = parent.children.entry(storage_key).or_default();
+				for (key, value) in values {
+					parent_values.entry(key).or_insert(value);
+				}
+			}
+			parent.new_children.extend(journal.new_children);
+		}
+		Ok(())
+	}
+
 	/// Discard prospective changes to state.
+	///
+	/// Any open storage transaction is closed.
 	pub fn discard_prospective(&mut self) {
 		self.prospective.clear();
+		self.transactions.clear();
 	}
 
 	/// Commit prospective changes to state.
+	///
+	/// Any open storage transaction is closed, keeping its changes.
 	pub fn commit_prospective(&mut self) {
+		self.transactions.clear();
 		if self.committed.is_empty() {
 			mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
@@ -946,4 +1113,71 @@ mod tests {
 		assert_eq!(next_to_40.0.to_vec(), vec![50]);
 		assert_eq!(next_to_40.1.value, Some(vec![50]));
 	}
+
+	#[test]
+	fn transaction_rollback_restores_prospective_changes() {
+		let child_info = ChildInfo::new_default(b"Child1");
+		let child_info = &child_info;
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(vec![1], Some(vec![1]));
+		overlay.set_storage(vec![2], Some(vec![2]));
+		overlay.commit_prospective();
+		overlay.set_storage(vec![3], Some(vec![3]));
+
+		overlay.start_transaction();
+		overlay.set_storage(vec![1], Some(vec![10]));
+		overlay.set_storage(vec![3], None);
+		overlay.set_storage(vec![4], Some(vec![4]));
+		overlay.clear_prefix(&[2]);
+		overlay.set_child_storage(child_info, vec![5], Some(vec![5]));
+		assert_eq!(overlay.transaction_depth(), 1);
+
+		assert_eq!(overlay.rollback_transaction(), Ok(()));
+		assert_eq!(overlay.transaction_depth(), 0);
+		assert_eq!(overlay.storage(&[1]).unwrap(), Some(&[1][..]));
+		assert_eq!(overlay.storage(&[2]).unwrap(), Some(&[2][..]));
+		assert_eq!(overlay.storage(&[3]).unwrap(), Some(&[3][..]));
+		assert_eq!(overlay.storage(&[4]), None);
+		assert_eq!(overlay.child_storage(child_info, &[5]), None);
+		assert!(overlay.prospective.children_default.is_empty());
+	}
+
+	#[test]
+	fn nested_transactions_commit_into_the_enclosing_one() {
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(vec![1], Some(vec![1]));
+
+		overlay.start_transaction();
+		overlay.set_storage(vec![1], Some(vec![2]));
+		overlay.start_transaction();
+		overlay.set_storage(vec![1], Some(vec![3]));
+		overlay.set_storage(vec![2], Some(vec![3]));
+		overlay.start_transaction();
+		overlay.set_storage(vec![1], Some(vec![4]));
+		assert_eq!(overlay.rollback_transaction(), Ok(()));
+		assert_eq!(overlay.storage(&[1]).unwrap(), Some(&[3][..]));
+
+		assert_eq!(overlay.commit_transaction(), Ok(()));
+		assert_eq!(overlay.storage(&[1]).unwrap(), Some(&[3][..]));
+		assert_eq!(overlay.storage(&[2]).unwrap(), Some(&[3][..]));
+
+		assert_eq!(overlay.rollback_transaction(), Ok(()));
+		assert_eq!(overlay.storage(&[1]).unwrap(), Some(&[1][..]));
+		assert_eq!(overlay.storage(&[2]), None);
+
+		assert_eq!(overlay.commit_transaction(), Err(NoOpenTransaction));
+		assert_eq!(overlay.rollback_transaction(), Err(NoOpenTransaction));
+	}
+
+	#[test]
+	fn committing_prospective_closes_transactions() {
+		let mut overlay = OverlayedChanges::default();
+		overlay.start_transaction();
+		overlay.set_storage(vec![1], Some(vec![1]));
+		overlay.commit_prospective();
+
+		assert_eq!(overlay.transaction_depth(), 0);
+		assert_eq!(overlay.rollback_transaction(), Err(NoOpenTransaction));
+		assert_eq!(overlay.storage(&[1]).unwrap(), Some(&[1][..]));
+	}
 }
@@ -0,0 +1,104 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::TryRuntimeCmd;
+use codec::Decode;
+use frame_support::weights::Weight;
+use sc_cli::{SharedParams, CliConfiguration, Result};
+use sc_client_db::BenchmarkingState;
+use sc_executor::NativeExecutor;
+use sp_state_machine::{StateMachine, ExecutionStrategy};
+use sp_externalities::Extensions;
+use sc_service::{Configuration, NativeExecutionDispatch};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use sp_core::{tasks, storage::well_known_keys};
+use std::fmt::Debug;
+
+impl TryRuntimeCmd {
+	/// Runs the command, upgrading the state of the chain to the runtime given with `--runtime`,
+	/// or to `default_code` if none is.
+	pub fn run<BB, ExecDispatch>(&self, config: Configuration, default_code: &[u8]) -> Result<()>
+	where
+		BB: BlockT + Debug,
+		ExecDispatch: NativeExecutionDispatch + 'static,
+	{
+		let spec = config.chain_spec;
+		let wasm_method = self.wasm_method.into();
+
+		let code = match self.runtime {
+			Some(ref path) => std::fs::read(path)
+				.map_err(|e| format!("Failed to read runtime {}: {:?}", path.display(), e))?,
+			None => default_code.to_vec(),
+		};
+
+		let mut storage = spec.build_storage()?;
+		storage.top.insert(well_known_keys::CODE.to_vec(), code);
+
+		let mut changes = Default::default();
+		let mut offchain_changes = Default::default();
+		let cache_size = Some(self.database_cache_size as usize);
+		let state = BenchmarkingState::<BB>::new(storage, cache_size)?;
+		let executor = NativeExecutor::<ExecDispatch>::new(
+			wasm_method,
+			None, // heap pages
+			2, // The runtime instances cache size.
+		);
+
+		// The native runtime of this node may not be the one upgraded to, so always use Wasm.
+		let result = StateMachine::<_, _, NumberFor<BB>, _>::new(
+			&state,
+			None,
+			&mut changes,
+			&mut offchain_changes,
+			&executor,
+			"TryRuntime_on_runtime_upgrade",
+			&[],
+			Extensions::default(),
+			&sp_state_machine::backend::BackendRuntimeCode::new(&state).runtime_code()?,
+			tasks::executor(),
+		)
+		.execute(ExecutionStrategy::AlwaysWasm)
+		.map_err(|e| format!("Error executing runtime upgrade: {:?}", e))?;
+
+		let (weight, failures) = <(Weight, Vec<Vec<u8>>) as Decode>::decode(&mut &result[..])
+			.map_err(|e| format!("Failed to decode runtime upgrade result: {:?}", e))?;
+
+		println!("Weight used by the runtime upgrade: {}", weight);
+
+		if failures.is_empty() {
+			println!("All upgrade checks passed.");
+			Ok(())
+		} else {
+			for failure in failures.iter() {
+				eprintln!("Upgrade check failed: {}", String::from_utf8_lossy(failure));
+			}
+			Err(format!("{} upgrade check(s) failed", failures.len()).into())
+		}
+	}
+}
+
+impl CliConfiguration for TryRuntimeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn chain_id(&self, _is_dev: bool) -> Result<String> {
+		match self.shared_params.chain {
+			Some(ref chain) => Ok(chain.clone()),
+			None => Err("A state snapshot exported with `export-state` must be given with `--chain`".into()),
+		}
+	}
+}
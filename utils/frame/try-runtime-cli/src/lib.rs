@@ -0,0 +1,55 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The `try-runtime` command, testing the runtime upgrade of a new runtime offline.
+//!
+//! The state of the chain is taken from a chain spec produced by the `export-state` command.
+//! The code of the new runtime replaces the one of this state, then the upgrade of all modules
+//! is executed with their `pre_upgrade` and `post_upgrade` checks. The runtime must implement
+//! the `TryRuntime` API of the `frame-try-runtime` crate.
+
+mod command;
+
+use sc_cli::WasmExecutionMethod;
+use std::{fmt::Debug, path::PathBuf};
+
+/// The `try-runtime` command used to test a runtime upgrade against a state snapshot.
+#[derive(Debug, structopt::StructOpt, Clone)]
+pub struct TryRuntimeCmd {
+	/// The Wasm blob of the runtime to upgrade to.
+	///
+	/// Defaults to the runtime of this node.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub runtime: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	/// Method for executing Wasm runtime code.
+	#[structopt(
+		long = "wasm-execution",
+		value_name = "METHOD",
+		possible_values = &WasmExecutionMethod::enabled_variants(),
+		case_insensitive = true,
+		default_value = "Interpreted"
+	)]
+	pub wasm_method: WasmExecutionMethod,
+
+	/// Limit the memory the database cache can use.
+	#[structopt(long = "db-cache", value_name = "MiB", default_value = "128")]
+	pub database_cache_size: u32,
+}